@@ -0,0 +1,334 @@
+use crate::document::{Document, NodeId};
+use crate::error::AppError;
+use crate::inventory::{self, InventoryOwner};
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Ability {
+    Strength,
+    Dexterity,
+    Constitution,
+    Intelligence,
+    Wisdom,
+    Charisma,
+}
+
+/// How a stored value relates to the ability score
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScoreRole {
+    /// The score chosen at character creation and level-ups
+    Base,
+    /// Base plus bonuses from gear and effects
+    Current,
+    /// (score - 10) / 2, rounded down
+    Modifier,
+}
+
+/// One stored value of an ability score
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AbilityField {
+    pub resource: String,
+    /// Node holding the attribute
+    pub node: NodeId,
+    pub attribute: String,
+    pub ability: Ability,
+    pub role: ScoreRole,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AbilityScore {
+    pub ability: Ability,
+    pub base: Option<i64>,
+    pub current: Option<i64>,
+}
+
+/// Ability scores of a character, read from its first node that has them
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CharacterAbilities {
+    pub character: InventoryOwner,
+    pub scores: Vec<AbilityScore>,
+    /// Every field found across Globals and the levels
+    pub fields: Vec<AbilityField>,
+}
+
+/// A new base score for one ability of a character (by owner key)
+#[derive(Debug, serde::Deserialize)]
+pub struct AbilityEdit {
+    pub character: String,
+    pub ability: Ability,
+    pub score: i64,
+}
+
+/// Bounds the game enforces on ability scores
+pub const MIN_SCORE: i64 = 1;
+pub const MAX_SCORE: i64 = 30;
+
+// ============================================================================
+// Known Names
+// ============================================================================
+
+impl Ability {
+    /// In the order the game stores them
+    pub const ALL: [Ability; 6] = [
+        Ability::Strength,
+        Ability::Dexterity,
+        Ability::Constitution,
+        Ability::Intelligence,
+        Ability::Wisdom,
+        Ability::Charisma,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Ability::Strength => "Strength",
+            Ability::Dexterity => "Dexterity",
+            Ability::Constitution => "Constitution",
+            Ability::Intelligence => "Intelligence",
+            Ability::Wisdom => "Wisdom",
+            Ability::Charisma => "Charisma",
+        }
+    }
+
+    /// Which role an attribute id plays for this ability, if any; only the
+    /// exact LSLib ids match, so `Int` or `IntModifier` are left alone
+    fn role_of(self, id: &str) -> Option<ScoreRole> {
+        let name = self.name();
+        if id == name {
+            Some(ScoreRole::Current)
+        } else if id == format!("Base{}", name) || id == format!("{}Base", name) {
+            Some(ScoreRole::Base)
+        } else if id == format!("{}Modifier", name) {
+            Some(ScoreRole::Modifier)
+        } else {
+            None
+        }
+    }
+}
+
+/// Nodes listing the scores in order, one child per ability
+const ARRAY_NODES: [(&str, ScoreRole); 2] = [
+    ("BaseAbilities", ScoreRole::Base),
+    ("Abilities", ScoreRole::Current),
+];
+
+// ============================================================================
+// Locating Scores
+// ============================================================================
+
+/// Ability score fields of a character node: named attributes (Strength,
+/// BaseStrength, StrengthModifier, ...) on the character or nodes below it,
+/// and Abilities/BaseAbilities lists. Lists with a leading "None" entry (7
+/// children) skip it.
+pub fn ability_fields(doc: &Document, character: NodeId, resource: &str) -> Vec<AbilityField> {
+    let mut fields = Vec::new();
+    let mut push = |node: NodeId, attribute: &str, ability: Ability, role: ScoreRole, value: i64| {
+        fields.push(AbilityField {
+            resource: resource.to_string(),
+            node,
+            attribute: attribute.to_string(),
+            ability,
+            role,
+            value,
+        });
+    };
+
+    for node in inventory::own_nodes(doc, character) {
+        let name = doc.node(node).name.as_str();
+        if let Some(&(_, role)) = ARRAY_NODES.iter().find(|(array, _)| *array == name) {
+            let entries = doc.children(node);
+            let skip = entries.len().saturating_sub(Ability::ALL.len());
+            for (&entry, ability) in entries.iter().skip(skip).zip(Ability::ALL) {
+                let value = doc.node(entry).attributes.iter()
+                    .find_map(|a| Some((a, a.value.as_int()?)));
+                if let Some((attribute, value)) = value {
+                    push(entry, &attribute.id, ability, role, value);
+                }
+            }
+            continue;
+        }
+
+        for attribute in &doc.node(node).attributes {
+            let Some(value) = attribute.value.as_int() else {
+                continue;
+            };
+            for ability in Ability::ALL {
+                if let Some(role) = ability.role_of(&attribute.id) {
+                    push(node, &attribute.id, ability, role, value);
+                }
+            }
+        }
+    }
+    fields
+}
+
+/// Scores per ability, each taken from the first resource that stores it
+pub fn summarize(character: InventoryOwner, fields: Vec<AbilityField>) -> CharacterAbilities {
+    let first = |ability: Ability, role: ScoreRole| fields.iter()
+        .find(|f| f.ability == ability && f.role == role)
+        .map(|f| f.value);
+    let scores = Ability::ALL.iter()
+        .map(|&ability| AbilityScore {
+            ability,
+            base: first(ability, ScoreRole::Base),
+            current: first(ability, ScoreRole::Current),
+        })
+        .collect();
+    CharacterAbilities { character, scores, fields }
+}
+
+// ============================================================================
+// Score Modification
+// ============================================================================
+
+/// The new value of every field of the edited ability. The base score is set
+/// as given; the current score keeps its bonus over the base in the same
+/// resource, and modifiers follow the current score. Fails if the base or
+/// the resulting current score leaves the game's 1-30 bounds.
+pub fn plan_ability_edit(fields: &[AbilityField], edit: &AbilityEdit) -> Result<Vec<(AbilityField, i64)>, AppError> {
+    check_score(edit.ability, edit.score, "")?;
+    let ability_fields: Vec<&AbilityField> = fields.iter().filter(|f| f.ability == edit.ability).collect();
+    if ability_fields.is_empty() {
+        return Err(AppError::not_found(format!(
+            "No {} score found on {}", edit.ability.name(), edit.character
+        )));
+    }
+
+    let in_resource = |resource: &str, role: ScoreRole| ability_fields.iter()
+        .find(|f| f.resource == resource && f.role == role)
+        .map(|f| f.value);
+
+    let mut changes = Vec::new();
+    for field in &ability_fields {
+        let bonus = match in_resource(&field.resource, ScoreRole::Base) {
+            Some(base) => in_resource(&field.resource, ScoreRole::Current).map_or(0, |current| current - base),
+            None => 0,
+        };
+        let current = edit.score + bonus;
+        let value = match field.role {
+            ScoreRole::Base => edit.score,
+            ScoreRole::Current => {
+                check_score(edit.ability, current, &format!(" with its {:+} bonus", bonus))?;
+                current
+            }
+            ScoreRole::Modifier => modifier(current),
+        };
+        changes.push(((*field).clone(), value));
+    }
+    Ok(changes)
+}
+
+fn check_score(ability: Ability, score: i64, context: &str) -> Result<(), AppError> {
+    if (MIN_SCORE..=MAX_SCORE).contains(&score) {
+        Ok(())
+    } else {
+        Err(AppError::validation(format!(
+            "{} would be {}{}; the game allows {} to {}",
+            ability.name(), score, context, MIN_SCORE, MAX_SCORE
+        )))
+    }
+}
+
+/// Ability modifier for a score, rounded down (8 → -1, 15 → +2)
+pub fn modifier(score: i64) -> i64 {
+    (score - 10).div_euclid(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsx;
+
+    /// A character with named score attributes and a BaseAbilities list with a leading "None" entry
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<save>
+	<version major="4" minor="0" revision="9" build="331" />
+	<region id="Characters">
+		<node id="Characters">
+			<children>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="aaaaaaaa-0000-0000-0000-000000000001" />
+					<attribute id="BaseStrength" type="int32" value="15" />
+					<attribute id="Strength" type="int32" value="17" />
+					<attribute id="StrengthModifier" type="int32" value="3" />
+					<attribute id="Str" type="int32" value="99" />
+					<attribute id="IntModifier" type="int32" value="99" />
+					<children>
+						<node id="BaseAbilities">
+							<children>
+								<node id="Ability"><attribute id="Object" type="int32" value="0" /></node>
+								<node id="Ability"><attribute id="Object" type="int32" value="15" /></node>
+								<node id="Ability"><attribute id="Object" type="int32" value="14" /></node>
+								<node id="Ability"><attribute id="Object" type="int32" value="13" /></node>
+								<node id="Ability"><attribute id="Object" type="int32" value="12" /></node>
+								<node id="Ability"><attribute id="Object" type="int32" value="10" /></node>
+								<node id="Ability"><attribute id="Object" type="int32" value="8" /></node>
+							</children>
+						</node>
+					</children>
+				</node>
+			</children>
+		</node>
+	</region>
+</save>"#;
+
+    fn fields() -> Vec<AbilityField> {
+        let doc = lsx::read(SAMPLE).unwrap();
+        ability_fields(&doc, doc.find_nodes("Character")[0], "Globals.lsf")
+    }
+
+    fn edit(ability: Ability, score: i64) -> AbilityEdit {
+        AbilityEdit { character: "Tav".to_string(), ability, score }
+    }
+
+    #[test]
+    fn only_exact_ids_are_scores() {
+        let named: Vec<String> = fields().into_iter()
+            .filter(|f| f.attribute != "Object")
+            .map(|f| f.attribute)
+            .collect();
+        assert_eq!(named, vec!["BaseStrength", "Strength", "StrengthModifier"]);
+        assert_eq!(Ability::Strength.role_of("Str"), None);
+        assert_eq!(Ability::Intelligence.role_of("IntModifier"), None);
+        assert_eq!(Ability::Strength.role_of("strength"), None);
+        assert_eq!(Ability::Strength.role_of("StrengthBase"), Some(ScoreRole::Base));
+    }
+
+    #[test]
+    fn lists_skip_a_leading_none_entry() {
+        let listed: Vec<(Ability, i64)> = fields().into_iter()
+            .filter(|f| f.attribute == "Object")
+            .map(|f| (f.ability, f.value))
+            .collect();
+        assert_eq!(listed, vec![
+            (Ability::Strength, 15),
+            (Ability::Dexterity, 14),
+            (Ability::Constitution, 13),
+            (Ability::Intelligence, 12),
+            (Ability::Wisdom, 10),
+            (Ability::Charisma, 8),
+        ]);
+    }
+
+    #[test]
+    fn the_current_score_keeps_its_bonus() {
+        let changes = plan_ability_edit(&fields(), &edit(Ability::Strength, 20)).unwrap();
+        let values: Vec<(&str, i64)> = changes.iter().map(|(f, v)| (f.attribute.as_str(), *v)).collect();
+        assert_eq!(values, vec![("BaseStrength", 20), ("Strength", 22), ("StrengthModifier", 6), ("Object", 20)]);
+        assert_eq!(modifier(8), -1);
+        assert_eq!(modifier(15), 2);
+    }
+
+    #[test]
+    fn scores_stay_within_bounds() {
+        assert!(plan_ability_edit(&fields(), &edit(Ability::Strength, 0)).is_err());
+        assert!(plan_ability_edit(&fields(), &edit(Ability::Strength, 31)).is_err());
+        // 29 with the +2 bonus would be 31
+        assert!(plan_ability_edit(&fields(), &edit(Ability::Strength, 29)).is_err());
+        assert!(plan_ability_edit(&fields(), &edit(Ability::Charisma, 30)).is_ok());
+    }
+}
@@ -1,145 +1,209 @@
-use std::process::Command;
+use std::process::{Command, Output};
 use std::env;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use regex::Regex;
+use crate::error::AppError;
 
 // ============================================================================
 // Path Resolution
 // ============================================================================
 
-/// Get the path to Divine.exe from tools/lslib
-pub fn get_divine_path() -> Result<String, String> {
+/// Environment variable that can point at Divine.exe / Divine.dll
+const DIVINE_PATH_ENV: &str = "LSLIB_DIVINE_PATH";
+
+/// Relative folders searched for Divine when no path is configured
+const DIVINE_SEARCH_DIRS: [&str; 3] = [
+    "../tools/lslib/Packed/Tools",
+    "tools/lslib/Packed/Tools",
+    "../../tools/lslib/Packed/Tools",
+];
+
+/// Path chosen by the user, takes precedence over the environment and search
+static CONFIGURED_DIVINE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Use a specific Divine.exe / Divine.dll; `None` goes back to searching
+pub fn configure_divine_path(path: Option<&str>) -> Result<(), AppError> {
+    let path = match path.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(p) if !Path::new(p).is_file() => {
+            return Err(AppError::FileNotFound { path: p.to_string() });
+        }
+        Some(p) => Some(PathBuf::from(p)),
+        None => None,
+    };
+    *CONFIGURED_DIVINE_PATH.lock().unwrap_or_else(|e| e.into_inner()) = path;
+    Ok(())
+}
+
+/// Resolve Divine from the configured path, LSLIB_DIVINE_PATH, or tools/lslib
+pub fn get_divine_path() -> Result<PathBuf, AppError> {
+    let configured = CONFIGURED_DIVINE_PATH.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(path) = configured {
+        return Ok(path);
+    }
+
+    if let Some(path) = env::var_os(DIVINE_PATH_ENV) {
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            return Err(AppError::ToolMissing {
+                message: format!("{} points to {}, which does not exist", DIVINE_PATH_ENV, path.display()),
+            });
+        }
+        return Ok(path);
+    }
+
     let current_dir = env::current_dir()
-        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        .map_err(|e| AppError::Io { path: None, message: format!("Failed to get current directory: {}", e) })?;
     
     println!("Current directory: {:?}", current_dir);
     
     let divine_path = find_divine_path(&current_dir)
-        .ok_or_else(|| "Divine.exe not found. Please ensure LSLib is installed in tools/lslib/Packed/Tools/".to_string())?;
+        .ok_or_else(|| AppError::ToolMissing {
+            message: "Divine not found. Please ensure LSLib is installed in tools/lslib/Packed/Tools/ or select Divine.exe / Divine.dll".to_string(),
+        })?;
     
-    println!("Found Divine.exe at: {:?}", divine_path);
-    Ok(divine_path.to_string_lossy().to_string())
+    println!("Found Divine at: {:?}", divine_path);
+    Ok(divine_path)
 }
 
-/// Search for Divine.exe in common relative locations
+/// Search for Divine in common relative locations, preferring the build
+/// that runs natively on this platform
 fn find_divine_path(base_dir: &Path) -> Option<PathBuf> {
-    let possible_paths = vec![
-        base_dir.join("../tools/lslib/Packed/Tools/Divine.exe"),
-        base_dir.join("tools/lslib/Packed/Tools/Divine.exe"),
-        base_dir.join("../../tools/lslib/Packed/Tools/Divine.exe"),
-    ];
+    let names: &[&str] = if cfg!(windows) {
+        &["Divine.exe", "Divine.dll"]
+    } else {
+        &["Divine.dll", "Divine.exe"]
+    };
     
-    for path in possible_paths {
-        if path.exists() {
-            return path.canonicalize().ok();
+    for dir in DIVINE_SEARCH_DIRS {
+        for name in names {
+            let path = base_dir.join(dir).join(name);
+            if path.exists() {
+                return path.canonicalize().ok();
+            }
         }
     }
     None
 }
 
 // ============================================================================
-// Divine.exe Command Execution
+// Divine Runner
 // ============================================================================
 
-/// Execute a Divine.exe command with given arguments
-fn execute_divine_command(args: Vec<&str>) -> Result<(), String> {
-    let divine_exe = get_divine_path()?;
-    
-    let output = Command::new(&divine_exe)
-        .args(args)
-        .output()
-        .map_err(|e| format!("Failed to execute Divine.exe: {}. Ensure LSLib is installed correctly.", e))?;
-    
-    log_divine_output(&output);
-    
-    if !output.status.success() {
-        let error_msg = extract_error_message(&output);
-        return Err(format!("Divine operation failed: {}", error_msg));
-    }
-    
-    Ok(())
+/// How Divine is started on this platform
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DivineLauncher {
+    /// Run the executable directly
+    Native,
+    /// `dotnet Divine.dll`
+    Dotnet,
+    /// `wine Divine.exe`
+    Wine,
 }
 
-/// Log Divine.exe standard output and error
-fn log_divine_output(output: &std::process::Output) {
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    
-    if !stdout.is_empty() {
-        println!("Divine stdout: {}", stdout);
+/// LSLib status reported to the frontend
+#[derive(Debug, serde::Serialize)]
+pub struct LslibStatus {
+    pub path: String,
+    pub launcher: DivineLauncher,
+    pub version: Option<String>,
+}
+
+/// A located Divine build and the way to launch it
+#[derive(Debug, Clone)]
+pub struct DivineRunner {
+    pub path: PathBuf,
+    pub launcher: DivineLauncher,
+}
+
+impl DivineRunner {
+    /// Locate Divine and pick a launcher for it
+    pub fn locate() -> Result<Self, AppError> {
+        Ok(Self::for_path(get_divine_path()?))
     }
-    if !stderr.is_empty() {
-        println!("Divine stderr: {}", stderr);
+
+    /// `Divine.dll` always goes through dotnet. Off Windows, `Divine.exe`
+    /// uses a sibling `Divine.dll` via dotnet when there is one, else wine.
+    pub fn for_path(path: PathBuf) -> Self {
+        let extension = path.extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("dll") => DivineRunner { path, launcher: DivineLauncher::Dotnet },
+            Some("exe") if !cfg!(windows) => {
+                let dll = path.with_extension("dll");
+                if dll.is_file() {
+                    DivineRunner { path: dll, launcher: DivineLauncher::Dotnet }
+                } else {
+                    DivineRunner { path, launcher: DivineLauncher::Wine }
+                }
+            }
+            _ => DivineRunner { path, launcher: DivineLauncher::Native },
+        }
     }
-}
 
-/// Extract meaningful error message from Divine output
-fn extract_error_message(output: &std::process::Output) -> String {
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    
-    if !stderr.is_empty() {
-        stderr.to_string()
-    } else if !stdout.is_empty() {
-        stdout.to_string()
-    } else {
-        format!("Exit code: {:?}", output.status.code())
+    /// Program that is actually started for this launcher
+    fn program(&self) -> &str {
+        match self.launcher {
+            DivineLauncher::Native => "Divine",
+            DivineLauncher::Dotnet => "dotnet",
+            DivineLauncher::Wine => "wine",
+        }
     }
-}
 
-// ============================================================================
-// Public API Functions
-// ============================================================================
+    fn command(&self) -> Command {
+        let mut command = match self.launcher {
+            DivineLauncher::Native => Command::new(&self.path),
+            DivineLauncher::Dotnet | DivineLauncher::Wine => {
+                let mut command = Command::new(self.program());
+                command.arg(&self.path);
+                command
+            }
+        };
+        if let Some(dir) = self.path.parent() {
+            command.current_dir(dir);
+        }
+        command
+    }
 
-pub fn extract_save(pkg_path: &str, output_path: &str) -> Result<(), String> {
-    validate_input_file(pkg_path)?;
-    
-    println!("Extracting {} to {}", pkg_path, output_path);
-    
-    execute_divine_command(vec![
-        "-g", "bg3",
-        "-a", "extract-package",
-        "-s", pkg_path,
-        "-d", output_path,
-    ])
-}
+    /// Run Divine and capture its output, whatever the exit code
+    fn output(&self, args: &[&str]) -> Result<Output, AppError> {
+        self.command().args(args).output().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::ToolMissing {
+                message: format!("{} is required to run {} but was not found", self.program(), self.path.display()),
+            },
+            _ => AppError::ToolFailed {
+                tool: "Divine".to_string(),
+                exit_code: None,
+                stderr: format!("Failed to execute {}: {}", self.path.display(), e),
+            },
+        })
+    }
 
-pub fn convert_lsf_to_lsx(lsf_path: &str, lsx_path: &str) -> Result<(), String> {
-    println!("Converting {} to {}", lsf_path, lsx_path);
-
-    execute_divine_command(vec![
-        "-g", "bg3",
-        "-a", "convert-resource",
-        "-s", lsf_path,
-        "-d", lsx_path,
-        "-i", "lsf",
-        "-o", "lsx",
-    ])
-}
+    /// LSLib version from Divine's help banner, if it prints one
+    pub fn detect_version(&self) -> Option<String> {
+        let output = self.output(&["--help"]).ok()?;
+        let text = format!("{}\n{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+        parse_version(&text)
+    }
 
-#[allow(dead_code)]
-pub fn convert_lsx_to_lsf(lsx_path: &str, lsf_path: &str) -> Result<(), String> {
-    println!("Converting {} to {}", lsx_path, lsf_path);
-
-    execute_divine_command(vec![
-        "-g", "bg3",
-        "-a", "convert-resource",
-        "-s", lsx_path,
-        "-d", lsf_path,
-        "-i", "lsx",
-        "-o", "lsf",
-    ])
+    pub fn status(&self) -> LslibStatus {
+        LslibStatus {
+            path: self.path.to_string_lossy().to_string(),
+            launcher: self.launcher,
+            version: self.detect_version(),
+        }
+    }
 }
 
-#[allow(dead_code)]
-pub fn repack_save(source_path: &str, output_lsv_path: &str) -> Result<(), String> {
-    println!("Repacking {} to {}", source_path, output_lsv_path);
-
-    execute_divine_command(vec![
-        "-g", "bg3",
-        "-a", "create-package",
-        "-s", source_path,
-        "-d", output_lsv_path,
-    ])
+/// First version number in Divine output, preferring one next to "LSLib" or "Divine"
+fn parse_version(text: &str) -> Option<String> {
+    let named = Regex::new(r"(?i)(?:lslib|divine)\D{0,16}?(\d+\.\d+\.\d+(?:\.\d+)?)").unwrap();
+    let any = Regex::new(r"\b(\d+\.\d+\.\d+(?:\.\d+)?)\b").unwrap();
+    named.captures(text)
+        .or_else(|| any.captures(text))
+        .map(|caps| caps[1].to_string())
 }
 
 // ============================================================================
@@ -147,15 +211,15 @@ pub fn repack_save(source_path: &str, output_lsv_path: &str) -> Result<(), Strin
 // ============================================================================
 
 /// Validate that input file exists
-fn validate_input_file(file_path: &str) -> Result<(), String> {
+fn validate_input_file(file_path: &str) -> Result<(), AppError> {
     if !Path::new(file_path).exists() {
-        return Err(format!("File not found: {}", file_path));
+        return Err(AppError::FileNotFound { path: file_path.to_string() });
     }
     Ok(())
 }
 
 /// Creates a backup of a save file before modification
-pub fn backup_save(save_path: &str) -> Result<String, String> {
+pub fn backup_save(save_path: &str) -> Result<String, AppError> {
     validate_input_file(save_path)?;
     
     let path = Path::new(save_path);
@@ -172,8 +236,36 @@ pub fn backup_save(save_path: &str) -> Result<String, String> {
         .join(backup_name);
     
     std::fs::copy(save_path, &backup_path)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
+        .map_err(|e| AppError::io(&backup_path, e))?;
     
     println!("Backup created at: {:?}", backup_path);
     Ok(backup_path.to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_next_to_lslib_are_preferred() {
+        assert_eq!(parse_version("LSLib v1.19.5").as_deref(), Some("1.19.5"));
+        assert_eq!(parse_version("Divine 1.18.7.0 (built on .NET 8.0.1)").as_deref(), Some("1.18.7.0"));
+        assert_eq!(parse_version(".NET 8.0.1 runtime, lslib version: 1.20.1").as_deref(), Some("1.20.1"));
+        assert_eq!(parse_version("Runtime 8.0.1").as_deref(), Some("8.0.1"));
+        assert_eq!(parse_version("Usage: divine [options]"), None);
+    }
+
+    #[test]
+    fn divine_is_found_in_the_search_folders() {
+        let base = std::env::temp_dir().join(format!("divine-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&base);
+        assert_eq!(find_divine_path(&base), None);
+
+        let dir = base.join(DIVINE_SEARCH_DIRS[1]);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Divine.exe"), b"").unwrap();
+        let found = find_divine_path(&base).unwrap();
+        assert_eq!(found.file_name().and_then(|n| n.to_str()), Some("Divine.exe"));
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use std::io::{Read, Write};
+
+// ============================================================================
+// Compression
+// ============================================================================
+
+/// Compression method stored in the low nibble of Larian compression flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionMethod {
+    None,
+    Zlib,
+    Lz4,
+    Zstd,
+}
+
+impl CompressionMethod {
+    /// Decode the method from a Larian compression flags byte
+    pub fn from_flags(flags: u8) -> Result<Self, String> {
+        match flags & 0x0F {
+            0 => Ok(CompressionMethod::None),
+            1 => Ok(CompressionMethod::Zlib),
+            2 => Ok(CompressionMethod::Lz4),
+            3 => Ok(CompressionMethod::Zstd),
+            other => Err(format!("Unknown compression method: {}", other)),
+        }
+    }
+
+    /// Encode the method as a flags byte using the "default" compression level
+    pub fn to_flags(self) -> u8 {
+        match self {
+            CompressionMethod::None => 0,
+            CompressionMethod::Zlib => 1 | DEFAULT_COMPRESSION_LEVEL,
+            CompressionMethod::Lz4 => 2 | DEFAULT_COMPRESSION_LEVEL,
+            CompressionMethod::Zstd => 3 | DEFAULT_COMPRESSION_LEVEL,
+        }
+    }
+}
+
+/// Level bits in the high nibble of the flags byte (LSLib `DefaultCompression`)
+const DEFAULT_COMPRESSION_LEVEL: u8 = 0x20;
+
+/// Compress a buffer according to Larian compression flags.
+/// `chunked` selects the LZ4 frame format used by LSF substreams.
+pub fn compress(data: &[u8], flags: u8, chunked: bool) -> Result<Vec<u8>, String> {
+    match CompressionMethod::from_flags(flags)? {
+        CompressionMethod::None => Ok(data.to_vec()),
+        CompressionMethod::Zlib => {
+            let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)
+                .and_then(|_| encoder.finish())
+                .map_err(|e| format!("Zlib compression failed: {}", e))
+        }
+        CompressionMethod::Lz4 if chunked => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+            encoder.write_all(data)
+                .map_err(|e| format!("LZ4 frame compression failed: {}", e))?;
+            encoder.finish()
+                .map_err(|e| format!("LZ4 frame compression failed: {}", e))
+        }
+        CompressionMethod::Lz4 => Ok(lz4_flex::block::compress(data)),
+        CompressionMethod::Zstd => zstd::bulk::compress(data, 0)
+            .map_err(|e| format!("Zstd compression failed: {}", e)),
+    }
+}
+
+/// Decompress a buffer according to its Larian compression flags.
+/// `chunked` selects the LZ4 frame format used by LSF substreams
+/// instead of a raw LZ4 block.
+pub fn decompress(data: &[u8], uncompressed_size: usize, flags: u8, chunked: bool) -> Result<Vec<u8>, String> {
+    let output = match CompressionMethod::from_flags(flags)? {
+        CompressionMethod::None => data.to_vec(),
+        CompressionMethod::Zlib => {
+            let mut output = Vec::with_capacity(uncompressed_size);
+            flate2::read::ZlibDecoder::new(data)
+                .read_to_end(&mut output)
+                .map_err(|e| format!("Zlib decompression failed: {}", e))?;
+            output
+        }
+        CompressionMethod::Lz4 if chunked => {
+            let mut output = Vec::with_capacity(uncompressed_size);
+            lz4_flex::frame::FrameDecoder::new(data)
+                .read_to_end(&mut output)
+                .map_err(|e| format!("LZ4 frame decompression failed: {}", e))?;
+            output
+        }
+        CompressionMethod::Lz4 => lz4_flex::block::decompress(data, uncompressed_size)
+            .map_err(|e| format!("LZ4 decompression failed: {}", e))?,
+        CompressionMethod::Zstd => zstd::bulk::decompress(data, uncompressed_size)
+            .map_err(|e| format!("Zstd decompression failed: {}", e))?,
+    };
+
+    if output.len() != uncompressed_size {
+        return Err(format!(
+            "Decompressed size mismatch: expected {} bytes, got {}",
+            uncompressed_size,
+            output.len()
+        ));
+    }
+    Ok(output)
+}
+
+// ============================================================================
+// Little-Endian Reading
+// ============================================================================
+
+/// Bounds-checked little-endian reader over an in-memory buffer
+pub struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        ByteReader { data, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn seek(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| format!("Unexpected end of data at offset {} (wanted {} bytes)", self.pos, len))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, String> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+}
+
+/// Decode a fixed-size, NUL-padded UTF-8 name field
+pub fn read_padded_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
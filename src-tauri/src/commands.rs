@@ -1,6 +1,17 @@
 use std::path::{Path, PathBuf};
+use tauri::State;
+use crate::abilities;
 use crate::bg3_io;
+use crate::document::NodeId;
+use crate::experience;
+use crate::health;
+use crate::inventory;
+use crate::party;
+use crate::position;
 use crate::save_model;
+use crate::templates;
+use crate::session::{SaveSession, SavedSession, SessionInfo, SessionRegistry};
+use crate::error::AppError;
 
 #[derive(serde::Serialize)]
 pub struct SaveEntry {
@@ -13,20 +24,13 @@ pub struct SaveEntry {
 // Helpers for common operations
 // ============================================================================
 
-/// Get the path to the temporary save extraction folder
-fn get_temp_save_path() -> Result<PathBuf, String> {
-    std::env::current_dir()
-        .map_err(|e| format!("Failed to get current directory: {}", e))
-        .map(|dir| dir.join("extracted/temp_save"))
-}
-
 /// Expand environment variables in folder paths
-fn expand_path_variables(path: &str) -> Result<String, String> {
+fn expand_path_variables(path: &str) -> Result<String, AppError> {
     if path.contains("%LOCALAPPDATA%") || path.contains("$env:LOCALAPPDATA") {
         let local_appdata = std::env::var("LOCALAPPDATA")
             .or_else(|_| std::env::var("UserProfile")
                 .map(|p| format!("{}\\AppData\\Local", p)))
-            .map_err(|_| "Could not determine LocalAppData folder".to_string())?;
+            .map_err(|_| AppError::not_found("Could not determine LocalAppData folder"))?;
         Ok(path.replace("%LOCALAPPDATA%", &local_appdata)
             .replace("$env:LOCALAPPDATA", &local_appdata))
     } else {
@@ -34,32 +38,22 @@ fn expand_path_variables(path: &str) -> Result<String, String> {
     }
 }
 
-/// Recursively clean directory and create fresh copy
-fn clean_and_create_directory(path: &Path) -> Result<(), String> {
-    if path.exists() {
-        std::fs::remove_dir_all(path).map_err(|e| e.to_string())?;
-    }
-    std::fs::create_dir_all(path).map_err(|e| e.to_string())
-}
-
 /// Find first .lsv file in directory
-fn find_save_file_in_directory(dir: &Path) -> Result<Option<PathBuf>, String> {
+fn find_save_file_in_directory(dir: &Path) -> Result<Option<PathBuf>, AppError> {
     let entries = std::fs::read_dir(dir)
-        .map_err(|e| format!("Failed to read directory: {}", e))?;
+        .map_err(|e| AppError::io(dir, e))?;
     
-    for entry in entries {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("lsv") {
-                return Ok(Some(path));
-            }
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("lsv") {
+            return Ok(Some(path));
         }
     }
     Ok(None)
 }
 
 /// Get metadata for a save file (name, path, modified time)
-fn create_save_entry(dir_path: &Path, lsv_path: &Path) -> Result<SaveEntry, String> {
+fn create_save_entry(dir_path: &Path, lsv_path: &Path) -> Result<SaveEntry, AppError> {
     let modified = std::fs::metadata(lsv_path)
         .and_then(|m| m.modified())
         .map(|t| format!("{:?}", t))
@@ -75,40 +69,28 @@ fn create_save_entry(dir_path: &Path, lsv_path: &Path) -> Result<SaveEntry, Stri
     })
 }
 
-/// Validate that a file exists and is readable
-fn validate_file_exists(path: &str) -> Result<(), String> {
-    if !Path::new(path).exists() {
-        return Err(format!("File not found: {}", path));
-    }
-    Ok(())
-}
-
 
 #[tauri::command]
-pub fn list_saves(folder_path: String) -> Result<Vec<SaveEntry>, String> {
+pub fn list_saves(folder_path: String) -> Result<Vec<SaveEntry>, AppError> {
     let expanded_path = expand_path_variables(&folder_path)?;
     let dir = Path::new(&expanded_path);
     
     if !dir.exists() || !dir.is_dir() {
-        return Err(format!("Invalid directory: {}", expanded_path));
+        return Err(AppError::FileNotFound { path: expanded_path });
     }
     
     let mut saves = Vec::new();
     
     let entries = std::fs::read_dir(dir)
-        .map_err(|e| format!("Failed to read directory: {}", e))?;
+        .map_err(|e| AppError::io(dir, e))?;
     
-    for entry in entries {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            
-            if path.is_dir() {
-                if let Ok(lsv_file) = find_save_file_in_directory(&path) {
-                    if let Some(lsv_path) = lsv_file {
-                        if let Ok(save_entry) = create_save_entry(&path, &lsv_path) {
-                            saves.push(save_entry);
-                        }
-                    }
+    for entry in entries.flatten() {
+        let path = entry.path();
+        
+        if path.is_dir() {
+            if let Ok(Some(lsv_path)) = find_save_file_in_directory(&path) {
+                if let Ok(save_entry) = create_save_entry(&path, &lsv_path) {
+                    saves.push(save_entry);
                 }
             }
         }
@@ -121,138 +103,614 @@ pub fn list_saves(folder_path: String) -> Result<Vec<SaveEntry>, String> {
 }
 
 #[tauri::command]
-pub fn check_lslib_status() -> Result<String, String> {
-    match bg3_io::get_divine_path() {
-        Ok(path) => Ok(format!("LSLib tools found at: {}", path)),
-        Err(e) => Err(e)
+pub fn check_lslib_status(divine_path: Option<String>) -> Result<bg3_io::LslibStatus, AppError> {
+    // A path picked by the user replaces the automatic search
+    if divine_path.is_some() {
+        bg3_io::configure_divine_path(divine_path.as_deref())?;
     }
+    
+    let runner = bg3_io::DivineRunner::locate()?;
+    Ok(runner.status())
 }
 
 #[tauri::command]
-pub async fn extract_save(save_path: String) -> Result<String, String> {
-    validate_file_exists(&save_path)?;
-    
-    let extract_path = get_temp_save_path()?;
-    let extract_path_str = extract_path.to_string_lossy().to_string();
-    
-    // Clean and recreate extraction directory
-    clean_and_create_directory(&extract_path)?;
+pub async fn extract_save(save_path: String, sessions: State<'_, SessionRegistry>) -> Result<SessionInfo, AppError> {
+    // Extract natively into a new session workspace, no Divine.exe required
+    sessions.open(Path::new(&save_path))
+}
 
-    // Extract
-    bg3_io::extract_save(&save_path, &extract_path_str)?;
+#[tauri::command]
+pub fn list_sessions(sessions: State<'_, SessionRegistry>) -> Vec<SessionInfo> {
+    sessions.list()
+}
 
-    // Convert Globals.lsf -> lsx
-    let globals_lsf = format!("{}/Globals.lsf", extract_path_str);
-    let globals_lsx = format!("{}/Globals.lsx", extract_path_str);
-    if Path::new(&globals_lsf).exists() {
-        bg3_io::convert_lsf_to_lsx(&globals_lsf, &globals_lsx)?;
+#[tauri::command]
+pub fn close_session(session_id: String, sessions: State<'_, SessionRegistry>) {
+    sessions.close(&session_id);
+}
+
+#[tauri::command]
+pub async fn read_save_info(session_id: String, sessions: State<'_, SessionRegistry>) -> Result<serde_json::Value, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let content = session.read_text("SaveInfo.json")?;
+        serde_json::from_str(&content).map_err(|e| AppError::Parse {
+            file: Some(session.workspace.join("SaveInfo.json").display().to_string()),
+            line: Some(e.line()),
+            message: e.to_string(),
+        })
+    })
+}
+
+/// Player characters and companions across Globals and every level, with
+/// active party membership from SaveInfo.json
+fn collect_party(session: &mut SaveSession) -> Result<Vec<party::CharacterInfo>, AppError> {
+    let mut characters = Vec::new();
+    for path in &session.resource_files()? {
+        party::merge_characters(&mut characters, party::characters_in(session.document(path)?, &resource_name(path)));
     }
+    party::apply_save_info(&mut characters, save_info_json(session).as_ref());
+    Ok(characters)
+}
+
+/// SaveInfo.json parsed, or None if it is missing or unreadable
+fn save_info_json(session: &SaveSession) -> Option<serde_json::Value> {
+    session.read_text("SaveInfo.json").ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
 
-    // Convert WLD_Main_A.lsf -> lsx (Recursively convert all in LevelCache? Or just Main)
-    // Finding the level name dynamically would be better, but often it's LevelCache/WLD_Main_A.lsf
-    // SaveInfo.json has "Current Level" key.
-    let level_lsf = format!("{}/LevelCache/WLD_Main_A.lsf", extract_path_str);
-    let level_lsx = format!("{}/LevelCache/WLD_Main_A.lsx", extract_path_str);
-    if Path::new(&level_lsf).exists() {
-        bg3_io::convert_lsf_to_lsx(&level_lsf, &level_lsx)?;
+#[tauri::command]
+pub async fn list_party(session_id: String, sessions: State<'_, SessionRegistry>) -> Result<Vec<party::CharacterInfo>, AppError> {
+    sessions.with_session(&session_id, collect_party)
+}
+
+/// A player character or companion by owner key
+fn find_character(session: &mut SaveSession, character_key: &str) -> Result<party::CharacterInfo, AppError> {
+    collect_party(session)?.into_iter()
+        .find(|c| c.character.key() == character_key)
+        .ok_or_else(|| AppError::not_found(format!("No party member {}", character_key)))
+}
+
+/// Ability score fields of a character across Globals and every level
+fn collect_abilities(session: &mut SaveSession, character_key: &str) -> Result<abilities::CharacterAbilities, AppError> {
+    let character = find_character(session, character_key)?;
+    let mut fields = Vec::new();
+    for node in &character.nodes {
+        let document = session.document(Path::new(&node.resource))?;
+        fields.extend(abilities::ability_fields(document, node.node, &node.resource));
     }
-    
-    // Store the original save path for later use
-    let marker_path = format!("{}/.source_path", extract_path_str);
-    std::fs::write(marker_path, &save_path).map_err(|e| e.to_string())?;
+    Ok(abilities::summarize(character.character, fields))
+}
 
-    Ok(format!("Save extracted and converted to {}", extract_path_str))
+#[tauri::command]
+pub async fn get_ability_scores(session_id: String, character: String, sessions: State<'_, SessionRegistry>) -> Result<abilities::CharacterAbilities, AppError> {
+    sessions.with_session(&session_id, |session| collect_abilities(session, &character))
 }
 
+/// Set a character's base ability score, keeping current scores and
+/// modifiers in sync. Every field is checked before any is changed.
 #[tauri::command]
-pub async fn read_save_info() -> Result<serde_json::Value, String> {
-    let extract_path = get_temp_save_path()?;
-    let info_path = extract_path.join("SaveInfo.json");
-    
-    if !info_path.exists() {
-        return Err("SaveInfo.json not found. Extract a save first.".to_string());
+pub async fn set_ability_score(session_id: String, edit: abilities::AbilityEdit, sessions: State<'_, SessionRegistry>) -> Result<abilities::CharacterAbilities, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let current = collect_abilities(session, &edit.character)?;
+        let changes = abilities::plan_ability_edit(&current.fields, &edit)?;
+        
+        for (field, value) in &changes {
+            let document = session.document(Path::new(&field.resource))?;
+            party::check_int_field(document, field.node, &field.attribute, *value)?;
+        }
+        for (field, value) in &changes {
+            let document = session.document_mut(Path::new(&field.resource))?;
+            party::write_int_field(document, field.node, &field.attribute, *value)?;
+        }
+        
+        println!("Set {} of {} to {} ({} fields)", edit.ability.name(), current.character.name, edit.score, changes.len());
+        collect_abilities(session, &edit.character)
+    })
+}
+
+/// Experience fields of a character across Globals and every level, and its
+/// SaveInfo.json summary
+fn collect_experience(session: &mut SaveSession, character_key: &str) -> Result<experience::CharacterExperience, AppError> {
+    let character = find_character(session, character_key)?;
+    let mut fields = Vec::new();
+    for node in &character.nodes {
+        let document = session.document(Path::new(&node.resource))?;
+        fields.extend(experience::experience_fields(document, node.node, &node.resource));
     }
+    Ok(experience::summarize(&character, fields, save_info_json(session).as_ref()))
+}
 
-    let content = std::fs::read_to_string(info_path).map_err(|e| e.to_string())?;
-    serde_json::from_str(&content).map_err(|e| e.to_string())
+#[tauri::command]
+pub async fn get_experience(session_id: String, character: String, sessions: State<'_, SessionRegistry>) -> Result<experience::CharacterExperience, AppError> {
+    sessions.with_session(&session_id, |session| collect_experience(session, &character))
 }
 
+/// Set a character's experience (or exactly the experience of a level) in
+/// every tree field and the SaveInfo.json summary. Every field is checked
+/// before any is changed.
 #[tauri::command]
-pub async fn get_gold_count() -> Result<save_model::SaveState, String> {
-    let extract_path = get_temp_save_path()?;
+pub async fn set_experience(session_id: String, edit: experience::ExperienceEdit, sessions: State<'_, SessionRegistry>) -> Result<experience::CharacterExperience, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let current = collect_experience(session, &edit.character)?;
+        let total = experience::plan_experience_edit(&current, &edit.target)?;
+        
+        for field in &current.fields {
+            let document = session.document(Path::new(&field.resource))?;
+            party::check_int_field(document, field.node, &field.attribute, experience::value_for(field.role, total))?;
+        }
+        for field in &current.fields {
+            let document = session.document_mut(Path::new(&field.resource))?;
+            party::write_int_field(document, field.node, &field.attribute, experience::value_for(field.role, total))?;
+        }
+        
+        let mut summary_keys = 0;
+        if let Some(mut save_info) = save_info_json(session) {
+            let character = find_character(session, &edit.character)?;
+            summary_keys = experience::update_save_info(&character, &mut save_info, total);
+            if summary_keys > 0 {
+                let content = serde_json::to_string_pretty(&save_info)
+                    .map_err(|e| AppError::parse(e.to_string()))?;
+                session.write_text("SaveInfo.json", &content)?;
+            }
+        }
+        
+        println!("Set experience of {} to {} ({} fields, {} SaveInfo keys)", current.character.name, total, current.fields.len(), summary_keys);
+        collect_experience(session, &edit.character)
+    })
+}
+
+/// Health fields and death statuses of a character across Globals and every level
+fn collect_health(session: &mut SaveSession, character_key: &str) -> Result<health::CharacterHealth, AppError> {
+    let character = find_character(session, character_key)?;
+    let mut fields = Vec::new();
+    let mut statuses = Vec::new();
+    for node in &character.nodes {
+        let document = session.document(Path::new(&node.resource))?;
+        let (node_fields, node_statuses) = health::health_of(document, node.node, &node.resource);
+        fields.extend(node_fields);
+        statuses.extend(node_statuses);
+    }
+    Ok(health::summarize(character.character, fields, statuses))
+}
+
+/// Check every health change, then write them all
+fn apply_health_changes(session: &mut SaveSession, changes: &[(health::HealthField, i64)]) -> Result<(), AppError> {
+    for (field, value) in changes {
+        health::check_field(session.document(Path::new(&field.resource))?, field, *value)?;
+    }
+    for (field, value) in changes {
+        health::write_field(session.document_mut(Path::new(&field.resource))?, field, *value)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_health(session_id: String, character: String, sessions: State<'_, SessionRegistry>) -> Result<health::CharacterHealth, AppError> {
+    sessions.with_session(&session_id, |session| collect_health(session, &character))
+}
+
+/// Set a character's current and/or max HP in every copy of its health component
+#[tauri::command]
+pub async fn set_hit_points(session_id: String, edit: health::HitPointEdit, sessions: State<'_, SessionRegistry>) -> Result<health::CharacterHealth, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let current = collect_health(session, &edit.character)?;
+        let changes = health::plan_hit_points(&current, &edit)?;
+        apply_health_changes(session, &changes)?;
+        
+        println!("Set HP of {} to {:?} / {:?} ({} fields)", current.character.name, edit.hp, edit.max_hp, changes.len());
+        collect_health(session, &edit.character)
+    })
+}
+
+/// Revive a downed or dead character: clear death flags, death saving throws
+/// and death statuses, and restore HP to `hp` (max HP if not given)
+#[tauri::command]
+pub async fn revive_character(session_id: String, character: String, hp: Option<i64>, sessions: State<'_, SessionRegistry>) -> Result<health::CharacterHealth, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let current = collect_health(session, &character)?;
+        let plan = health::plan_revive(&current, hp)?;
+        apply_health_changes(session, &plan.changes)?;
+        for status in &plan.statuses {
+            session.document_mut(Path::new(&status.resource))?.remove_node(status.node);
+        }
+        
+        println!("Revived {} ({} fields, {} statuses removed)", current.character.name, plan.changes.len(), plan.statuses.len());
+        collect_health(session, &character)
+    })
+}
+
+/// Position fields of a character across Globals and every level
+fn collect_position(session: &mut SaveSession, character: &party::CharacterInfo) -> Result<position::CharacterPosition, AppError> {
+    let mut fields = Vec::new();
+    for node in &character.nodes {
+        let document = session.document(Path::new(&node.resource))?;
+        fields.extend(position::position_fields(document, node.node, &node.resource));
+    }
+    Ok(position::summarize(character, fields))
+}
+
+/// Positions of every player character and companion
+fn collect_positions(session: &mut SaveSession) -> Result<Vec<position::CharacterPosition>, AppError> {
+    let characters = collect_party(session)?;
+    characters.iter().map(|character| collect_position(session, character)).collect()
+}
+
+#[tauri::command]
+pub async fn list_positions(session_id: String, sessions: State<'_, SessionRegistry>) -> Result<Vec<position::CharacterPosition>, AppError> {
+    sessions.with_session(&session_id, collect_positions)
+}
+
+/// Move characters to coordinates or to another character, updating their
+/// Translate/Rotate fields and the SaveInfo.json position mirrors. A
+/// character going to another level has its node moved into that level
+/// file. Every field is checked before any is changed.
+#[tauri::command]
+pub async fn teleport_characters(session_id: String, edit: position::TeleportEdit, sessions: State<'_, SessionRegistry>) -> Result<Vec<position::CharacterPosition>, AppError> {
+    sessions.with_session(&session_id, |session| {
+        if edit.characters.is_empty() {
+            return Err(AppError::validation("Pick at least one character to move"));
+        }
+        let mut party = collect_party(session)?;
+        let find = |party: &[party::CharacterInfo], key: &str| party.iter().position(|c| c.character.key() == key)
+            .ok_or_else(|| AppError::not_found(format!("No party member {}", key)));
+        let destination = match &edit.target {
+            position::TeleportTarget::Character { character } => match party.iter().find(|c| c.character.key() == *character) {
+                Some(character) => Some(collect_position(session, character)?),
+                None => None,
+            },
+            position::TeleportTarget::Coordinates { .. } => None,
+        };
+        let target = position::resolve_target(&edit.target, destination.as_ref())?;
+        
+        let mut level_moves = 0;
+        if let Some(level) = &target.level {
+            let level_file = session.level_files()?.into_iter()
+                .find(|path| path.file_stem().and_then(|s| s.to_str()) == Some(level.as_str()))
+                .ok_or_else(|| AppError::not_found(format!("No level {} in this save", level)))?;
+            for key in &edit.characters {
+                let character = &party[find(&party, key)?];
+                let Some(node) = position::node_to_move(character, level)? else {
+                    continue;
+                };
+                let source = PathBuf::from(&node.resource);
+                let document = session.document(&source)?;
+                let parent = document.parent(node.node)
+                    .map(|parent| document.node(parent).name.clone())
+                    .unwrap_or_default();
+                let copy = position::copy_character(document, node.node)?;
+                position::place_character(session.document_mut(&level_file)?, &parent, &copy, level)?;
+                session.document_mut(&source)?.remove_node(node.node);
+                println!("Moved {} from {} to {}", character.character.name, node.resource, level);
+                level_moves += 1;
+            }
+            if level_moves > 0 {
+                party = collect_party(session)?;
+            }
+        }
+        
+        let mut moving = Vec::new();
+        for key in &edit.characters {
+            let character = &party[find(&party, key)?];
+            moving.push((character, collect_position(session, character)?));
+        }
+        let mut changes = Vec::new();
+        for (_, current) in &moving {
+            changes.extend(position::plan_teleport(current, &target.position, target.rotation.as_deref())?);
+        }
+        
+        for (field, value) in &changes {
+            position::check_field(session.document(Path::new(&field.resource))?, field, value)?;
+        }
+        for (field, value) in &changes {
+            position::write_field(session.document_mut(Path::new(&field.resource))?, field, value)?;
+        }
+        
+        // The party's level changes once every player character is there
+        let players: Vec<String> = party.iter().filter(|c| c.is_player).map(|c| c.character.key()).collect();
+        let party_level = target.level.as_ref().filter(|_| {
+            level_moves > 0 && !players.is_empty() && players.iter().all(|key| edit.characters.contains(key))
+        });
+        let mut summary_keys = 0;
+        if let Some(mut save_info) = save_info_json(session) {
+            for (character, _) in &moving {
+                summary_keys += position::update_save_info(character, &party, &mut save_info, &target.position);
+            }
+            if let Some(level) = party_level {
+                summary_keys += position::update_current_level(&mut save_info, level);
+            }
+            if summary_keys > 0 {
+                let content = serde_json::to_string_pretty(&save_info)
+                    .map_err(|e| AppError::parse(e.to_string()))?;
+                session.write_text("SaveInfo.json", &content)?;
+            }
+        }
+        
+        println!("Moved {} characters to {:?} ({} fields, {} SaveInfo keys)", moving.len(), target, changes.len(), summary_keys);
+        let positions = collect_positions(session)?;
+        if let Some(level) = party_level {
+            session.current_level = Some(level.clone());
+        }
+        Ok(positions)
+    })
+}
+
+/// How a resource path is reported to the frontend, with forward slashes
+fn resource_name(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Gold across Globals and every LevelCache level, as LSF or converted LSX
+fn collect_gold(session: &mut SaveSession) -> Result<save_model::SaveState, AppError> {
+    let resource_paths = session.resource_files()?;
     
-    // Check both Globals.lsx and WLD_Main_A.lsx for gold
-    let globals_path = extract_path.join("Globals.lsx");
-    let level_path = extract_path.join("LevelCache/WLD_Main_A.lsx");
+    if resource_paths.is_empty() {
+        return Err(AppError::not_found("Save data not found. Extract a save first."));
+    }
     
     let mut combined_state = save_model::SaveState {
         total_gold: 0,
-        items: Vec::new(),
+        owners: Vec::new(),
     };
     
-    // Read Globals.lsx
-    if globals_path.exists() {
-        let content = std::fs::read_to_string(&globals_path).map_err(|e| e.to_string())?;
-        let globals_state = save_model::get_gold_info(&content);
-        combined_state.total_gold += globals_state.total_gold;
-        combined_state.items.extend(globals_state.items);
+    for path in &resource_paths {
+        let resource = resource_name(path);
+        combined_state.merge(save_model::get_gold_info(session.document(path)?, &resource)?);
     }
     
-    // Read WLD_Main_A.lsx
-    if level_path.exists() {
-        let content = std::fs::read_to_string(&level_path).map_err(|e| e.to_string())?;
-        let level_state = save_model::get_gold_info(&content);
-        combined_state.total_gold += level_state.total_gold;
-        combined_state.items.extend(level_state.items);
+    let party: Vec<String> = collect_party(session)?.iter().map(|c| c.character.key()).collect();
+    combined_state.mark_party(&party);
+    Ok(combined_state)
+}
+
+#[tauri::command]
+pub async fn get_gold_count(session_id: String, sessions: State<'_, SessionRegistry>) -> Result<save_model::SaveState, AppError> {
+    sessions.with_session(&session_id, collect_gold)
+}
+
+/// Every item per owner across Globals and every level, with container contents nested
+fn collect_inventory(session: &mut SaveSession) -> Result<Vec<inventory::OwnerInventory>, AppError> {
+    let mut owners = Vec::new();
+    for path in &session.resource_files()? {
+        let resource = resource_name(path);
+        inventory::merge_inventories(&mut owners, inventory::list_items(session.document(path)?, &resource));
     }
-    
-    if !globals_path.exists() && !level_path.exists() {
-        return Err("Save data not found. Extract a save first.".to_string());
+    Ok(owners)
+}
+
+#[tauri::command]
+pub async fn list_inventory(session_id: String, sessions: State<'_, SessionRegistry>) -> Result<Vec<inventory::OwnerInventory>, AppError> {
+    sessions.with_session(&session_id, collect_inventory)
+}
+
+/// Set the stack size of every item matching the edit's UUID or template and
+/// return the updated inventories. All matches are checked before any is changed.
+#[tauri::command]
+pub async fn set_item_amount(session_id: String, edit: save_model::StackEdit, sessions: State<'_, SessionRegistry>) -> Result<Vec<inventory::OwnerInventory>, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let mut targets = Vec::new();
+        for path in session.resource_files()? {
+            let document = session.document(&path)?;
+            let items = inventory::find_items(document, &edit.item);
+            for &item in &items {
+                save_model::check_item_amount(document, item, edit.amount)?;
+            }
+            if !items.is_empty() {
+                targets.push((path, items));
+            }
+        }
+        
+        if targets.is_empty() {
+            return Err(AppError::not_found(format!("No item matches {:?}", edit.item)));
+        }
+        
+        for (path, items) in &targets {
+            let document = session.document_mut(path)?;
+            for &item in items {
+                save_model::set_item_amount(document, item, edit.amount)?;
+            }
+        }
+        
+        println!("Set {} stacks to {} ({:?})", targets.iter().map(|(_, items)| items.len()).sum::<usize>(), edit.amount, edit.item);
+        collect_inventory(session)
+    })
+}
+
+/// An inventory list of the owner with this key, preferring `preferred`'s resource
+fn find_owner_inventory(session: &mut SaveSession, owner_key: &str, preferred: &Path) -> Result<(PathBuf, NodeId, inventory::InventoryOwner), AppError> {
+    let mut paths = session.resource_files()?;
+    paths.sort_by_key(|path| path != preferred);
+    for path in paths {
+        let found = inventory::inventories(session.document(&path)?).into_iter()
+            .find(|inventory| inventory.owner.key() == owner_key);
+        if let Some(inventory) = found {
+            return Ok((path, inventory.node, inventory.owner));
+        }
     }
-    
-    Ok(combined_state)
+    Err(AppError::not_found(format!("No inventory found for {}", owner_key)))
 }
 
+/// Copy an item, with everything in it, into the inventory of `target_owner`
+/// (or next to the original) and return the updated inventories
 #[tauri::command]
-pub async fn modify_and_save_gold(new_gold: i32) -> Result<String, String> {
-    // Get paths
-    let extract_path = get_temp_save_path()?;
-    let save_path_marker = extract_path.join(".source_path");
-    
-    // Read the original save path (stored during extraction)
-    let source_save_path = std::fs::read_to_string(&save_path_marker)
-        .map_err(|_| "Original save path not found. Please extract a save first.".to_string())?;
-    
-    // Create backup
-    let backup_path = bg3_io::backup_save(&source_save_path)?;
-    
-    // Read and modify the LSX file
-    let lsx_path = extract_path.join("Globals.lsx");
-    if !lsx_path.exists() {
-        return Err("Globals data not found (Globals.lsx). Extract a save first.".to_string());
+pub async fn duplicate_item(
+    session_id: String,
+    resource: String,
+    node: NodeId,
+    target_owner: Option<String>,
+    sessions: State<'_, SessionRegistry>,
+) -> Result<Vec<inventory::OwnerInventory>, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let source = PathBuf::from(&resource);
+        let document = session.document(&source)?;
+        let copy = inventory::copy_item(document, node)?;
+        
+        let (target, list, owner) = match &target_owner {
+            Some(owner_key) => find_owner_inventory(session, owner_key, &source)?,
+            None => {
+                let list = document.parent(node)
+                    .ok_or_else(|| AppError::validation(format!("Item {} is not in an inventory", node)))?;
+                let owner = inventory::inventories(document).into_iter()
+                    .find(|inventory| inventory.node == list)
+                    .ok_or_else(|| AppError::validation(format!("Item {} is not in an inventory", node)))?
+                    .owner;
+                (source.clone(), list, owner)
+            }
+        };
+        
+        let same_document = target == source;
+        let copied = inventory::place_item(session.document_mut(&target)?, list, &owner, &copy, same_document)?;
+        println!("Duplicated {}:{} as {}:{} for {}", resource, node, resource_name(&target), copied, owner.name);
+        collect_inventory(session)
+    })
+}
+
+/// Move an item, with everything in it, into the inventory of `target_owner`
+/// (a party member or the camp chest) and return the updated inventories
+#[tauri::command]
+pub async fn move_item(
+    session_id: String,
+    resource: String,
+    node: NodeId,
+    target_owner: String,
+    sessions: State<'_, SessionRegistry>,
+) -> Result<Vec<inventory::OwnerInventory>, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let source = PathBuf::from(&resource);
+        if !inventory::is_item(session.document(&source)?, node) {
+            return Err(AppError::not_found(format!("No item {} in {}", node, resource)));
+        }
+        let (target, list, owner) = find_owner_inventory(session, &target_owner, &source)?;
+        
+        if target == source {
+            inventory::check_move_target(session.document(&source)?, node, list)?;
+            inventory::move_item(session.document_mut(&source)?, node, list, &owner)?;
+        } else {
+            // Check before changing either resource so a refused move changes nothing
+            let copy = inventory::copy_item(session.document(&source)?, node)?;
+            copy.check_movable()?;
+            inventory::put_item(session.document_mut(&target)?, list, &owner, &copy)?;
+            inventory::take_item(session.document_mut(&source)?, node)?;
+        }
+        
+        collect_inventory(session)
+    })
+}
+
+/// Remove an item and its contents, clean up references to them in Globals
+/// and every level, and return the updated inventories
+#[tauri::command]
+pub async fn delete_item(session_id: String, resource: String, node: NodeId, sessions: State<'_, SessionRegistry>) -> Result<Vec<inventory::OwnerInventory>, AppError> {
+    sessions.with_session(&session_id, |session| {
+        remove_item(session, Path::new(&resource), node)?;
+        collect_inventory(session)
+    })
+}
+
+/// Delete an item and its contents from `source` and the references to them
+/// from every other resource
+fn remove_item(session: &mut SaveSession, source: &Path, node: NodeId) -> Result<(), AppError> {
+    // Check first so a bad node does not mark the resource as modified
+    if !inventory::is_item(session.document(source)?, node) {
+        return Err(AppError::not_found(format!("No item {} in {}", node, resource_name(source))));
     }
+    let ids = inventory::delete_item(session.document_mut(source)?, node)?;
     
-    let content = std::fs::read_to_string(&lsx_path).map_err(|e| e.to_string())?;
-    let modified_content = save_model::modify_gold(&content, new_gold)?;
-    
-    // Write modified LSX back
-    std::fs::write(&lsx_path, modified_content).map_err(|e| e.to_string())?;
-    
-    // Convert LSX back to LSF for both Globals and Level
-    let globals_lsf = extract_path.join("Globals.lsf");
-    bg3_io::convert_lsx_to_lsf(
-        &lsx_path.to_string_lossy().to_string(),
-        &globals_lsf.to_string_lossy().to_string()
-    )?;
-    
-    // Repack the save
-    let output_save = format!("{}_modified.lsv", source_save_path.trim_end_matches(".lsv"));
-    bg3_io::repack_save(&extract_path.to_string_lossy().to_string(), &output_save)?;
-    
-    Ok(format!("Save modified successfully!\nBackup: {}\nNew save: {}", backup_path, output_save))
+    for path in session.resource_files()? {
+        if path == source || !inventory::has_references(session.document(&path)?, &ids) {
+            continue;
+        }
+        let removed = inventory::remove_references(session.document_mut(&path)?, &ids)?;
+        println!("Removed {} references in {}", removed, resource_name(&path));
+    }
+    Ok(())
+}
+
+/// Templates of the items found in Globals and every level
+fn collect_templates(session: &mut SaveSession) -> Result<Vec<templates::ItemTemplate>, AppError> {
+    let mut known = Vec::new();
+    for path in &session.resource_files()? {
+        templates::merge_templates(&mut known, templates::templates_in(session.document(path)?));
+    }
+    Ok(known)
+}
+
+/// Item templates that can be added: those of items in the save, plus gold
+#[tauri::command]
+pub async fn list_item_templates(session_id: String, sessions: State<'_, SessionRegistry>) -> Result<Vec<templates::ItemTemplate>, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let mut known = collect_templates(session)?;
+        let gold = templates::gold(&known);
+        templates::merge_templates(&mut known, vec![gold]);
+        Ok(known)
+    })
+}
+
+/// Add a new item of a root template (by template UUID or stats id) to
+/// `target_owner`'s inventory and return the updated inventories. A template
+/// the save holds no item of is built from `description`.
+#[tauri::command]
+pub async fn add_item(
+    session_id: String,
+    target_owner: String,
+    template: String,
+    amount: i32,
+    description: Option<templates::TemplateDescription>,
+    sessions: State<'_, SessionRegistry>,
+) -> Result<Vec<inventory::OwnerInventory>, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let template = templates::resolve_template(&collect_templates(session)?, &template, description.as_ref())?;
+        template.check_amount(amount)?;
+        
+        let preferred = session.resource_files()?.into_iter().next().unwrap_or_default();
+        let (target, list, owner) = find_owner_inventory(session, &target_owner, &preferred)?;
+        let item = template.build(amount)?.into();
+        let node = inventory::place_item(session.document_mut(&target)?, list, &owner, &item, false)?;
+        println!("Added {} x{} as {}:{} for {}", template.name, amount, resource_name(&target), node, owner.name);
+        collect_inventory(session)
+    })
+}
+
+/// Apply a gold edit to the session's documents and return the gold
+/// recomputed from the edited tree; nothing is written until the session is saved
+#[tauri::command]
+pub async fn set_gold(session_id: String, edit: save_model::GoldEdit, sessions: State<'_, SessionRegistry>) -> Result<save_model::SaveState, AppError> {
+    sessions.with_session(&session_id, |session| {
+        let state = collect_gold(session)?;
+        let changes = save_model::plan_gold_edit(&state, &edit)?;
+        
+        // New stacks are copied from an existing one so they match the game's layout
+        let prototype = match state.owners.iter().flat_map(|group| group.items.iter()).next() {
+            Some(item) => session.document(Path::new(&item.resource))?.subtree(item.node),
+            None => templates::gold(&collect_templates(session)?).build(1)?,
+        };
+        
+        for change in &changes {
+            match change {
+                save_model::GoldChange::Set { resource, node, amount } => {
+                    let document = session.document_mut(Path::new(resource))?;
+                    save_model::set_stack_amount(document, *node, *amount)?;
+                }
+                save_model::GoldChange::Create { resource, inventory, owner, amount } => {
+                    let document = session.document_mut(Path::new(resource))?;
+                    save_model::create_gold_stack(document, *inventory, owner, &prototype, *amount)?;
+                }
+                save_model::GoldChange::Remove { resource, node } => {
+                    remove_item(session, Path::new(resource), *node)?;
+                }
+            }
+        }
+        
+        println!("Changed {} gold stacks ({:?})", changes.len(), edit);
+        collect_gold(session)
+    })
+}
+
+#[tauri::command]
+pub async fn save_session(session_id: String, sessions: State<'_, SessionRegistry>) -> Result<SavedSession, AppError> {
+    sessions.with_session(&session_id, |session| session.save())
 }
 
 #[tauri::command]
-pub async fn get_backup_path(save_path: String) -> Result<String, String> {
+pub async fn get_backup_path(save_path: String) -> Result<String, AppError> {
     bg3_io::backup_save(&save_path)
 }
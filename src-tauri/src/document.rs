@@ -0,0 +1,501 @@
+use crate::values::{AttributeValue, ValueError};
+
+// ============================================================================
+// Versions & Formats
+// ============================================================================
+
+/// LSF version written for documents that were not loaded from LSF
+pub const DEFAULT_LSF_VERSION: u32 = 7;
+
+/// Larian engine version, as stored in LSF headers and the LSX `<version>` element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EngineVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub revision: u32,
+    pub build: u32,
+}
+
+impl EngineVersion {
+    /// Unpack the 32-bit version used by pre-BG3 LSF headers
+    pub fn from_packed_i32(packed: i32) -> Self {
+        let packed = packed as u32;
+        EngineVersion {
+            major: (packed >> 28) & 0x0F,
+            minor: (packed >> 24) & 0x0F,
+            revision: (packed >> 16) & 0xFF,
+            build: packed & 0xFFFF,
+        }
+    }
+
+    /// Unpack the 64-bit version used by BG3 LSF headers
+    pub fn from_packed_i64(packed: i64) -> Self {
+        let packed = packed as u64;
+        EngineVersion {
+            major: ((packed >> 55) & 0x7F) as u32,
+            minor: ((packed >> 47) & 0xFF) as u32,
+            revision: ((packed >> 31) & 0xFFFF) as u32,
+            build: (packed & 0x7FFF_FFFF) as u32,
+        }
+    }
+
+    /// Pack into the 32-bit form used by pre-BG3 LSF headers
+    pub fn to_packed_i32(self) -> i32 {
+        (((self.major & 0x0F) << 28)
+            | ((self.minor & 0x0F) << 24)
+            | ((self.revision & 0xFF) << 16)
+            | (self.build & 0xFFFF)) as i32
+    }
+
+    /// Pack into the 64-bit form used by BG3 LSF headers
+    pub fn to_packed_i64(self) -> i64 {
+        (((self.major as u64 & 0x7F) << 55)
+            | ((self.minor as u64 & 0xFF) << 47)
+            | ((self.revision as u64 & 0xFFFF) << 31)
+            | (self.build as u64 & 0x7FFF_FFFF)) as i64
+    }
+}
+
+/// LSF metadata format; `KeysAndAdjacency` adds sibling links and node keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataFormat {
+    #[default]
+    None,
+    KeysAndAdjacency,
+    /// Behaves the same as `None`
+    None2,
+}
+
+impl MetadataFormat {
+    pub fn from_u32(value: u32) -> Self {
+        match value {
+            1 => MetadataFormat::KeysAndAdjacency,
+            2 => MetadataFormat::None2,
+            _ => MetadataFormat::None,
+        }
+    }
+
+    pub fn to_u32(self) -> u32 {
+        match self {
+            MetadataFormat::None => 0,
+            MetadataFormat::KeysAndAdjacency => 1,
+            MetadataFormat::None2 => 2,
+        }
+    }
+}
+
+// ============================================================================
+// Attribute Types
+// ============================================================================
+
+/// Larian attribute data types, numbered as in the LSF type id field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeType {
+    None = 0,
+    UInt8 = 1,
+    Int16 = 2,
+    UInt16 = 3,
+    Int32 = 4,
+    UInt32 = 5,
+    Float = 6,
+    Double = 7,
+    IVec2 = 8,
+    IVec3 = 9,
+    IVec4 = 10,
+    Vec2 = 11,
+    Vec3 = 12,
+    Vec4 = 13,
+    Mat2 = 14,
+    Mat3 = 15,
+    Mat3x4 = 16,
+    Mat4x3 = 17,
+    Mat4 = 18,
+    Bool = 19,
+    String = 20,
+    Path = 21,
+    FixedString = 22,
+    LSString = 23,
+    UInt64 = 24,
+    ScratchBuffer = 25,
+    OldInt64 = 26,
+    Int8 = 27,
+    TranslatedString = 28,
+    WString = 29,
+    LSWString = 30,
+    Guid = 31,
+    Int64 = 32,
+    TranslatedFSString = 33,
+}
+
+/// LSX type names, indexed by type id
+const TYPE_NAMES: [&str; 34] = [
+    "None", "uint8", "int16", "uint16", "int32", "uint32", "float", "double",
+    "ivec2", "ivec3", "ivec4", "fvec2", "fvec3", "fvec4",
+    "mat2x2", "mat3x3", "mat3x4", "mat4x3", "mat4x4",
+    "bool", "string", "path", "FixedString", "LSString", "uint64", "ScratchBuffer",
+    "old_int64", "int8", "TranslatedString", "WString", "LSWString", "guid", "int64",
+    "TranslatedFSString",
+];
+
+/// All types in type id order
+const ALL_TYPES: [AttributeType; 34] = [
+    AttributeType::None, AttributeType::UInt8, AttributeType::Int16, AttributeType::UInt16,
+    AttributeType::Int32, AttributeType::UInt32, AttributeType::Float, AttributeType::Double,
+    AttributeType::IVec2, AttributeType::IVec3, AttributeType::IVec4,
+    AttributeType::Vec2, AttributeType::Vec3, AttributeType::Vec4,
+    AttributeType::Mat2, AttributeType::Mat3, AttributeType::Mat3x4, AttributeType::Mat4x3,
+    AttributeType::Mat4, AttributeType::Bool, AttributeType::String, AttributeType::Path,
+    AttributeType::FixedString, AttributeType::LSString, AttributeType::UInt64,
+    AttributeType::ScratchBuffer, AttributeType::OldInt64, AttributeType::Int8,
+    AttributeType::TranslatedString, AttributeType::WString, AttributeType::LSWString,
+    AttributeType::Guid, AttributeType::Int64, AttributeType::TranslatedFSString,
+];
+
+impl AttributeType {
+    pub fn from_id(id: u32) -> Option<Self> {
+        ALL_TYPES.get(id as usize).copied()
+    }
+
+    pub fn id(self) -> u32 {
+        self as u32
+    }
+
+    /// Type name as written in LSX `type="..."`
+    pub fn name(self) -> &'static str {
+        TYPE_NAMES[self as usize]
+    }
+
+    /// Resolve an LSX type name (or legacy numeric type id)
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(index) = TYPE_NAMES.iter().position(|n| *n == name) {
+            return Some(ALL_TYPES[index]);
+        }
+        name.parse::<u32>().ok().and_then(Self::from_id)
+    }
+}
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// Index of a node in a document's node arena
+pub type NodeId = usize;
+
+/// Localized string reference (TranslatedString / TranslatedFSString)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TranslatedString {
+    /// Inline text, only present in pre-BG3 files
+    pub value: Option<String>,
+    pub handle: String,
+    pub version: u16,
+    /// Format arguments, only used by TranslatedFSString
+    pub arguments: Vec<TranslatedFSArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TranslatedFSArgument {
+    pub key: String,
+    pub string: TranslatedString,
+    pub value: String,
+}
+
+/// A typed attribute on a node; `value` always matches `attr_type`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub id: String,
+    pub attr_type: AttributeType,
+    pub value: AttributeValue,
+    /// Original markup when parsed from LSX
+    pub source: Option<AttributeSource>,
+}
+
+/// A node in the document tree with links to its parent and children
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub key: Option<String>,
+    pub attributes: Vec<Attribute>,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+    /// Original markup when parsed from LSX
+    pub source: Option<NodeSource>,
+}
+
+/// A detached copy of a node and everything below it, used to clone nodes
+/// within a document or between documents
+#[derive(Debug, Clone)]
+pub struct Subtree {
+    pub name: String,
+    pub key: Option<String>,
+    pub attributes: Vec<Attribute>,
+    pub children: Vec<Subtree>,
+}
+
+/// A top-level region (e.g. "Globals", "Items") and its root node
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub id: String,
+    pub root: NodeId,
+    /// Original markup when parsed from LSX
+    pub source: Option<RegionSource>,
+}
+
+/// A parsed LSF/LSX resource. Nodes live in an arena and reference each other by `NodeId`.
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub version: EngineVersion,
+    pub format: MetadataFormat,
+    pub lsf_version: u32,
+    pub regions: Vec<Region>,
+    /// Original prolog, header and layout when parsed from LSX
+    pub lsx_source: Option<LsxSource>,
+    nodes: Vec<Node>,
+}
+
+// ============================================================================
+// LSX Source Formatting
+// ============================================================================
+
+/// Text preceding an element (whitespace, comments) and the element's original markup
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SourceSpan {
+    pub leading: String,
+    pub raw: String,
+}
+
+/// Original markup of an attribute and the attribute as it was parsed,
+/// so the markup is only reused while the attribute is unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeSource {
+    pub span: SourceSpan,
+    pub original: Box<Attribute>,
+}
+
+/// Original markup of a node's tags
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NodeSource {
+    /// Start tag, reused while `name` and `key` are unchanged
+    pub open: SourceSpan,
+    pub name: String,
+    pub key: Option<String>,
+    pub children_open: Option<SourceSpan>,
+    /// `None` when `<children />` was self-closing
+    pub children_close: Option<SourceSpan>,
+    /// `None` when the node was self-closing
+    pub close: Option<SourceSpan>,
+}
+
+/// Original markup of a region's tags
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionSource {
+    pub open: SourceSpan,
+    pub id: String,
+    pub close: SourceSpan,
+}
+
+/// Original document-level LSX text and layout conventions
+#[derive(Debug, Clone, PartialEq)]
+pub struct LsxSource {
+    pub bom: bool,
+    /// Everything before `<save>` (XML declaration, comments)
+    pub prolog: String,
+    pub save_open: String,
+    /// `<version>` element, reused while the version and format are unchanged
+    pub version: Option<SourceSpan>,
+    pub original_version: EngineVersion,
+    pub original_format: MetadataFormat,
+    pub lslib_meta: Option<String>,
+    pub save_close: SourceSpan,
+    /// Everything after `</save>`, usually the trailing newline
+    pub epilogue: String,
+    /// Line break used by the file ("" for single-line files)
+    pub newline: String,
+    /// One level of indentation
+    pub indent: String,
+    /// How self-closing tags end (`" />"` as written by LSLib, or `"/>"`)
+    pub empty_tag_end: String,
+}
+
+// ============================================================================
+// Tree Construction & Navigation
+// ============================================================================
+
+impl Attribute {
+    /// Create an attribute, checking that the value fits the type
+    pub fn new(id: &str, attr_type: AttributeType, value: AttributeValue) -> Result<Self, ValueError> {
+        value.check_type(attr_type)?;
+        Ok(Attribute {
+            id: id.to_string(),
+            attr_type,
+            value,
+            source: None,
+        })
+    }
+
+    /// Replace the value, keeping the declared type
+    pub fn set(&mut self, value: AttributeValue) -> Result<(), ValueError> {
+        value.check_type(self.attr_type)?;
+        self.value = value;
+        Ok(())
+    }
+
+    /// Set an integer attribute, failing if the number does not fit its type
+    pub fn set_int(&mut self, value: i64) -> Result<(), ValueError> {
+        self.value = AttributeValue::from_int(self.attr_type, value)?;
+        Ok(())
+    }
+}
+
+impl Node {
+    /// Find an attribute on this node by id
+    pub fn attribute(&self, id: &str) -> Option<&Attribute> {
+        self.attributes.iter().find(|a| a.id == id)
+    }
+
+    pub fn attribute_mut(&mut self, id: &str) -> Option<&mut Attribute> {
+        self.attributes.iter_mut().find(|a| a.id == id)
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Document {
+            version: EngineVersion::default(),
+            format: MetadataFormat::None,
+            lsf_version: DEFAULT_LSF_VERSION,
+            regions: Vec::new(),
+            lsx_source: None,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Append a node, linking it under `parent` when given
+    pub fn add_node(&mut self, parent: Option<NodeId>, name: &str) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_string(),
+            key: None,
+            attributes: Vec::new(),
+            parent,
+            children: Vec::new(),
+            source: None,
+        });
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(id);
+        }
+        id
+    }
+
+    /// Register a parentless node as the root of a region
+    pub fn add_region(&mut self, id: &str, root: NodeId) {
+        self.regions.push(Region { id: id.to_string(), root, source: None });
+    }
+
+    pub fn region(&self, id: &str) -> Option<&Region> {
+        self.regions.iter().find(|r| r.id == id)
+    }
+
+    /// Number of nodes in the arena, including detached ones
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id]
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id].parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id].children
+    }
+
+    /// Direct children of `id` with the given node name
+    pub fn children_named<'a>(&'a self, id: NodeId, name: &'a str) -> impl Iterator<Item = NodeId> + 'a {
+        self.nodes[id].children.iter().copied().filter(move |&c| self.nodes[c].name == name)
+    }
+
+    /// All nodes below `id` (excluding `id` itself) in document order
+    pub fn descendants(&self, id: NodeId) -> Vec<NodeId> {
+        let mut result = Vec::new();
+        let mut stack: Vec<NodeId> = self.nodes[id].children.iter().rev().copied().collect();
+        while let Some(current) = stack.pop() {
+            result.push(current);
+            stack.extend(self.nodes[current].children.iter().rev());
+        }
+        result
+    }
+
+    /// Every node reachable from a region root, in document order
+    pub fn tree_nodes(&self) -> Vec<NodeId> {
+        let mut result = Vec::new();
+        for region in &self.regions {
+            result.push(region.root);
+            result.extend(self.descendants(region.root));
+        }
+        result
+    }
+
+    /// Every node reachable from a region root with the given name, in document order
+    pub fn find_nodes(&self, name: &str) -> Vec<NodeId> {
+        self.tree_nodes().into_iter().filter(|&n| self.nodes[n].name == name).collect()
+    }
+
+    /// Whether `id` is a node of the document: in the arena and reachable from a region root
+    pub fn contains(&self, id: NodeId) -> bool {
+        if id >= self.nodes.len() {
+            return false;
+        }
+        let mut root = id;
+        while let Some(parent) = self.nodes[root].parent {
+            root = parent;
+        }
+        self.regions.iter().any(|region| region.root == root)
+    }
+
+    /// Detach a node (and its subtree) from its parent. The nodes stay in the
+    /// arena so ids remain valid, but are no longer part of the document.
+    pub fn remove_node(&mut self, id: NodeId) {
+        if let Some(parent) = self.nodes[id].parent.take() {
+            self.nodes[parent].children.retain(|&child| child != id);
+        }
+    }
+
+    /// Copy a node and its descendants out of the tree
+    pub fn subtree(&self, id: NodeId) -> Subtree {
+        let node = &self.nodes[id];
+        Subtree {
+            name: node.name.clone(),
+            key: node.key.clone(),
+            attributes: node.attributes.clone(),
+            children: node.children.iter().map(|&child| self.subtree(child)).collect(),
+        }
+    }
+
+    /// Append a copy of `subtree` under `parent` and return its root. The copy
+    /// keeps no LSX source, so it is written with the document's layout.
+    pub fn insert_subtree(&mut self, parent: Option<NodeId>, subtree: &Subtree) -> NodeId {
+        let id = self.add_node(parent, &subtree.name);
+        let node = &mut self.nodes[id];
+        node.key = subtree.key.clone();
+        node.attributes = subtree.attributes.iter()
+            .map(|attribute| Attribute { source: None, ..attribute.clone() })
+            .collect();
+        for child in &subtree.children {
+            self.insert_subtree(Some(id), child);
+        }
+        id
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::path::Path;
+use serde::ser::SerializeMap;
+use crate::values::ValueError;
+
+// ============================================================================
+// Error Type
+// ============================================================================
+
+/// Every failure the backend reports. Serialized to the frontend as
+/// `{ kind, message, ...fields }` so the UI can react to specific kinds.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    /// Divine/LSLib could not be located
+    ToolMissing { message: String },
+    /// Divine/LSLib ran but reported a failure
+    ToolFailed { tool: String, exit_code: Option<i32>, stderr: String },
+    FileNotFound { path: String },
+    /// Malformed package, resource or JSON data
+    Parse { file: Option<String>, line: Option<usize>, message: String },
+    UnsupportedVersion { format: String, version: u32 },
+    /// A request that cannot be applied (bad input, value out of range)
+    Validation { message: String },
+    /// Data the operation needs is not in the save (e.g. no gold items)
+    NotFound { message: String },
+    Io { path: Option<String>, message: String },
+}
+
+impl AppError {
+    /// I/O failure on a path; a missing file becomes `FileNotFound`
+    pub fn io(path: &Path, error: std::io::Error) -> Self {
+        if error.kind() == std::io::ErrorKind::NotFound {
+            AppError::FileNotFound { path: path.display().to_string() }
+        } else {
+            AppError::Io { path: Some(path.display().to_string()), message: error.to_string() }
+        }
+    }
+
+    pub fn parse(message: impl Into<String>) -> Self {
+        AppError::Parse { file: None, line: None, message: message.into() }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation { message: message.into() }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound { message: message.into() }
+    }
+
+    /// Record which file a parse error came from; other errors are unchanged
+    pub fn in_file(self, path: &Path) -> Self {
+        match self {
+            AppError::Parse { file: None, line, message } => AppError::Parse {
+                file: Some(path.display().to_string()),
+                line,
+                message,
+            },
+            other => other,
+        }
+    }
+
+    /// Stable identifier of the error kind, as sent to the frontend
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::ToolMissing { .. } => "toolMissing",
+            AppError::ToolFailed { .. } => "toolFailed",
+            AppError::FileNotFound { .. } => "fileNotFound",
+            AppError::Parse { .. } => "parse",
+            AppError::UnsupportedVersion { .. } => "unsupportedVersion",
+            AppError::Validation { .. } => "validation",
+            AppError::NotFound { .. } => "notFound",
+            AppError::Io { .. } => "io",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::ToolMissing { message } => write!(f, "{}", message),
+            AppError::ToolFailed { tool, exit_code: Some(code), stderr } => {
+                write!(f, "{} failed with exit code {}: {}", tool, code, stderr)
+            }
+            AppError::ToolFailed { tool, exit_code: None, stderr } => write!(f, "{} failed: {}", tool, stderr),
+            AppError::FileNotFound { path } => write!(f, "File not found: {}", path),
+            AppError::Parse { file: Some(file), line: Some(line), message } => {
+                write!(f, "Failed to parse {} (line {}): {}", file, line, message)
+            }
+            AppError::Parse { file: Some(file), line: None, message } => {
+                write!(f, "Failed to parse {}: {}", file, message)
+            }
+            AppError::Parse { file: None, line: Some(line), message } => {
+                write!(f, "Parse error at line {}: {}", line, message)
+            }
+            AppError::Parse { file: None, line: None, message } => write!(f, "Parse error: {}", message),
+            AppError::UnsupportedVersion { format, version } => {
+                write!(f, "Unsupported {} version: {}", format, version)
+            }
+            AppError::Validation { message } | AppError::NotFound { message } => write!(f, "{}", message),
+            AppError::Io { path: Some(path), message } => write!(f, "I/O error on {}: {}", path, message),
+            AppError::Io { path: None, message } => write!(f, "I/O error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<ValueError> for AppError {
+    fn from(error: ValueError) -> Self {
+        AppError::validation(error.to_string())
+    }
+}
+
+impl serde::Serialize for AppError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            AppError::ToolMissing { .. } | AppError::Validation { .. } | AppError::NotFound { .. } => {}
+            AppError::ToolFailed { tool, exit_code, stderr } => {
+                map.serialize_entry("tool", tool)?;
+                map.serialize_entry("exitCode", exit_code)?;
+                map.serialize_entry("stderr", stderr)?;
+            }
+            AppError::FileNotFound { path } => map.serialize_entry("path", path)?,
+            AppError::Parse { file, line, .. } => {
+                map.serialize_entry("file", file)?;
+                map.serialize_entry("line", line)?;
+            }
+            AppError::UnsupportedVersion { format, version } => {
+                map.serialize_entry("format", format)?;
+                map.serialize_entry("version", version)?;
+            }
+            AppError::Io { path, .. } => map.serialize_entry("path", path)?,
+        }
+        map.end()
+    }
+}
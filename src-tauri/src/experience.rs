@@ -0,0 +1,264 @@
+use crate::document::{Document, NodeId};
+use crate::error::AppError;
+use crate::inventory::{self, InventoryOwner};
+use crate::party::{self, CharacterInfo};
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// What a stored experience value counts
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExperienceRole {
+    /// Experience earned in total, e.g. 9393
+    Total,
+    /// Experience earned since reaching the current level, e.g. 2893 at level 5
+    IntoLevel,
+}
+
+/// One stored experience value of a character
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ExperienceField {
+    pub resource: String,
+    pub node: NodeId,
+    pub attribute: String,
+    pub role: ExperienceRole,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CharacterExperience {
+    pub character: InventoryOwner,
+    /// The level the character has taken
+    pub level: Option<i64>,
+    /// Total experience, from the tree or else SaveInfo.json
+    pub experience: Option<i64>,
+    /// The level that experience is enough for
+    pub available_level: Option<u32>,
+    /// Total experience the next level needs, None at the level cap
+    pub next_level_experience: Option<i64>,
+    pub fields: Vec<ExperienceField>,
+    /// Experience keys of the character's SaveInfo.json "Active Party" entry
+    pub save_info_keys: Vec<String>,
+}
+
+/// How a character's experience should be set
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "to", rename_all = "camelCase")]
+pub enum ExperienceTarget {
+    /// This much total experience
+    Experience { experience: i64 },
+    /// Exactly the experience needed for this level
+    Level { level: u32 },
+}
+
+/// A new experience total for a character (by owner key)
+#[derive(Debug, serde::Deserialize)]
+pub struct ExperienceEdit {
+    pub character: String,
+    pub target: ExperienceTarget,
+}
+
+// ============================================================================
+// Experience Table
+// ============================================================================
+
+pub const MAX_LEVEL: u32 = 12;
+
+/// Total experience needed for each level, starting at level 1
+const LEVEL_EXPERIENCE: [i64; MAX_LEVEL as usize] = [
+    0, 300, 900, 2700, 6500, 13000, 21000, 30000, 42000, 56000, 76000, 100000,
+];
+
+/// Total experience needed for `level`, None outside 1 to the level cap
+pub fn experience_for_level(level: u32) -> Option<i64> {
+    level.checked_sub(1).and_then(|index| LEVEL_EXPERIENCE.get(index as usize)).copied()
+}
+
+/// The highest level `experience` is enough for
+pub fn level_for_experience(experience: i64) -> u32 {
+    LEVEL_EXPERIENCE.iter().take_while(|&&needed| needed <= experience).count().max(1) as u32
+}
+
+// ============================================================================
+// Known Names
+// ============================================================================
+
+const TOTAL_ATTRIBUTES: [&str; 4] = ["Experience", "TotalExperience", "Exp", "XP"];
+const INTO_LEVEL_ATTRIBUTES: [&str; 3] = ["CurrentLevelExperience", "LevelExperience", "ExperienceInLevel"];
+
+/// SaveInfo.json keys count experience when they mention it ("Experience",
+/// "XP", ...); those also mentioning the level or "current" count from the
+/// current level
+fn save_info_role(key: &str) -> Option<ExperienceRole> {
+    let key = key.to_lowercase();
+    if !key.contains("experience") && !key.split(|c: char| !c.is_alphanumeric()).any(|word| word == "xp") {
+        return None;
+    }
+    if key.contains("level") || key.contains("current") {
+        Some(ExperienceRole::IntoLevel)
+    } else {
+        Some(ExperienceRole::Total)
+    }
+}
+
+// ============================================================================
+// Locating Experience
+// ============================================================================
+
+/// Experience attributes on a character node or the nodes below it
+pub fn experience_fields(doc: &Document, character: NodeId, resource: &str) -> Vec<ExperienceField> {
+    let mut fields = Vec::new();
+    for node in inventory::own_nodes(doc, character) {
+        for attribute in &doc.node(node).attributes {
+            let role = if TOTAL_ATTRIBUTES.contains(&attribute.id.as_str()) {
+                ExperienceRole::Total
+            } else if INTO_LEVEL_ATTRIBUTES.contains(&attribute.id.as_str()) {
+                ExperienceRole::IntoLevel
+            } else {
+                continue;
+            };
+            if let Some(value) = attribute.value.as_int() {
+                fields.push(ExperienceField {
+                    resource: resource.to_string(),
+                    node,
+                    attribute: attribute.id.clone(),
+                    role,
+                    value,
+                });
+            }
+        }
+    }
+    fields
+}
+
+/// A character's experience from its tree fields and SaveInfo.json entry
+pub fn summarize(character: &CharacterInfo, fields: Vec<ExperienceField>, save_info: Option<&serde_json::Value>) -> CharacterExperience {
+    let save_info_values: Vec<(String, ExperienceRole, i64)> = save_info
+        .and_then(|info| party::save_info_entry(character, info)?.as_object())
+        .map(|entry| entry.iter()
+            .filter_map(|(key, value)| Some((key.clone(), save_info_role(key)?, value.as_i64()?)))
+            .collect())
+        .unwrap_or_default();
+
+    let tree = |role: ExperienceRole| fields.iter().find(|f| f.role == role).map(|f| f.value);
+    let listed = |role: ExperienceRole| save_info_values.iter().find(|(_, r, _)| *r == role).map(|(_, _, v)| *v);
+    let experience = tree(ExperienceRole::Total)
+        .or_else(|| listed(ExperienceRole::Total))
+        .or_else(|| {
+            let level = character.level.and_then(|level| experience_for_level(u32::try_from(level).ok()?))?;
+            Some(level + tree(ExperienceRole::IntoLevel).or_else(|| listed(ExperienceRole::IntoLevel))?)
+        });
+    let available_level = experience.map(level_for_experience);
+
+    CharacterExperience {
+        character: character.character.clone(),
+        level: character.level,
+        experience,
+        available_level,
+        next_level_experience: available_level.and_then(|level| experience_for_level(level + 1)),
+        fields,
+        save_info_keys: save_info_values.into_iter().map(|(key, _, _)| key).collect(),
+    }
+}
+
+// ============================================================================
+// Experience Modification
+// ============================================================================
+
+/// The total experience to set: within the level cap and not below what
+/// the character's current level needs
+pub fn plan_experience_edit(current: &CharacterExperience, target: &ExperienceTarget) -> Result<i64, AppError> {
+    if current.fields.is_empty() && current.save_info_keys.is_empty() {
+        return Err(AppError::not_found(format!("No experience found for {}", current.character.name)));
+    }
+
+    let max = experience_for_level(MAX_LEVEL).unwrap_or_default();
+    let experience = match *target {
+        ExperienceTarget::Experience { experience } => experience,
+        ExperienceTarget::Level { level } => experience_for_level(level).ok_or_else(|| AppError::validation(format!(
+            "Level must be 1 to {}, got {}", MAX_LEVEL, level
+        )))?,
+    };
+    if !(0..=max).contains(&experience) {
+        return Err(AppError::validation(format!(
+            "Experience must be 0 to {} (level cap {}), got {}", max, MAX_LEVEL, experience
+        )));
+    }
+
+    let needed = current.level
+        .and_then(|level| experience_for_level(u32::try_from(level).ok()?));
+    if let Some(needed) = needed.filter(|&needed| experience < needed) {
+        return Err(AppError::validation(format!(
+            "{} is level {} which needs at least {} experience",
+            current.character.name, current.level.unwrap_or_default(), needed
+        )));
+    }
+    Ok(experience)
+}
+
+/// The value a field of `role` stores for `experience` in total
+pub fn value_for(role: ExperienceRole, experience: i64) -> i64 {
+    match role {
+        ExperienceRole::Total => experience,
+        ExperienceRole::IntoLevel => {
+            experience - experience_for_level(level_for_experience(experience)).unwrap_or_default()
+        }
+    }
+}
+
+/// Set the experience keys of the character's "Active Party" entry; returns
+/// how many were changed
+pub fn update_save_info(character: &CharacterInfo, save_info: &mut serde_json::Value, experience: i64) -> usize {
+    let Some(entry) = party::save_info_entry_mut(character, save_info).and_then(|e| e.as_object_mut()) else {
+        return 0;
+    };
+    let mut changed = 0;
+    for (key, value) in entry.iter_mut() {
+        if let Some(role) = save_info_role(key).filter(|_| value.is_i64()) {
+            *value = serde_json::Value::from(value_for(role, experience));
+            changed += 1;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_and_experience_match_the_table() {
+        assert_eq!(experience_for_level(1), Some(0));
+        assert_eq!(experience_for_level(2), Some(300));
+        assert_eq!(experience_for_level(5), Some(6500));
+        assert_eq!(experience_for_level(12), Some(100000));
+        assert_eq!(experience_for_level(0), None);
+        assert_eq!(experience_for_level(13), None);
+
+        assert_eq!(level_for_experience(0), 1);
+        assert_eq!(level_for_experience(299), 1);
+        assert_eq!(level_for_experience(300), 2);
+        assert_eq!(level_for_experience(9393), 5);
+        assert_eq!(level_for_experience(99999), 11);
+        assert_eq!(level_for_experience(250000), 12);
+        assert_eq!(level_for_experience(-5), 1);
+    }
+
+    #[test]
+    fn into_level_values_count_from_the_current_level() {
+        assert_eq!(value_for(ExperienceRole::Total, 9393), 9393);
+        assert_eq!(value_for(ExperienceRole::IntoLevel, 9393), 2893);
+        assert_eq!(value_for(ExperienceRole::IntoLevel, 900), 0);
+        assert_eq!(value_for(ExperienceRole::IntoLevel, 100000), 0);
+    }
+
+    #[test]
+    fn save_info_keys_are_sorted_by_what_they_count() {
+        assert_eq!(save_info_role("Experience"), Some(ExperienceRole::Total));
+        assert_eq!(save_info_role("Current Level XP"), Some(ExperienceRole::IntoLevel));
+        assert_eq!(save_info_role("Level"), None);
+        assert_eq!(save_info_role("Max XPosition"), None);
+    }
+}
@@ -0,0 +1,351 @@
+use crate::document::{AttributeType, Document, NodeId};
+use crate::error::AppError;
+use crate::inventory::{self, InventoryOwner};
+use crate::party::{self, HP_ATTRIBUTES, MAX_HP_ATTRIBUTES};
+use crate::values::AttributeValue;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// What a stored health value means
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthRole {
+    Hp,
+    MaxHp,
+    /// Set while the character is dead
+    Dead,
+    /// Set while the character is downed (0 HP, rolling death saves)
+    Downed,
+    DeathSaveSuccesses,
+    DeathSaveFailures,
+}
+
+/// One stored health value; flags read as 1 or 0
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HealthField {
+    pub resource: String,
+    pub node: NodeId,
+    pub attribute: String,
+    pub role: HealthRole,
+    pub value: i64,
+}
+
+/// A downed, dying or dead status applied to the character
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HealthStatus {
+    pub resource: String,
+    pub node: NodeId,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CharacterHealth {
+    pub character: InventoryOwner,
+    pub hp: Option<i64>,
+    pub max_hp: Option<i64>,
+    pub dead: bool,
+    pub downed: bool,
+    pub death_save_successes: Option<i64>,
+    pub death_save_failures: Option<i64>,
+    pub fields: Vec<HealthField>,
+    pub statuses: Vec<HealthStatus>,
+}
+
+/// New current and/or max HP for a character (by owner key)
+#[derive(Debug, serde::Deserialize)]
+pub struct HitPointEdit {
+    pub character: String,
+    pub hp: Option<i64>,
+    pub max_hp: Option<i64>,
+}
+
+/// Everything a revive changes
+#[derive(Debug, Default)]
+pub struct RevivePlan {
+    pub changes: Vec<(HealthField, i64)>,
+    pub statuses: Vec<HealthStatus>,
+}
+
+// ============================================================================
+// Known Names
+// ============================================================================
+
+/// Flags only; DeathState and DeathType are enums, not dead flags
+const DEAD_ATTRIBUTES: [&str; 2] = ["IsDead", "Dead"];
+const DOWNED_ATTRIBUTES: [&str; 4] = ["IsDowned", "Downed", "IsKnockedOut", "KnockedOut"];
+const DEATH_SAVE_SUCCESS_ATTRIBUTES: [&str; 2] = ["DeathSavingThrowSuccesses", "DeathSaveSuccesses"];
+const DEATH_SAVE_FAILURE_ATTRIBUTES: [&str; 2] = ["DeathSavingThrowFailures", "DeathSaveFailures"];
+
+/// Status ids that keep a character down or dead
+const DEATH_STATUSES: [&str; 4] = ["DOWNED", "DYING", "KNOCKED_OUT", "DEAD"];
+const STATUS_ID_ATTRIBUTES: [&str; 2] = ["StatusId", "StatusID"];
+
+fn role_of(id: &str) -> Option<HealthRole> {
+    let roles: [(&[&str], HealthRole); 6] = [
+        (&HP_ATTRIBUTES, HealthRole::Hp),
+        (&MAX_HP_ATTRIBUTES, HealthRole::MaxHp),
+        (&DEAD_ATTRIBUTES, HealthRole::Dead),
+        (&DOWNED_ATTRIBUTES, HealthRole::Downed),
+        (&DEATH_SAVE_SUCCESS_ATTRIBUTES, HealthRole::DeathSaveSuccesses),
+        (&DEATH_SAVE_FAILURE_ATTRIBUTES, HealthRole::DeathSaveFailures),
+    ];
+    roles.iter().find(|(ids, _)| ids.contains(&id)).map(|&(_, role)| role)
+}
+
+// ============================================================================
+// Locating Health
+// ============================================================================
+
+/// Health attributes and death statuses on a character node or the nodes
+/// below it (its health component), skipping its items
+pub fn health_of(doc: &Document, character: NodeId, resource: &str) -> (Vec<HealthField>, Vec<HealthStatus>) {
+    let mut fields = Vec::new();
+    let mut statuses = Vec::new();
+    for node in inventory::own_nodes(doc, character) {
+        for attribute in &doc.node(node).attributes {
+            let Some(role) = role_of(&attribute.id) else {
+                continue;
+            };
+            let value = match attribute.value.as_bool() {
+                Some(flag) => Some(flag as i64),
+                None => attribute.value.as_int(),
+            };
+            if let Some(value) = value {
+                fields.push(HealthField {
+                    resource: resource.to_string(),
+                    node,
+                    attribute: attribute.id.clone(),
+                    role,
+                    value,
+                });
+            }
+        }
+
+        if node == character {
+            continue;
+        }
+        let status = STATUS_ID_ATTRIBUTES.iter()
+            .find_map(|id| doc.node(node).attribute(id)?.value.as_str());
+        if let Some(status) = status.filter(|s| DEATH_STATUSES.iter().any(|d| d.eq_ignore_ascii_case(s))) {
+            statuses.push(HealthStatus { resource: resource.to_string(), node, status: status.to_string() });
+        }
+    }
+    (fields, statuses)
+}
+
+/// A character's health, each value taken from the first resource storing it
+pub fn summarize(character: InventoryOwner, fields: Vec<HealthField>, statuses: Vec<HealthStatus>) -> CharacterHealth {
+    let first = |role: HealthRole| fields.iter().find(|f| f.role == role).map(|f| f.value);
+    let set = |role: HealthRole| fields.iter().any(|f| f.role == role && f.value != 0);
+    let status = |names: &[&str]| statuses.iter().any(|s| names.iter().any(|n| n.eq_ignore_ascii_case(&s.status)));
+    CharacterHealth {
+        character,
+        hp: first(HealthRole::Hp),
+        max_hp: first(HealthRole::MaxHp),
+        dead: set(HealthRole::Dead) || status(&["DEAD"]),
+        downed: set(HealthRole::Downed) || status(&["DOWNED", "DYING", "KNOCKED_OUT"]),
+        death_save_successes: first(HealthRole::DeathSaveSuccesses),
+        death_save_failures: first(HealthRole::DeathSaveFailures),
+        fields,
+        statuses,
+    }
+}
+
+// ============================================================================
+// Health Modification
+// ============================================================================
+
+/// New values for the HP and max HP fields. Max HP must be at least 1 and
+/// HP from 1 to max HP; lowering max HP below the current HP lowers HP too.
+pub fn plan_hit_points(current: &CharacterHealth, edit: &HitPointEdit) -> Result<Vec<(HealthField, i64)>, AppError> {
+    if edit.hp.is_none() && edit.max_hp.is_none() {
+        return Err(AppError::validation("Give HP, max HP or both"));
+    }
+    if let Some(max_hp) = edit.max_hp.filter(|&max_hp| max_hp < 1) {
+        return Err(AppError::validation(format!("Max HP must be at least 1, got {}", max_hp)));
+    }
+    let max_hp = edit.max_hp.or(current.max_hp);
+    let hp = match edit.hp {
+        Some(hp) => Some(check_hp(hp, max_hp)?),
+        None => current.hp.zip(max_hp).filter(|(hp, max)| hp > max).map(|(_, max)| max),
+    };
+
+    let mut changes = Vec::new();
+    for (role, value) in [(HealthRole::Hp, hp), (HealthRole::MaxHp, edit.max_hp)] {
+        let Some(value) = value else {
+            continue;
+        };
+        let before = changes.len();
+        changes.extend(current.fields.iter().filter(|f| f.role == role).map(|f| (f.clone(), value)));
+        if changes.len() == before {
+            return Err(AppError::not_found(format!(
+                "No {} field found for {}", if role == HealthRole::Hp { "HP" } else { "max HP" }, current.character.name
+            )));
+        }
+    }
+    Ok(changes)
+}
+
+/// Clear the dead and downed flags, death saving throws and death statuses,
+/// and bring HP up to `hp` (or max HP) when it is 0
+pub fn plan_revive(current: &CharacterHealth, hp: Option<i64>) -> Result<RevivePlan, AppError> {
+    let at_zero = current.hp.is_some_and(|hp| hp <= 0);
+    if !current.dead && !current.downed && !at_zero {
+        return Err(AppError::validation(format!("{} is not downed or dead", current.character.name)));
+    }
+    let hp = match hp {
+        Some(hp) => Some(check_hp(hp, current.max_hp)?),
+        None if at_zero => Some(current.max_hp.unwrap_or(1).max(1)),
+        None => None,
+    };
+
+    let mut plan = RevivePlan { statuses: current.statuses.clone(), ..RevivePlan::default() };
+    for field in &current.fields {
+        let value = match field.role {
+            HealthRole::Hp => match hp {
+                Some(hp) => hp,
+                None => continue,
+            },
+            HealthRole::MaxHp => continue,
+            _ => 0,
+        };
+        if field.value != value {
+            plan.changes.push((field.clone(), value));
+        }
+    }
+    Ok(plan)
+}
+
+fn check_hp(hp: i64, max_hp: Option<i64>) -> Result<i64, AppError> {
+    if hp < 1 {
+        return Err(AppError::validation(format!("HP must be at least 1, got {}", hp)));
+    }
+    if let Some(max_hp) = max_hp.filter(|&max_hp| hp > max_hp) {
+        return Err(AppError::validation(format!("HP cannot exceed max HP ({}), got {}", max_hp, hp)));
+    }
+    Ok(hp)
+}
+
+/// Check that a health field can take `value`; bool flags only take 0 or 1
+pub fn check_field(doc: &Document, field: &HealthField, value: i64) -> Result<(), AppError> {
+    let attribute = doc.contains(field.node)
+        .then(|| doc.node(field.node).attribute(&field.attribute))
+        .flatten();
+    match attribute {
+        Some(attribute) if attribute.attr_type == AttributeType::Bool => match value {
+            0 | 1 => Ok(()),
+            _ => Err(AppError::validation(format!("{} is a flag, got {}", field.attribute, value))),
+        },
+        _ => party::check_int_field(doc, field.node, &field.attribute, value),
+    }
+}
+
+pub fn write_field(doc: &mut Document, field: &HealthField, value: i64) -> Result<(), AppError> {
+    check_field(doc, field, value)?;
+    let attribute = doc.node_mut(field.node).attribute_mut(&field.attribute);
+    match attribute {
+        Some(attribute) if attribute.attr_type == AttributeType::Bool => attribute.set(AttributeValue::Bool(value != 0))?,
+        _ => party::write_int_field(doc, field.node, &field.attribute, value)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsx;
+
+    /// A downed character at 0 of 30 HP with a failed death save, a DOWNED
+    /// status, a DeathState enum and a potion holding its own HP
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<save>
+	<version major="4" minor="0" revision="9" build="331" />
+	<region id="Characters">
+		<node id="Characters">
+			<children>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="aaaaaaaa-0000-0000-0000-000000000001" />
+					<attribute id="Name" type="LSString" value="Tav" />
+					<children>
+						<node id="Health">
+							<attribute id="CurrentHP" type="int32" value="0" />
+							<attribute id="MaxHP" type="int32" value="30" />
+							<attribute id="IsDowned" type="bool" value="True" />
+							<attribute id="DeathState" type="uint8" value="2" />
+							<attribute id="DeathSaveFailures" type="uint8" value="1" />
+						</node>
+						<node id="Status">
+							<attribute id="StatusId" type="FixedString" value="DOWNED" />
+						</node>
+						<node id="InventoryList">
+							<children>
+								<node id="Item">
+									<attribute id="UUID" type="FixedString" value="potion-1" />
+									<attribute id="HP" type="int32" value="5" />
+								</node>
+							</children>
+						</node>
+					</children>
+				</node>
+			</children>
+		</node>
+	</region>
+</save>"#;
+
+    fn health() -> CharacterHealth {
+        let doc = lsx::read(SAMPLE).unwrap();
+        let character = doc.find_nodes("Character")[0];
+        let (fields, statuses) = health_of(&doc, character, "Globals.lsf");
+        summarize(inventory::describe_entity(&doc, character), fields, statuses)
+    }
+
+    fn edit(hp: Option<i64>, max_hp: Option<i64>) -> HitPointEdit {
+        HitPointEdit { character: "Tav".to_string(), hp, max_hp }
+    }
+
+    fn values(changes: &[(HealthField, i64)]) -> Vec<(&str, i64)> {
+        changes.iter().map(|(f, v)| (f.attribute.as_str(), *v)).collect()
+    }
+
+    #[test]
+    fn health_is_read_from_the_character_only() {
+        let health = health();
+        assert_eq!((health.hp, health.max_hp), (Some(0), Some(30)));
+        assert!(health.downed && !health.dead);
+        assert_eq!(health.death_save_failures, Some(1));
+        assert_eq!(health.statuses.len(), 1);
+        assert!(health.fields.iter().all(|f| f.attribute != "DeathState" && f.attribute != "HP"));
+    }
+
+    #[test]
+    fn hit_points_stay_within_max_hp() {
+        let health = health();
+        assert_eq!(values(&plan_hit_points(&health, &edit(Some(12), None)).unwrap()), vec![("CurrentHP", 12)]);
+        assert_eq!(values(&plan_hit_points(&health, &edit(None, Some(40))).unwrap()), vec![("MaxHP", 40)]);
+        assert!(plan_hit_points(&health, &edit(Some(31), None)).is_err());
+        assert!(plan_hit_points(&health, &edit(Some(0), None)).is_err());
+        assert!(plan_hit_points(&health, &edit(None, Some(0))).is_err());
+        assert!(plan_hit_points(&health, &edit(None, None)).is_err());
+
+        let mut hurt = health.clone();
+        hurt.hp = Some(25);
+        assert_eq!(values(&plan_hit_points(&hurt, &edit(None, Some(20))).unwrap()), vec![("CurrentHP", 20), ("MaxHP", 20)]);
+    }
+
+    #[test]
+    fn revives_clear_death_state_and_restore_hp() {
+        let health = health();
+        let plan = plan_revive(&health, None).unwrap();
+        assert_eq!(values(&plan.changes), vec![("CurrentHP", 30), ("IsDowned", 0), ("DeathSaveFailures", 0)]);
+        assert_eq!(plan.statuses.len(), 1);
+        assert_eq!(values(&plan_revive(&health, Some(10)).unwrap().changes)[0], ("CurrentHP", 10));
+        assert!(plan_revive(&health, Some(31)).is_err());
+
+        let mut standing = health.clone();
+        standing.hp = Some(30);
+        standing.downed = false;
+        assert!(plan_revive(&standing, None).is_err());
+    }
+}
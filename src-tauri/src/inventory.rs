@@ -0,0 +1,1092 @@
+use std::collections::{HashMap, HashSet};
+use crate::document::{Attribute, AttributeType, Document, NodeId, Subtree};
+use crate::error::AppError;
+use crate::values::AttributeValue;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OwnerKind {
+    Character,
+    CampChest,
+    /// A bag, chest or other item holding its own inventory
+    Container,
+    Unknown,
+}
+
+/// The entity an inventory belongs to
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct InventoryOwner {
+    pub kind: OwnerKind,
+    pub uuid: Option<String>,
+    pub name: String,
+    /// Origin character (Shadowheart, Astarion, ...) when the UUID is a known one
+    pub origin: Option<String>,
+}
+
+/// An inventory list node, its owner and the items directly in it
+#[derive(Debug, Clone)]
+pub struct Inventory {
+    pub node: NodeId,
+    /// Node of the owning entity, when one was found
+    pub entity: Option<NodeId>,
+    pub owner: InventoryOwner,
+    pub items: Vec<NodeId>,
+}
+
+/// One item in an inventory, with the items inside it if it is a container
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ItemInfo {
+    /// Resource file (relative to the session workspace) holding the item
+    pub resource: String,
+    /// Item node within that resource
+    pub node: NodeId,
+    pub uuid: Option<String>,
+    pub template: Option<String>,
+    pub stats: Option<String>,
+    pub name: String,
+    /// Stack size; items without an Amount are a single item
+    pub amount: i64,
+    pub slot: Option<i64>,
+    pub equipped: bool,
+    /// Whether the stack size can be edited
+    pub stackable: bool,
+    /// Items in the container's own inventory (bags inside bags)
+    pub contents: Vec<ItemInfo>,
+}
+
+/// How a stack edit picks its items
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "by", rename_all = "camelCase")]
+pub enum ItemSelector {
+    /// The one item with this UUID
+    Uuid { uuid: String },
+    /// Every item created from this template
+    Template { template: String },
+}
+
+/// Everything one owner carries, nested by container
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OwnerInventory {
+    pub owner: InventoryOwner,
+    pub items: Vec<ItemInfo>,
+}
+
+// ============================================================================
+// Known Names
+// ============================================================================
+
+/// Nodes holding a list of `Item` children
+const INVENTORY_LIST_NAMES: [&str; 2] = ["InventoryList", "ItemList"];
+
+/// Attributes that identify an entity other nodes can refer to
+const ENTITY_ID_ATTRIBUTES: [&str; 4] = ["UUID", "GUID", "Guid", "MapKey"];
+
+/// Attributes on an inventory list that point at its owner
+const OWNER_LINK_ATTRIBUTES: [&str; 5] = ["Owner", "OwnerUUID", "InventoryOwner", "ParentEntity", "Parent"];
+
+/// Attributes naming the template an entity was created from
+const TEMPLATE_ATTRIBUTES: [&str; 4] = ["TemplateID", "Template", "OriginalTemplate", "TemplateName"];
+
+/// Attributes that may hold a readable entity name, in order of preference
+const NAME_ATTRIBUTES: [&str; 5] = ["CustomDisplayName", "DisplayName", "Name", "CharacterName", "ItemName"];
+
+/// Attribute holding an item's position in its inventory
+const SLOT_ATTRIBUTE: &str = "Slot";
+
+/// Attributes naming the stats entry of an item
+const STATS_ATTRIBUTES: [&str; 3] = ["Stats", "StatsId", "StatsEntry"];
+
+/// Flags marking an item as worn or wielded
+const EQUIPPED_ATTRIBUTES: [&str; 2] = ["Equipped", "IsEquipped"];
+
+/// Node name fragments of equipment and hotbar slots (matched ignoring case)
+const SLOT_NODE_MARKERS: [&str; 3] = ["equip", "slot", "hotbar"];
+
+/// Template or name fragments of items that stack: gold, potions and other
+/// consumables, arrows, scrolls and camp supplies
+const STACKABLE_MARKERS: [&str; 10] = [
+    "LOOT_Gold", "OBJ_Gold", "CONS_", "OBJ_Potion", "ARR_", "OBJ_Arrow", "OBJ_Scroll", "SCROLL_", "CampSupply", "Camp_Supply",
+];
+
+/// Origin characters by UUID (see FIELD_MAPPING.md)
+const ORIGINS: [(&str, &str); 6] = [
+    ("3ed74f06-3c60-42dc-83f6-f034cb47c679", "Shadowheart"),
+    ("c7c13742-bacd-460a-8f65-f864fe41f255", "Astarion"),
+    ("ad9af97d-75da-406a-ae13-7071c563f604", "Gale"),
+    ("58a69333-40bf-8358-1d17-fff240d7b12d", "Lae'zel"),
+    ("c774d764-4a17-48dc-b470-32ace9ce447d", "Wyll"),
+    ("2c76687d-93a2-477b-8b18-8a14b549304c", "Karlach"),
+];
+
+// ============================================================================
+// Inventory Resolution
+// ============================================================================
+
+impl InventoryOwner {
+    fn unknown() -> Self {
+        InventoryOwner { kind: OwnerKind::Unknown, uuid: None, name: "Unknown owner".to_string(), origin: None }
+    }
+
+    /// Identity used to merge the same owner across Globals and levels
+    pub fn key(&self) -> String {
+        self.uuid.clone().unwrap_or_else(|| self.name.clone())
+    }
+}
+
+/// Every inventory list in the document with its resolved owner, in document order
+pub fn inventories(doc: &Document) -> Vec<Inventory> {
+    let index = entity_index(doc);
+    let mut lists: Vec<NodeId> = INVENTORY_LIST_NAMES.iter()
+        .flat_map(|name| doc.find_nodes(name))
+        .collect();
+    lists.sort_unstable();
+
+    lists.into_iter()
+        .map(|list| {
+            let entity = owning_entity(doc, list, &index);
+            Inventory {
+                node: list,
+                entity,
+                owner: describe_owner(doc, entity),
+                items: doc.children_named(list, "Item").collect(),
+            }
+        })
+        .collect()
+}
+
+/// Inventory lists grouped by the entity owning them
+fn lists_by_entity(inventories: &[Inventory]) -> HashMap<NodeId, Vec<&Inventory>> {
+    let mut lists: HashMap<NodeId, Vec<&Inventory>> = HashMap::new();
+    for inventory in inventories {
+        if let Some(entity) = inventory.entity {
+            lists.entry(entity).or_default().push(inventory);
+        }
+    }
+    lists
+}
+
+/// Text of an attribute for matching: strings as-is, GUIDs formatted
+fn attribute_text(doc: &Document, node: NodeId, id: &str) -> Option<String> {
+    value_text(doc.node(node).attribute(id)?)
+}
+
+fn value_text(attribute: &Attribute) -> Option<String> {
+    let text = match &attribute.value {
+        AttributeValue::String(s) => s.clone(),
+        AttributeValue::Guid(_) => attribute.value.to_string(),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// First non-empty entity id of a node
+fn entity_id(doc: &Document, node: NodeId) -> Option<String> {
+    ENTITY_ID_ATTRIBUTES.iter().find_map(|id| attribute_text(doc, node, id))
+}
+
+/// Map every entity id (lowercased) to the node carrying it
+fn entity_index(doc: &Document) -> HashMap<String, NodeId> {
+    let mut index = HashMap::new();
+    for node in doc.tree_nodes() {
+        for id in ENTITY_ID_ATTRIBUTES {
+            if let Some(value) = attribute_text(doc, node, id) {
+                index.entry(value.to_lowercase()).or_insert(node);
+            }
+        }
+    }
+    index
+}
+
+/// The entity owning an inventory list: an explicit owner link on the list
+/// if it resolves, otherwise the closest enclosing character, item or node
+/// carrying an entity id
+fn owning_entity(doc: &Document, list: NodeId, index: &HashMap<String, NodeId>) -> Option<NodeId> {
+    let linked = OWNER_LINK_ATTRIBUTES.iter()
+        .filter_map(|id| attribute_text(doc, list, id))
+        .find_map(|value| index.get(&value.to_lowercase()).copied());
+    if linked.is_some() {
+        return linked;
+    }
+
+    let mut current = doc.parent(list);
+    while let Some(node) = current {
+        let name = doc.node(node).name.as_str();
+        if name == "Character" || name == "Item" || entity_id(doc, node).is_some() {
+            return Some(node);
+        }
+        current = doc.parent(node);
+    }
+    None
+}
+
+/// Origin name if any id or template attribute contains a known origin UUID
+fn origin_of(doc: &Document, node: NodeId) -> Option<&'static str> {
+    let values: Vec<String> = ENTITY_ID_ATTRIBUTES.iter()
+        .chain(TEMPLATE_ATTRIBUTES.iter())
+        .filter_map(|id| attribute_text(doc, node, id))
+        .map(|value| value.to_lowercase())
+        .collect();
+    ORIGINS.iter()
+        .find(|(uuid, _)| values.iter().any(|value| value.contains(uuid)))
+        .map(|&(_, name)| name)
+}
+
+/// Whether any string attribute of the node mentions the camp chest
+fn is_camp_chest(doc: &Document, node: NodeId) -> bool {
+    doc.node(node).attributes.iter()
+        .filter_map(|a| a.value.as_str())
+        .any(|value| value.to_lowercase().contains("campchest"))
+}
+
+/// Identity of an entity node (character, container, ...), as reported for inventory owners
+pub fn describe_entity(doc: &Document, node: NodeId) -> InventoryOwner {
+    describe_owner(doc, Some(node))
+}
+
+fn describe_owner(doc: &Document, node: Option<NodeId>) -> InventoryOwner {
+    let Some(node) = node else {
+        return InventoryOwner::unknown();
+    };
+
+    let uuid = entity_id(doc, node);
+    let origin = origin_of(doc, node);
+    let node_name = doc.node(node).name.as_str();
+    let kind = if origin.is_some() || node_name == "Character" {
+        OwnerKind::Character
+    } else if is_camp_chest(doc, node) {
+        OwnerKind::CampChest
+    } else if node_name == "Item" {
+        OwnerKind::Container
+    } else {
+        OwnerKind::Unknown
+    };
+
+    let name = match (origin, kind) {
+        (Some(origin), _) => origin.to_string(),
+        (None, OwnerKind::CampChest) => "Camp Chest".to_string(),
+        _ => NAME_ATTRIBUTES.iter()
+            .find_map(|id| attribute_text(doc, node, id))
+            .or_else(|| uuid.as_ref().map(|uuid| format!("{} {}", node_name, uuid)))
+            .unwrap_or_else(|| node_name.to_string()),
+    };
+
+    InventoryOwner { kind, uuid, name, origin: origin.map(str::to_string) }
+}
+
+// ============================================================================
+// Item Listing
+// ============================================================================
+
+/// Every item per owner, with containers' contents nested under them instead
+/// of listed as owners of their own. `resource` is recorded on each item.
+pub fn list_items(doc: &Document, resource: &str) -> Vec<OwnerInventory> {
+    let inventories = inventories(doc);
+    let held: HashSet<NodeId> = inventories.iter().flat_map(|inventory| inventory.items.iter().copied()).collect();
+    let by_entity = lists_by_entity(&inventories);
+
+    let mut owners: Vec<OwnerInventory> = Vec::new();
+    for inventory in &inventories {
+        // Lists owned by an item that is itself in an inventory are listed as its contents
+        if inventory.entity.is_some_and(|entity| held.contains(&entity)) {
+            continue;
+        }
+        let items = inventory.items.iter()
+            .map(|&item| describe_item(doc, resource, item, &by_entity, &mut vec![inventory.node]))
+            .collect();
+        merge_inventories(&mut owners, vec![OwnerInventory { owner: inventory.owner.clone(), items }]);
+    }
+    owners
+}
+
+/// Add inventories from another resource or list, merging the same owner
+pub fn merge_inventories(into: &mut Vec<OwnerInventory>, other: Vec<OwnerInventory>) {
+    for inventory in other {
+        let key = inventory.owner.key();
+        match into.iter_mut().find(|existing| existing.owner.key() == key) {
+            Some(existing) => existing.items.extend(inventory.items),
+            None => into.push(inventory),
+        }
+    }
+}
+
+/// An item's details and contents; `visiting` holds the lists on the current
+/// path so a container cannot end up inside itself
+fn describe_item(
+    doc: &Document,
+    resource: &str,
+    item: NodeId,
+    by_entity: &HashMap<NodeId, Vec<&Inventory>>,
+    visiting: &mut Vec<NodeId>,
+) -> ItemInfo {
+    let own = own_nodes(doc, item);
+    let text = |ids: &[&str]| ids.iter().find_map(|id| own.iter().find_map(|&node| attribute_text(doc, node, id)));
+    let int = |id: &str| own.iter().find_map(|&node| doc.node(node).attribute(id)?.value.as_int());
+
+    let uuid = entity_id(doc, item);
+    let template = item_template(doc, item);
+    let equipped = EQUIPPED_ATTRIBUTES.iter().any(|id| {
+        own.iter()
+            .filter_map(|&node| doc.node(node).attribute(id))
+            .any(|a| a.value == AttributeValue::Bool(true) || a.value.as_int().is_some_and(|v| v != 0))
+    });
+
+    let mut contents = Vec::new();
+    for inventory in by_entity.get(&item).into_iter().flatten() {
+        if visiting.contains(&inventory.node) {
+            continue;
+        }
+        visiting.push(inventory.node);
+        for &child in &inventory.items {
+            contents.push(describe_item(doc, resource, child, by_entity, visiting));
+        }
+        visiting.pop();
+    }
+
+    ItemInfo {
+        resource: resource.to_string(),
+        node: item,
+        name: text(&NAME_ATTRIBUTES)
+            .or_else(|| template.clone())
+            .unwrap_or_else(|| format!("Item {}", item)),
+        uuid,
+        template,
+        stats: item_stats(doc, item),
+        amount: int("Amount").unwrap_or(1),
+        slot: int(SLOT_ATTRIBUTE),
+        equipped,
+        stackable: is_stackable(doc, item),
+        contents,
+    }
+}
+
+/// Whether a node is an item still in the document
+pub fn is_item(doc: &Document, node: NodeId) -> bool {
+    doc.contains(node) && doc.node(node).name == "Item"
+}
+
+/// Items in any inventory matching the selector, containers' contents included
+pub fn find_items(doc: &Document, selector: &ItemSelector) -> Vec<NodeId> {
+    inventories(doc).into_iter()
+        .flat_map(|inventory| inventory.items)
+        .filter(|&item| match selector {
+            ItemSelector::Uuid { uuid } => entity_id(doc, item).is_some_and(|id| id.eq_ignore_ascii_case(uuid)),
+            ItemSelector::Template { template } => own_nodes(doc, item).into_iter().any(|node| {
+                TEMPLATE_ATTRIBUTES.iter()
+                    .filter_map(|id| attribute_text(doc, node, id))
+                    .any(|value| value.eq_ignore_ascii_case(template))
+            }),
+        })
+        .collect()
+}
+
+/// First attribute with this id on the item itself, not on items nested in it
+pub fn item_attribute<'a>(doc: &'a Document, item: NodeId, id: &str) -> Option<&'a Attribute> {
+    own_nodes(doc, item).into_iter().find_map(|node| doc.node(node).attribute(id))
+}
+
+/// Template id of an item, from its own template attributes
+pub fn item_template(doc: &Document, item: NodeId) -> Option<String> {
+    let own = own_nodes(doc, item);
+    TEMPLATE_ATTRIBUTES.iter().find_map(|id| own.iter().find_map(|&node| attribute_text(doc, node, id)))
+}
+
+/// Stats id of an item, from its own stats attributes
+pub fn item_stats(doc: &Document, item: NodeId) -> Option<String> {
+    let own = own_nodes(doc, item);
+    STATS_ATTRIBUTES.iter().find_map(|id| own.iter().find_map(|&node| attribute_text(doc, node, id)))
+}
+
+/// Whether an item is a stack: it has an Amount, or its template or name
+/// is one of the known stackable kinds
+pub fn is_stackable(doc: &Document, item: NodeId) -> bool {
+    item_attribute(doc, item, "Amount").is_some()
+        || own_nodes(doc, item).into_iter()
+            .flat_map(|node| doc.node(node).attributes.iter())
+            .filter_map(|a| a.value.as_str())
+            .any(|value| STACKABLE_MARKERS.iter().any(|marker| value.contains(marker)))
+}
+
+/// A node and the nodes below it that describe it (an item or character
+/// itself), leaving out nested items and inventory lists
+pub fn own_nodes(doc: &Document, entity: NodeId) -> Vec<NodeId> {
+    let mut result = vec![entity];
+    let mut stack: Vec<NodeId> = doc.children(entity).iter().rev().copied().collect();
+    while let Some(node) = stack.pop() {
+        let name = doc.node(node).name.as_str();
+        if name == "Item" || INVENTORY_LIST_NAMES.contains(&name) {
+            continue;
+        }
+        result.push(node);
+        stack.extend(doc.children(node).iter().rev());
+    }
+    result
+}
+
+// ============================================================================
+// Item Placement
+// ============================================================================
+
+/// A detached copy of an item and everything it holds, ready to be placed
+/// in an inventory list
+#[derive(Debug, Clone)]
+pub struct ItemCopy {
+    pub item: Subtree,
+    /// Content lists stored outside the item and linked to it by owner, with
+    /// their parent node in the source document
+    pub external: Vec<(NodeId, Subtree)>,
+}
+
+impl ItemCopy {
+    /// Whether the copy can be moved to another document
+    pub fn check_movable(&self) -> Result<(), AppError> {
+        if self.external.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::validation(
+                "Containers whose contents are stored outside the item can only be moved within the same resource",
+            ))
+        }
+    }
+}
+
+impl From<Subtree> for ItemCopy {
+    fn from(item: Subtree) -> Self {
+        ItemCopy { item, external: Vec::new() }
+    }
+}
+
+/// Copy an item with its nested contents, including content lists that link
+/// to it (or to a bag inside it) from elsewhere in the document
+pub fn copy_item(doc: &Document, item: NodeId) -> Result<ItemCopy, AppError> {
+    copy_from(doc, &inventories(doc), item)
+}
+
+fn copy_from(doc: &Document, inventories: &[Inventory], item: NodeId) -> Result<ItemCopy, AppError> {
+    if !is_item(doc, item) {
+        return Err(AppError::validation(format!("Node {} is not an item", item)));
+    }
+
+    let inside: HashSet<NodeId> = std::iter::once(item).chain(doc.descendants(item)).collect();
+    let external = content_lists(inventories, item).into_iter()
+        .filter(|list| !inside.contains(list))
+        .filter_map(|list| Some((doc.parent(list)?, doc.subtree(list))))
+        .collect();
+
+    Ok(ItemCopy { item: doc.subtree(item), external })
+}
+
+/// Inventory lists owned by an item or by any container inside it, wherever
+/// they are stored
+fn content_lists(inventories: &[Inventory], item: NodeId) -> Vec<NodeId> {
+    let by_entity = lists_by_entity(inventories);
+    let mut lists = Vec::new();
+    let mut seen = HashSet::new();
+    let mut containers = vec![item];
+    while let Some(container) = containers.pop() {
+        for inventory in by_entity.get(&container).into_iter().flatten() {
+            if seen.insert(inventory.node) {
+                lists.push(inventory.node);
+                containers.extend(inventory.items.iter().copied());
+            }
+        }
+    }
+    lists
+}
+
+/// Place a copy in an inventory list as a new entity. Every entity inside it
+/// gets a fresh UUID (links between them follow), the item takes the next
+/// free slot, links to `owner` and is unequipped, and every reference to the
+/// originals elsewhere in the document is copied for the new entities (see
+/// `copy_references`). `same_document` says whether the copy was taken from `doc`, which is
+/// needed to place content lists stored outside the item.
+pub fn place_item(
+    doc: &mut Document,
+    list: NodeId,
+    owner: &InventoryOwner,
+    copy: &ItemCopy,
+    same_document: bool,
+) -> Result<NodeId, AppError> {
+    if !doc.contains(list) || !INVENTORY_LIST_NAMES.contains(&doc.node(list).name.as_str()) {
+        return Err(AppError::validation(format!("Node {} is not an inventory list", list)));
+    }
+    if !same_document && !copy.external.is_empty() {
+        return Err(AppError::validation(
+            "Containers whose contents are stored outside the item can only be copied within the same resource",
+        ));
+    }
+
+    // Fresh ids for every entity in the copy, keyed by the lowercased old id
+    let mut ids: Vec<(String, String)> = Vec::new();
+    for subtree in std::iter::once(&copy.item).chain(copy.external.iter().map(|(_, list)| list)) {
+        collect_entity_ids(subtree, &mut ids);
+    }
+    let root_id = copy.item.attributes.iter()
+        .filter(|a| ENTITY_ID_ATTRIBUTES.contains(&a.id.as_str()))
+        .find_map(|a| new_id_for(&ids, a))
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let siblings: Vec<NodeId> = doc.children_named(list, "Item").collect();
+    let slot = next_slot(doc, &siblings);
+    let node = doc.insert_subtree(Some(list), &copy.item);
+    let mut inserted = vec![node];
+    for (parent, external) in &copy.external {
+        inserted.push(doc.insert_subtree(Some(*parent), external));
+    }
+    for &root in &inserted {
+        for id in std::iter::once(root).chain(doc.descendants(root)) {
+            for attribute in doc.node_mut(id).attributes.iter_mut() {
+                if let Some(new_id) = new_id_for(&ids, attribute) {
+                    attribute.set(AttributeValue::parse(attribute.attr_type, &new_id)?)?;
+                }
+            }
+        }
+    }
+
+    set_entity_id(doc, node, &root_id)?;
+    adopt_item(doc, node, owner, slot)?;
+
+    let index = reference_index(doc);
+    let mut references = 0;
+    for (old_id, new_id) in &ids {
+        references += copy_references(doc, &index, old_id, new_id)?;
+    }
+    println!("Placed {} in inventory list {} ({} references)", node, list, references);
+    Ok(node)
+}
+
+/// Make an item just put in a list belong there: it takes `slot`, its owner
+/// links point at `owner` and it is unequipped
+fn adopt_item(doc: &mut Document, item: NodeId, owner: &InventoryOwner, slot: Option<(AttributeType, i64)>) -> Result<(), AppError> {
+    if let Some(uuid) = &owner.uuid {
+        for id in OWNER_LINK_ATTRIBUTES {
+            if let Some(attribute) = doc.node_mut(item).attribute_mut(id) {
+                attribute.set(AttributeValue::parse(attribute.attr_type, uuid)?)?;
+            }
+        }
+    }
+    for id in EQUIPPED_ATTRIBUTES {
+        if let Some(attribute) = doc.node_mut(item).attribute_mut(id) {
+            attribute.set(AttributeValue::default_for(attribute.attr_type))?;
+        }
+    }
+    if let Some((attr_type, slot)) = slot {
+        match doc.node_mut(item).attribute_mut(SLOT_ATTRIBUTE) {
+            Some(attribute) => attribute.set_int(slot)?,
+            None => {
+                let attribute = Attribute::new(SLOT_ATTRIBUTE, attr_type, AttributeValue::from_int(attr_type, slot)?)?;
+                doc.node_mut(item).attributes.push(attribute);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Pair every entity id in a subtree with a fresh UUID
+fn collect_entity_ids(subtree: &Subtree, ids: &mut Vec<(String, String)>) {
+    for attribute in subtree.attributes.iter().filter(|a| ENTITY_ID_ATTRIBUTES.contains(&a.id.as_str())) {
+        if let Some(id) = value_text(attribute).map(|id| id.to_lowercase()) {
+            if !ids.iter().any(|(old, _)| *old == id) {
+                ids.push((id, uuid::Uuid::new_v4().to_string()));
+            }
+        }
+    }
+    for child in &subtree.children {
+        collect_entity_ids(child, ids);
+    }
+}
+
+/// The new id for an attribute naming one of the copied entities
+fn new_id_for(ids: &[(String, String)], attribute: &Attribute) -> Option<String> {
+    let text = value_text(attribute)?.to_lowercase();
+    ids.iter().find(|(old, _)| *old == text).map(|(_, new)| new.clone())
+}
+
+/// Type and value of the slot after the highest one used by `items`
+fn next_slot(doc: &Document, items: &[NodeId]) -> Option<(AttributeType, i64)> {
+    items.iter()
+        .filter_map(|&item| doc.node(item).attribute(SLOT_ATTRIBUTE))
+        .filter_map(|attribute| Some((attribute.attr_type, attribute.value.as_int()?)))
+        .max_by_key(|&(_, slot)| slot)
+        .map(|(attr_type, slot)| (attr_type, slot + 1))
+}
+
+/// Give a node a new entity id on every id attribute it has, adding a UUID
+/// attribute if it has none
+fn set_entity_id(doc: &mut Document, node: NodeId, id: &str) -> Result<(), AppError> {
+    let mut found = false;
+    for attribute in doc.node_mut(node).attributes.iter_mut()
+        .filter(|a| ENTITY_ID_ATTRIBUTES.contains(&a.id.as_str()))
+    {
+        if matches!(attribute.value, AttributeValue::String(_) | AttributeValue::Guid(_)) {
+            attribute.set(AttributeValue::parse(attribute.attr_type, id)?)?;
+            found = true;
+        }
+    }
+    if !found {
+        let attribute = Attribute::new("UUID", AttributeType::FixedString, AttributeValue::String(id.to_string()))?;
+        doc.node_mut(node).attributes.push(attribute);
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Item Moves
+// ============================================================================
+
+/// Move an item, with its contents, into another inventory list of the same
+/// document. It keeps its UUID, takes the next free slot there, links to the
+/// new owner, and is unequipped: references to it inside the old owner (such
+/// as equipment slots) are dropped.
+pub fn move_item(doc: &mut Document, item: NodeId, list: NodeId, owner: &InventoryOwner) -> Result<(), AppError> {
+    let inventories = inventories(doc);
+    check_target(doc, &inventories, item, list)?;
+    let old_list = doc.parent(item);
+    if old_list == Some(list) {
+        return Ok(());
+    }
+
+    release_from_owner(doc, &inventories, item)?;
+    let siblings: Vec<NodeId> = doc.children_named(list, "Item").collect();
+    let slot = next_slot(doc, &siblings);
+    doc.remove_node(item);
+    doc.node_mut(item).parent = Some(list);
+    doc.node_mut(list).children.push(item);
+    adopt_item(doc, item, owner, slot)?;
+    println!("Moved item {} from list {:?} to list {} ({})", item, old_list, list, owner.name);
+    Ok(())
+}
+
+/// Take an item out of its document to move it to another one. Its UUID is
+/// kept; containers with content lists stored elsewhere cannot be moved out.
+/// Every reference left in this document to it or its contents (equipment
+/// and hotbar slots included) is dropped, as the item is no longer here.
+pub fn take_item(doc: &mut Document, item: NodeId) -> Result<ItemCopy, AppError> {
+    let copy = copy_from(doc, &inventories(doc), item)?;
+    copy.check_movable()?;
+    let ids = entity_ids(doc, &[item]);
+    doc.remove_node(item);
+    let references = remove_references(doc, &ids)?;
+    println!("Took item {} ({} references dropped)", item, references);
+    Ok(copy)
+}
+
+/// Put an item taken from another document into an inventory list, keeping its UUID
+pub fn put_item(doc: &mut Document, list: NodeId, owner: &InventoryOwner, copy: &ItemCopy) -> Result<NodeId, AppError> {
+    if !doc.contains(list) || !INVENTORY_LIST_NAMES.contains(&doc.node(list).name.as_str()) {
+        return Err(AppError::validation(format!("Node {} is not an inventory list", list)));
+    }
+    let siblings: Vec<NodeId> = doc.children_named(list, "Item").collect();
+    let slot = next_slot(doc, &siblings);
+    let node = doc.insert_subtree(Some(list), &copy.item);
+    adopt_item(doc, node, owner, slot)?;
+    println!("Put item {} in list {} ({})", node, list, owner.name);
+    Ok(node)
+}
+
+/// Check that a move target is an inventory list outside the item itself
+pub fn check_move_target(doc: &Document, item: NodeId, list: NodeId) -> Result<(), AppError> {
+    check_target(doc, &inventories(doc), item, list)
+}
+
+fn check_target(doc: &Document, inventories: &[Inventory], item: NodeId, list: NodeId) -> Result<(), AppError> {
+    if !is_item(doc, item) {
+        return Err(AppError::not_found(format!("No item {}", item)));
+    }
+    if !doc.contains(list) || !INVENTORY_LIST_NAMES.contains(&doc.node(list).name.as_str()) {
+        return Err(AppError::validation(format!("Node {} is not an inventory list", list)));
+    }
+    if doc.descendants(item).contains(&list) || content_lists(inventories, item).contains(&list) {
+        return Err(AppError::validation("An item cannot be moved into itself"));
+    }
+    Ok(())
+}
+
+/// Drop references to an item from inside the entity owning its current list
+fn release_from_owner(doc: &mut Document, inventories: &[Inventory], item: NodeId) -> Result<(), AppError> {
+    let Some(entity) = doc.parent(item).and_then(|list| {
+        inventories.iter().find(|inventory| inventory.node == list)?.entity
+    }) else {
+        return Ok(());
+    };
+    let Some(id) = entity_id(doc, item) else {
+        return Ok(());
+    };
+
+    let inside: HashSet<NodeId> = doc.descendants(entity).into_iter().collect();
+    let index = reference_index(doc);
+    for (node, attribute_id) in references_to(&index, &id) {
+        if inside.contains(node) && doc.contains(*node) {
+            drop_reference(doc, *node, attribute_id)?;
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Item Deletion
+// ============================================================================
+
+/// Remove an item, its nested contents and content lists stored elsewhere,
+/// and every reference to them in this document. Returns the entity ids
+/// removed so references in other resources can be cleaned up too.
+pub fn delete_item(doc: &mut Document, item: NodeId) -> Result<Vec<String>, AppError> {
+    if !is_item(doc, item) {
+        return Err(AppError::not_found(format!("No item {}", item)));
+    }
+
+    let inside: HashSet<NodeId> = doc.descendants(item).into_iter().collect();
+    let mut roots = vec![item];
+    roots.extend(content_lists(&inventories(doc), item).into_iter().filter(|list| !inside.contains(list)));
+    let ids = entity_ids(doc, &roots);
+
+    for &root in &roots {
+        doc.remove_node(root);
+    }
+    let references = remove_references(doc, &ids)?;
+    println!("Deleted item {} ({} entities, {} references)", item, ids.len(), references);
+    Ok(ids)
+}
+
+/// Entity ids of the items in and below the given nodes
+fn entity_ids(doc: &Document, roots: &[NodeId]) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for &root in roots {
+        for node in std::iter::once(root).chain(doc.descendants(root)) {
+            if doc.node(node).name == "Item" {
+                ids.extend(entity_id(doc, node).filter(|id| !ids.contains(id)));
+            }
+        }
+    }
+    ids
+}
+
+/// Whether any node refers to one of the ids
+pub fn has_references(doc: &Document, ids: &[String]) -> bool {
+    let index = reference_index(doc);
+    ids.iter().any(|id| !references_to(&index, id).is_empty())
+}
+
+/// Drop references to deleted entities: a node that only exists to hold the
+/// reference (no children, no other text) is removed, otherwise the
+/// attribute is cleared. Returns the number of references dropped.
+pub fn remove_references(doc: &mut Document, ids: &[String]) -> Result<usize, AppError> {
+    let index = reference_index(doc);
+    let mut removed = 0;
+    for id in ids {
+        for (node, attribute_id) in references_to(&index, id) {
+            if doc.contains(*node) {
+                drop_reference(doc, *node, attribute_id)?;
+                removed += 1;
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// Remove a reference node that holds nothing else, or clear the attribute
+fn drop_reference(doc: &mut Document, node: NodeId, attribute_id: &str) -> Result<(), AppError> {
+    if holds_only_references(doc, node, &[attribute_id]) {
+        doc.remove_node(node);
+    } else if let Some(attribute) = doc.node_mut(node).attribute_mut(attribute_id) {
+        attribute.set(AttributeValue::default_for(attribute.attr_type))?;
+    }
+    Ok(())
+}
+
+/// Whether a node has no children and no text besides the given attributes
+fn holds_only_references(doc: &Document, node: NodeId, attribute_ids: &[&str]) -> bool {
+    let entry = doc.node(node);
+    entry.children.is_empty() && entry.attributes.iter()
+        .filter(|a| !attribute_ids.contains(&a.id.as_str()))
+        .all(|a| value_text(a).is_none())
+}
+
+// ============================================================================
+// Item References
+// ============================================================================
+
+/// Nodes outside items and inventory lists with an attribute naming the
+/// item's entity id, as (node, attribute id)
+pub fn item_references(doc: &Document, item: NodeId) -> Vec<(NodeId, String)> {
+    match entity_id(doc, item) {
+        Some(id) => references_to(&reference_index(doc), &id).to_vec(),
+        None => Vec::new(),
+    }
+}
+
+/// Nodes naming each entity id, as (node, attribute id), keyed by the lowercased id
+type ReferenceIndex = HashMap<String, Vec<(NodeId, String)>>;
+
+/// Every text attribute that may name an entity. Items, the nodes describing
+/// them and inventory lists carry ids and owner links rather than
+/// references, so they are left out.
+fn reference_index(doc: &Document) -> ReferenceIndex {
+    let mut entities: HashSet<NodeId> = doc.find_nodes("Item").into_iter()
+        .flat_map(|item| own_nodes(doc, item))
+        .collect();
+    entities.extend(INVENTORY_LIST_NAMES.iter().flat_map(|name| doc.find_nodes(name)));
+
+    let mut index = ReferenceIndex::new();
+    for node in doc.tree_nodes().into_iter().filter(|node| !entities.contains(node)) {
+        for attribute in doc.node(node).attributes.iter().filter(|a| a.id != SLOT_ATTRIBUTE) {
+            if let Some(text) = value_text(attribute) {
+                index.entry(text.to_lowercase()).or_default().push((node, attribute.id.clone()));
+            }
+        }
+    }
+    index
+}
+
+fn references_to<'a>(index: &'a ReferenceIndex, id: &str) -> &'a [(NodeId, String)] {
+    index.get(&id.to_lowercase()).map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Whether a reference sits in an equipment or hotbar slot: the node has a
+/// slot number, or it or a node above it is named like a slot
+fn is_slot_reference(doc: &Document, node: NodeId) -> bool {
+    if doc.node(node).attribute(SLOT_ATTRIBUTE).is_some() {
+        return true;
+    }
+    let mut current = Some(node);
+    while let Some(id) = current {
+        let name = doc.node(id).name.to_lowercase();
+        if SLOT_NODE_MARKERS.iter().any(|marker| name.contains(marker)) {
+            return true;
+        }
+        current = doc.parent(id);
+    }
+    false
+}
+
+/// Copy every node that only exists to hold a reference to `old_id` next to
+/// itself, referring to `new_id` instead. Nodes carrying other data and
+/// equipment or hotbar slots are left alone, so the new entity is not
+/// equipped. Returns the number of nodes copied.
+fn copy_references(doc: &mut Document, index: &ReferenceIndex, old_id: &str, new_id: &str) -> Result<usize, AppError> {
+    let references = references_to(index, old_id);
+    let mut added = 0;
+    for (position, (reference, _)) in references.iter().enumerate() {
+        if references[..position].iter().any(|(seen, _)| seen == reference) {
+            continue;
+        }
+        let attribute_ids: Vec<&str> = references.iter()
+            .filter(|(node, _)| node == reference)
+            .map(|(_, attribute_id)| attribute_id.as_str())
+            .collect();
+        if !doc.contains(*reference)
+            || !holds_only_references(doc, *reference, &attribute_ids)
+            || is_slot_reference(doc, *reference)
+        {
+            continue;
+        }
+        let Some(parent) = doc.parent(*reference) else { continue };
+        let copy = doc.insert_subtree(Some(parent), &doc.subtree(*reference));
+        for attribute_id in &attribute_ids {
+            if let Some(attribute) = doc.node_mut(copy).attribute_mut(attribute_id) {
+                attribute.set(AttributeValue::parse(attribute.attr_type, new_id)?)?;
+            }
+        }
+        added += 1;
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsx;
+
+    /// A character holding a sword and a bag with a potion, equipment and
+    /// hotbar slots and a journal entry naming them, and the camp chest with
+    /// its own list
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<save>
+	<version major="4" minor="0" revision="9" build="331" />
+	<region id="Characters">
+		<node id="Characters">
+			<children>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="aaaaaaaa-0000-0000-0000-000000000001" />
+					<attribute id="Name" type="LSString" value="Tav" />
+					<children>
+						<node id="Equipment">
+							<attribute id="MainHand" type="FixedString" value="sword-1" />
+							<attribute id="Label" type="LSString" value="Weapons" />
+						</node>
+						<node id="InventoryList">
+							<children>
+								<node id="Item">
+									<attribute id="UUID" type="FixedString" value="sword-1" />
+									<attribute id="Stats" type="FixedString" value="WPN_Longsword" />
+									<attribute id="Slot" type="int32" value="0" />
+								</node>
+								<node id="Item">
+									<attribute id="UUID" type="FixedString" value="bag-1" />
+									<attribute id="Stats" type="FixedString" value="CONT_Bag_A" />
+									<attribute id="Slot" type="int32" value="1" />
+									<children>
+										<node id="InventoryList">
+											<children>
+												<node id="Item">
+													<attribute id="UUID" type="FixedString" value="potion-1" />
+													<attribute id="Stats" type="FixedString" value="OBJ_Potion_Healing" />
+													<attribute id="Amount" type="int32" value="3" />
+												</node>
+											</children>
+										</node>
+									</children>
+								</node>
+							</children>
+						</node>
+					</children>
+				</node>
+			</children>
+		</node>
+	</region>
+	<region id="Hotbar">
+		<node id="Hotbar">
+			<children>
+				<node id="HotbarSlot">
+					<attribute id="Slot" type="int32" value="4" />
+					<attribute id="Item" type="FixedString" value="potion-1" />
+				</node>
+			</children>
+		</node>
+	</region>
+	<region id="Journal">
+		<node id="Journal">
+			<children>
+				<node id="TrackedItem">
+					<attribute id="Item" type="FixedString" value="sword-1" />
+				</node>
+			</children>
+		</node>
+	</region>
+	<region id="Items">
+		<node id="Items">
+			<children>
+				<node id="Item">
+					<attribute id="UUID" type="FixedString" value="chest-1" />
+					<attribute id="TemplateName" type="FixedString" value="CONT_PlayerCampChest_A" />
+				</node>
+				<node id="ItemList">
+					<attribute id="Owner" type="FixedString" value="chest-1" />
+				</node>
+			</children>
+		</node>
+	</region>
+</save>"#;
+
+    fn item(doc: &Document, uuid: &str) -> NodeId {
+        find_items(doc, &ItemSelector::Uuid { uuid: uuid.to_string() })[0]
+    }
+
+    fn chest(doc: &Document) -> (NodeId, InventoryOwner) {
+        let inventory = inventories(doc).into_iter()
+            .find(|inventory| inventory.owner.kind == OwnerKind::CampChest)
+            .unwrap();
+        (inventory.node, inventory.owner)
+    }
+
+    #[test]
+    fn lists_are_owned_by_link_or_enclosing_entity() {
+        let doc = lsx::read(SAMPLE).unwrap();
+        let owners: Vec<(OwnerKind, String, usize)> = inventories(&doc).into_iter()
+            .map(|inventory| (inventory.owner.kind, inventory.owner.name, inventory.items.len()))
+            .collect();
+        assert_eq!(owners, vec![
+            (OwnerKind::Character, "Tav".to_string(), 2),
+            (OwnerKind::Container, "Item bag-1".to_string(), 1),
+            (OwnerKind::CampChest, "Camp Chest".to_string(), 0),
+        ]);
+        assert_eq!(describe_entity(&doc, item(&doc, "bag-1")).uuid.as_deref(), Some("bag-1"));
+    }
+
+    #[test]
+    fn containers_list_their_contents_under_them() {
+        let doc = lsx::read(SAMPLE).unwrap();
+        let owners = list_items(&doc, "Globals.lsf");
+        let names: Vec<&str> = owners.iter().map(|inventory| inventory.owner.name.as_str()).collect();
+        assert_eq!(names, vec!["Tav", "Camp Chest"]);
+
+        let items = &owners[0].items;
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].stats.as_deref(), items[0].slot, items[0].amount), (Some("WPN_Longsword"), Some(0), 1));
+        assert_eq!(items[1].contents.len(), 1);
+        let potion = &items[1].contents[0];
+        assert_eq!((potion.uuid.as_deref(), potion.amount, potion.stackable), (Some("potion-1"), 3, true));
+        assert_eq!(potion.resource, "Globals.lsf");
+    }
+
+    #[test]
+    fn duplicates_get_fresh_ids_and_their_own_references() {
+        let mut doc = lsx::read(SAMPLE).unwrap();
+        let (list, owner) = chest(&doc);
+        for uuid in ["sword-1", "bag-1"] {
+            let copy = copy_item(&doc, item(&doc, uuid)).unwrap();
+            place_item(&mut doc, list, &owner, &copy, true).unwrap();
+        }
+
+        let chest_items = inventories(&doc).into_iter()
+            .find(|inventory| inventory.owner.kind == OwnerKind::CampChest)
+            .unwrap()
+            .items;
+        let ids: Vec<String> = chest_items.iter().filter_map(|&i| entity_id(&doc, i)).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.iter().all(|id| uuid::Uuid::parse_str(id).is_ok()));
+        let potion = doc.descendants(chest_items[1]).into_iter()
+            .find(|&n| doc.node(n).name == "Item")
+            .and_then(|n| entity_id(&doc, n))
+            .unwrap();
+        assert_ne!(potion, "potion-1");
+
+        // The journal entry is copied for the new sword; equipment and hotbar slots are not
+        assert_eq!(doc.find_nodes("TrackedItem").len(), 2);
+        assert_eq!(item_references(&doc, chest_items[0]).len(), 1);
+        assert!(!has_references(&doc, &[potion]));
+    }
+
+    #[test]
+    fn deleting_an_item_drops_its_contents_and_references() {
+        let mut doc = lsx::read(SAMPLE).unwrap();
+        let bag = item(&doc, "bag-1");
+        let ids = delete_item(&mut doc, bag).unwrap();
+        assert_eq!(ids, ["bag-1", "potion-1"]);
+        assert!(!has_references(&doc, &ids));
+        assert!(doc.find_nodes("Item").iter().all(|&i| entity_id(&doc, i).as_deref() != Some("potion-1")));
+
+        let sword = item(&doc, "sword-1");
+        delete_item(&mut doc, sword).unwrap();
+        assert!(doc.find_nodes("TrackedItem").is_empty());
+        let equipment = doc.find_nodes("Equipment")[0];
+        assert_eq!(attribute_text(&doc, equipment, "MainHand"), None);
+        assert_eq!(attribute_text(&doc, equipment, "Label").as_deref(), Some("Weapons"));
+    }
+
+    #[test]
+    fn items_taken_out_leave_no_references_behind() {
+        let mut source = lsx::read(SAMPLE).unwrap();
+        let mut target = lsx::read(SAMPLE).unwrap();
+        let bag = item(&source, "bag-1");
+        let copy = take_item(&mut source, bag).unwrap();
+
+        assert!(find_items(&source, &ItemSelector::Uuid { uuid: "potion-1".to_string() }).is_empty());
+        assert!(!has_references(&source, &["potion-1".to_string(), "bag-1".to_string()]));
+        assert!(source.find_nodes("HotbarSlot").is_empty());
+        assert!(has_references(&source, &["sword-1".to_string()]));
+
+        let (list, owner) = chest(&target);
+        let node = put_item(&mut target, list, &owner, &copy).unwrap();
+        assert_eq!(entity_id(&target, node).as_deref(), Some("bag-1"));
+        let contents: Vec<String> = target.descendants(node).into_iter()
+            .filter(|&n| target.node(n).name == "Item")
+            .filter_map(|n| entity_id(&target, n))
+            .collect();
+        assert_eq!(contents, vec!["potion-1"]);
+    }
+}
+
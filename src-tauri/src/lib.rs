@@ -7,20 +7,56 @@ fn greet(name: &str) -> String {
 mod commands;
 mod bg3_io;
 mod save_model;
+mod binutils;
+mod lspk;
+mod document;
+mod lsf;
+mod lsx;
+mod values;
+mod error;
+mod session;
+mod inventory;
+mod templates;
+mod party;
+mod abilities;
+mod experience;
+mod health;
+mod position;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .manage(session::SessionRegistry::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             commands::check_lslib_status,
             commands::list_saves,
             commands::extract_save,
+            commands::list_sessions,
+            commands::close_session,
+            commands::save_session,
             commands::read_save_info,
+            commands::list_party,
+            commands::get_ability_scores,
+            commands::set_ability_score,
+            commands::get_experience,
+            commands::set_experience,
+            commands::get_health,
+            commands::set_hit_points,
+            commands::revive_character,
+            commands::list_positions,
+            commands::teleport_characters,
             commands::get_gold_count,
-            commands::modify_and_save_gold,
+            commands::list_inventory,
+            commands::set_item_amount,
+            commands::duplicate_item,
+            commands::delete_item,
+            commands::move_item,
+            commands::list_item_templates,
+            commands::add_item,
+            commands::set_gold,
             commands::get_backup_path
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,1000 @@
+use std::collections::HashMap;
+use std::path::Path;
+use crate::binutils::{self, ByteReader, CompressionMethod};
+use crate::error::AppError;
+use crate::values::{component_count, AttributeValue};
+use crate::document::{
+    Attribute, AttributeType, Document, EngineVersion, MetadataFormat, NodeId,
+    TranslatedFSArgument, TranslatedString,
+};
+
+// ============================================================================
+// Format Constants
+// ============================================================================
+
+/// Magic bytes at the start of every LSF file
+pub const LSF_SIGNATURE: &[u8; 4] = b"LSOF";
+
+/// Substreams (except strings) are LZ4 frame compressed from this version on
+pub const VER_CHUNKED_COMPRESS: u32 = 2;
+/// Extended node/attribute descriptors with sibling links
+pub const VER_EXTENDED_NODES: u32 = 3;
+/// First BG3 version; TranslatedStrings carry a version instead of inline text
+pub const VER_BG3: u32 = 4;
+/// 64-bit engine version in the header
+pub const VER_BG3_EXTENDED_HEADER: u32 = 5;
+/// Metadata block with the node keys section sizes
+pub const VER_BG3_ADDITIONAL_BLOB: u32 = 6;
+/// BG3 Patch 3 and later
+pub const VER_BG3_PATCH3: u32 = 7;
+
+/// Size of a node descriptor without sibling links
+const NODE_ENTRY_V2_SIZE: usize = 12;
+/// Size of a node descriptor with sibling links
+const NODE_ENTRY_V3_SIZE: usize = 16;
+/// Size of an attribute descriptor without adjacency data
+const ATTRIBUTE_ENTRY_V2_SIZE: usize = 12;
+/// Size of an attribute descriptor with adjacency data
+const ATTRIBUTE_ENTRY_V3_SIZE: usize = 16;
+/// Number of buckets in the string hash table written by LSLib
+const STRING_HASH_MAP_SIZE: usize = 0x200;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// Sizes and flags from the LSF metadata block
+struct Metadata {
+    strings_uncompressed_size: u32,
+    strings_size_on_disk: u32,
+    keys_uncompressed_size: u32,
+    keys_size_on_disk: u32,
+    nodes_uncompressed_size: u32,
+    nodes_size_on_disk: u32,
+    attributes_uncompressed_size: u32,
+    attributes_size_on_disk: u32,
+    values_uncompressed_size: u32,
+    values_size_on_disk: u32,
+    compression_flags: u8,
+    format: MetadataFormat,
+}
+
+/// A node descriptor from the node table
+struct NodeEntry {
+    name: (usize, usize),
+    parent: i32,
+    first_attribute: i32,
+}
+
+/// An attribute descriptor from the attribute table, with resolved value offset
+struct AttributeEntry {
+    name: (usize, usize),
+    type_id: u32,
+    length: usize,
+    next_attribute: i32,
+    offset: usize,
+}
+
+// ============================================================================
+// Public API Functions
+// ============================================================================
+
+/// Read an LSF file from disk into a document tree
+pub fn read_file(path: &Path) -> Result<Document, AppError> {
+    let data = std::fs::read(path).map_err(|e| AppError::io(path, e))?;
+    read(&data).map_err(|e| e.in_file(path))
+}
+
+/// Parse an in-memory LSF buffer into a document tree
+pub fn read(data: &[u8]) -> Result<Document, AppError> {
+    let mut reader = ByteReader::new(data);
+
+    if reader.read_bytes(4).map_err(AppError::parse)? != LSF_SIGNATURE {
+        return Err(AppError::parse("Not an LSF file (signature mismatch)"));
+    }
+    let lsf_version = reader.read_u32().map_err(AppError::parse)?;
+    if !(1..=VER_BG3_PATCH3).contains(&lsf_version) {
+        return Err(AppError::UnsupportedVersion { format: "LSF".to_string(), version: lsf_version });
+    }
+
+    read_body(&mut reader, lsf_version).map_err(AppError::parse)
+}
+
+/// Parse everything after the signature and version
+fn read_body(reader: &mut ByteReader, lsf_version: u32) -> Result<Document, String> {
+    let mut engine_version = if lsf_version >= VER_BG3_EXTENDED_HEADER {
+        EngineVersion::from_packed_i64(reader.read_i64()?)
+    } else {
+        EngineVersion::from_packed_i32(reader.read_i32()?)
+    };
+    // Merged LSF files may be missing the engine version
+    if engine_version.major == 0 {
+        engine_version = EngineVersion { major: 4, minor: 0, revision: 9, build: 0 };
+    }
+
+    let metadata = read_metadata(reader, lsf_version)?;
+    let has_adjacency = lsf_version >= VER_EXTENDED_NODES
+        && metadata.format == MetadataFormat::KeysAndAdjacency;
+
+    let strings = read_section(reader, &metadata, metadata.strings_size_on_disk, metadata.strings_uncompressed_size, lsf_version, false)?;
+    let names = read_names(&strings)?;
+
+    let nodes = read_section(reader, &metadata, metadata.nodes_size_on_disk, metadata.nodes_uncompressed_size, lsf_version, true)?;
+    let nodes = read_nodes(&nodes, has_adjacency)?;
+
+    let attributes = read_section(reader, &metadata, metadata.attributes_size_on_disk, metadata.attributes_uncompressed_size, lsf_version, true)?;
+    let attributes = if has_adjacency {
+        read_attributes_v3(&attributes)?
+    } else {
+        read_attributes_v2(&attributes)?
+    };
+
+    let values = read_section(reader, &metadata, metadata.values_size_on_disk, metadata.values_uncompressed_size, lsf_version, true)?;
+
+    let keys = if metadata.format == MetadataFormat::KeysAndAdjacency {
+        read_section(reader, &metadata, metadata.keys_size_on_disk, metadata.keys_uncompressed_size, lsf_version, true)?
+    } else {
+        Vec::new()
+    };
+
+    let mut document = Document::new();
+    document.version = engine_version;
+    document.format = metadata.format;
+    document.lsf_version = lsf_version;
+
+    let context = ValueContext { lsf_version, engine_version };
+    build_tree(&mut document, &names, &nodes, &attributes, &values, &context)?;
+    apply_keys(&mut document, &names, &keys)?;
+
+    Ok(document)
+}
+
+/// Serialize a document tree to an LSF file on disk
+pub fn write_file(document: &Document, path: &Path) -> Result<(), AppError> {
+    let data = write(document)?;
+    std::fs::write(path, data).map_err(|e| AppError::io(path, e))
+}
+
+/// Serialize a document tree to an LSF buffer, using the document's
+/// LSF version, engine version and metadata format
+pub fn write(document: &Document) -> Result<Vec<u8>, AppError> {
+    let lsf_version = document.lsf_version;
+    if !(1..=VER_BG3_PATCH3).contains(&lsf_version) {
+        return Err(AppError::UnsupportedVersion { format: "LSF".to_string(), version: lsf_version });
+    }
+
+    write_body(document, lsf_version).map_err(AppError::validation)
+}
+
+/// Build and compress all sections once the version is known to be supported
+fn write_body(document: &Document, lsf_version: u32) -> Result<Vec<u8>, String> {
+    let has_adjacency = lsf_version >= VER_EXTENDED_NODES
+        && document.format == MetadataFormat::KeysAndAdjacency;
+    let context = ValueContext { lsf_version, engine_version: document.version };
+    let sections = build_sections(document, has_adjacency, &context)?;
+
+    let flags = CompressionMethod::Lz4.to_flags();
+    let strings = compress_section(&sections.strings, flags, lsf_version, false)?;
+    let nodes = compress_section(&sections.nodes, flags, lsf_version, true)?;
+    let attributes = compress_section(&sections.attributes, flags, lsf_version, true)?;
+    let values = compress_section(&sections.values, flags, lsf_version, true)?;
+    let keys = if has_keys_section(lsf_version, document.format) {
+        compress_section(&sections.keys, flags, lsf_version, true)?
+    } else {
+        Vec::new()
+    };
+    let keys_uncompressed_size = if keys.is_empty() { 0 } else { sections.keys.len() };
+
+    let mut out = Vec::new();
+    out.extend_from_slice(LSF_SIGNATURE);
+    out.extend_from_slice(&lsf_version.to_le_bytes());
+    if lsf_version >= VER_BG3_EXTENDED_HEADER {
+        out.extend_from_slice(&document.version.to_packed_i64().to_le_bytes());
+    } else {
+        out.extend_from_slice(&document.version.to_packed_i32().to_le_bytes());
+    }
+
+    // Metadata block
+    push_u32(&mut out, sections.strings.len())?;
+    push_u32(&mut out, strings.len())?;
+    if lsf_version >= VER_BG3_ADDITIONAL_BLOB {
+        push_u32(&mut out, keys_uncompressed_size)?;
+        push_u32(&mut out, keys.len())?;
+    }
+    push_u32(&mut out, sections.nodes.len())?;
+    push_u32(&mut out, nodes.len())?;
+    push_u32(&mut out, sections.attributes.len())?;
+    push_u32(&mut out, attributes.len())?;
+    push_u32(&mut out, sections.values.len())?;
+    push_u32(&mut out, values.len())?;
+    out.push(flags);
+    out.push(0); // Unknown2
+    out.extend_from_slice(&0u16.to_le_bytes()); // Unknown3
+    out.extend_from_slice(&document.format.to_u32().to_le_bytes());
+
+    out.extend_from_slice(&strings);
+    out.extend_from_slice(&nodes);
+    out.extend_from_slice(&attributes);
+    out.extend_from_slice(&values);
+    out.extend_from_slice(&keys);
+    Ok(out)
+}
+
+// ============================================================================
+// Header & Section Reading
+// ============================================================================
+
+/// Read the metadata block; versions before 6 have no keys section sizes
+fn read_metadata(reader: &mut ByteReader, lsf_version: u32) -> Result<Metadata, String> {
+    let strings_uncompressed_size = reader.read_u32()?;
+    let strings_size_on_disk = reader.read_u32()?;
+    let (keys_uncompressed_size, keys_size_on_disk) = if lsf_version >= VER_BG3_ADDITIONAL_BLOB {
+        (reader.read_u32()?, reader.read_u32()?)
+    } else {
+        (0, 0)
+    };
+
+    let nodes_uncompressed_size = reader.read_u32()?;
+    let nodes_size_on_disk = reader.read_u32()?;
+    let attributes_uncompressed_size = reader.read_u32()?;
+    let attributes_size_on_disk = reader.read_u32()?;
+    let values_uncompressed_size = reader.read_u32()?;
+    let values_size_on_disk = reader.read_u32()?;
+    let compression_flags = reader.read_u8()?;
+    let _unknown2 = reader.read_u8()?;
+    let _unknown3 = reader.read_u16()?;
+    let format = MetadataFormat::from_u32(reader.read_u32()?);
+
+    let metadata = Metadata {
+        strings_uncompressed_size,
+        strings_size_on_disk,
+        keys_uncompressed_size,
+        keys_size_on_disk,
+        nodes_uncompressed_size,
+        nodes_size_on_disk,
+        attributes_uncompressed_size,
+        attributes_size_on_disk,
+        values_uncompressed_size,
+        values_size_on_disk,
+        compression_flags,
+        format,
+    };
+    Ok(metadata)
+}
+
+/// Read one substream, decompressing it when needed
+fn read_section(
+    reader: &mut ByteReader,
+    metadata: &Metadata,
+    size_on_disk: u32,
+    uncompressed_size: u32,
+    lsf_version: u32,
+    allow_chunked: bool,
+) -> Result<Vec<u8>, String> {
+    let (size_on_disk, uncompressed_size) = (size_on_disk as usize, uncompressed_size as usize);
+
+    // A zero on-disk size means the section is stored uncompressed
+    if size_on_disk == 0 {
+        return Ok(reader.read_bytes(uncompressed_size)?.to_vec());
+    }
+
+    let is_compressed = CompressionMethod::from_flags(metadata.compression_flags)? != CompressionMethod::None;
+    if !is_compressed {
+        return Ok(reader.read_bytes(uncompressed_size)?.to_vec());
+    }
+
+    let chunked = allow_chunked && lsf_version >= VER_CHUNKED_COMPRESS;
+    let compressed = reader.read_bytes(size_on_disk)?;
+    binutils::decompress(compressed, uncompressed_size, metadata.compression_flags, chunked)
+}
+
+/// Parse the string hash table into buckets of names
+fn read_names(data: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    let mut reader = ByteReader::new(data);
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let num_buckets = reader.read_u32()? as usize;
+    let mut names = Vec::with_capacity(num_buckets);
+    for _ in 0..num_buckets {
+        let num_strings = reader.read_u16()? as usize;
+        let mut bucket = Vec::with_capacity(num_strings);
+        for _ in 0..num_strings {
+            let len = reader.read_u16()? as usize;
+            bucket.push(String::from_utf8_lossy(reader.read_bytes(len)?).to_string());
+        }
+        names.push(bucket);
+    }
+    Ok(names)
+}
+
+/// Split a name reference into (bucket, index within bucket)
+fn split_name_ref(name_ref: u32) -> (usize, usize) {
+    ((name_ref >> 16) as usize, (name_ref & 0xFFFF) as usize)
+}
+
+fn resolve_name(names: &[Vec<String>], name: (usize, usize)) -> Result<&str, String> {
+    names.get(name.0)
+        .and_then(|bucket| bucket.get(name.1))
+        .map(|s| s.as_str())
+        .ok_or_else(|| format!("Invalid string table reference {:?}", name))
+}
+
+fn read_nodes(data: &[u8], long_nodes: bool) -> Result<Vec<NodeEntry>, String> {
+    let entry_size = if long_nodes { NODE_ENTRY_V3_SIZE } else { NODE_ENTRY_V2_SIZE };
+    let mut reader = ByteReader::new(data);
+    let mut nodes = Vec::with_capacity(data.len() / entry_size);
+
+    while !reader.is_at_end() {
+        let name = split_name_ref(reader.read_u32()?);
+        let entry = if long_nodes {
+            let parent = reader.read_i32()?;
+            let _next_sibling = reader.read_i32()?;
+            let first_attribute = reader.read_i32()?;
+            NodeEntry { name, parent, first_attribute }
+        } else {
+            let first_attribute = reader.read_i32()?;
+            let parent = reader.read_i32()?;
+            NodeEntry { name, parent, first_attribute }
+        };
+        nodes.push(entry);
+    }
+    Ok(nodes)
+}
+
+/// Attributes with explicit next links and value offsets
+fn read_attributes_v3(data: &[u8]) -> Result<Vec<AttributeEntry>, String> {
+    let mut reader = ByteReader::new(data);
+    let mut attributes = Vec::with_capacity(data.len() / ATTRIBUTE_ENTRY_V3_SIZE);
+
+    while !reader.is_at_end() {
+        let name = split_name_ref(reader.read_u32()?);
+        let type_and_length = reader.read_u32()?;
+        let next_attribute = reader.read_i32()?;
+        let offset = reader.read_u32()? as usize;
+        attributes.push(AttributeEntry {
+            name,
+            type_id: type_and_length & 0x3F,
+            length: (type_and_length >> 6) as usize,
+            next_attribute,
+            offset,
+        });
+    }
+    Ok(attributes)
+}
+
+/// Attributes stored per owning node; values are laid out back to back
+fn read_attributes_v2(data: &[u8]) -> Result<Vec<AttributeEntry>, String> {
+    let mut reader = ByteReader::new(data);
+    let mut attributes: Vec<AttributeEntry> = Vec::with_capacity(data.len() / ATTRIBUTE_ENTRY_V2_SIZE);
+    // Last attribute index seen for each node, used to chain next links
+    let mut last_by_node: HashMap<i32, usize> = HashMap::new();
+    let mut offset = 0usize;
+
+    while !reader.is_at_end() {
+        let name = split_name_ref(reader.read_u32()?);
+        let type_and_length = reader.read_u32()?;
+        let node_index = reader.read_i32()?;
+        let length = (type_and_length >> 6) as usize;
+
+        let index = attributes.len();
+        if let Some(previous) = last_by_node.insert(node_index, index) {
+            attributes[previous].next_attribute = index as i32;
+        }
+        attributes.push(AttributeEntry {
+            name,
+            type_id: type_and_length & 0x3F,
+            length,
+            next_attribute: -1,
+            offset,
+        });
+        offset += length;
+    }
+    Ok(attributes)
+}
+
+// ============================================================================
+// Tree Building
+// ============================================================================
+
+/// Build the node arena; nodes are stored depth-first so parents precede children
+fn build_tree(
+    document: &mut Document,
+    names: &[Vec<String>],
+    nodes: &[NodeEntry],
+    attributes: &[AttributeEntry],
+    values: &[u8],
+    context: &ValueContext,
+) -> Result<(), String> {
+    let mut node_ids: Vec<NodeId> = Vec::with_capacity(nodes.len());
+
+    for (index, entry) in nodes.iter().enumerate() {
+        let name = resolve_name(names, entry.name)?;
+        let parent = if entry.parent < 0 {
+            None
+        } else {
+            let parent = entry.parent as usize;
+            if parent >= index {
+                return Err(format!("Node {} references invalid parent {}", index, parent));
+            }
+            Some(node_ids[parent])
+        };
+
+        let node_id = document.add_node(parent, name);
+        if parent.is_none() {
+            document.add_region(name, node_id);
+        }
+        node_ids.push(node_id);
+
+        let mut attribute_index = entry.first_attribute;
+        while attribute_index >= 0 {
+            let attr = attributes.get(attribute_index as usize)
+                .ok_or_else(|| format!("Node {} references invalid attribute {}", index, attribute_index))?;
+            let attribute = read_attribute(names, attr, values, context)?;
+            document.node_mut(node_id).attributes.push(attribute);
+            attribute_index = attr.next_attribute;
+        }
+    }
+    Ok(())
+}
+
+/// Assign node keys from the keys section (node index + key name reference)
+fn apply_keys(document: &mut Document, names: &[Vec<String>], keys: &[u8]) -> Result<(), String> {
+    let mut reader = ByteReader::new(keys);
+    while !reader.is_at_end() {
+        let node_index = reader.read_u32()? as usize;
+        let key = resolve_name(names, split_name_ref(reader.read_u32()?))?.to_string();
+        if node_index >= document.node_count() {
+            return Err(format!("Key references invalid node {}", node_index));
+        }
+        document.node_mut(node_index).key = Some(key);
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Section Building
+// ============================================================================
+
+/// Uncompressed contents of every LSF substream
+struct Sections {
+    strings: Vec<u8>,
+    nodes: Vec<u8>,
+    attributes: Vec<u8>,
+    values: Vec<u8>,
+    keys: Vec<u8>,
+}
+
+/// String hash table that hands out (bucket << 16 | index) references
+struct StringTable {
+    buckets: Vec<Vec<String>>,
+    lookup: HashMap<String, u32>,
+}
+
+impl StringTable {
+    fn new() -> Self {
+        StringTable {
+            buckets: vec![Vec::new(); STRING_HASH_MAP_SIZE],
+            lookup: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, s: &str) -> Result<u32, String> {
+        if let Some(&name_ref) = self.lookup.get(s) {
+            return Ok(name_ref);
+        }
+        if s.len() > u16::MAX as usize {
+            return Err(format!("Name too long for LSF string table: {}", s));
+        }
+
+        // FNV-1a folded to the bucket count; readers only rely on the stored indices
+        let hash = s.bytes().fold(0x811C_9DC5u32, |h, b| (h ^ b as u32).wrapping_mul(0x0100_0193));
+        let bucket = ((hash ^ (hash >> 9) ^ (hash >> 18) ^ (hash >> 27)) as usize) % STRING_HASH_MAP_SIZE;
+        let index = self.buckets[bucket].len();
+        if index > u16::MAX as usize {
+            return Err("LSF string table bucket overflow".to_string());
+        }
+
+        let name_ref = ((bucket as u32) << 16) | index as u32;
+        self.buckets[bucket].push(s.to_string());
+        self.lookup.insert(s.to_string(), name_ref);
+        Ok(name_ref)
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(self.buckets.len() as u32).to_le_bytes());
+        for bucket in &self.buckets {
+            out.extend_from_slice(&(bucket.len() as u16).to_le_bytes());
+            for s in bucket {
+                out.extend_from_slice(&(s.len() as u16).to_le_bytes());
+                out.extend_from_slice(s.as_bytes());
+            }
+        }
+        out
+    }
+}
+
+/// Flatten the tree depth-first into node, attribute, value and key tables
+fn build_sections(document: &Document, has_adjacency: bool, context: &ValueContext) -> Result<Sections, String> {
+    let has_keys = has_keys_section(document.lsf_version, document.format);
+    let mut strings = StringTable::new();
+    let mut nodes = Vec::new();
+    let mut attributes = Vec::new();
+    let mut values = Vec::new();
+    let mut keys = Vec::new();
+
+    // Depth-first order so that every parent precedes its children
+    let mut order: Vec<NodeId> = Vec::new();
+    for region in &document.regions {
+        order.push(region.root);
+        order.extend(document.descendants(region.root));
+    }
+
+    let mut lsf_index: HashMap<NodeId, i32> = HashMap::with_capacity(order.len());
+    let mut next_sibling = vec![-1i32; order.len()];
+    let mut last_child: HashMap<i32, usize> = HashMap::new();
+    let mut attribute_count = 0i32;
+    let mut node_entries = Vec::with_capacity(order.len());
+
+    for (index, &node_id) in order.iter().enumerate() {
+        let node = document.node(node_id);
+        lsf_index.insert(node_id, index as i32);
+
+        let parent = match node.parent {
+            Some(parent) => *lsf_index.get(&parent)
+                .ok_or_else(|| format!("Node {} is written before its parent", node.name))?,
+            None => -1,
+        };
+        if parent >= 0 {
+            if let Some(previous) = last_child.insert(parent, index) {
+                next_sibling[previous] = index as i32;
+            }
+        }
+
+        let first_attribute = if node.attributes.is_empty() { -1 } else { attribute_count };
+        for (attr_index, attribute) in node.attributes.iter().enumerate() {
+            let offset = values.len();
+            write_attribute_value(&mut values, attribute, context)
+                .map_err(|e| format!("Node {}, attribute {}: {}", node.name, attribute.id, e))?;
+            let length = values.len() - offset;
+            if length >= (1 << 26) {
+                return Err(format!("Attribute {} value is too large for LSF", attribute.id));
+            }
+
+            let name_ref = strings.add(&attribute.id)?;
+            let type_and_length = attribute.attr_type.id() | ((length as u32) << 6);
+            attributes.extend_from_slice(&name_ref.to_le_bytes());
+            attributes.extend_from_slice(&type_and_length.to_le_bytes());
+            if has_adjacency {
+                let is_last = attr_index + 1 == node.attributes.len();
+                let next = if is_last { -1 } else { attribute_count + 1 };
+                attributes.extend_from_slice(&next.to_le_bytes());
+                push_u32(&mut attributes, offset)?;
+            } else {
+                attributes.extend_from_slice(&(index as i32).to_le_bytes());
+            }
+            attribute_count += 1;
+        }
+
+        if let (Some(key), true) = (&node.key, has_keys) {
+            keys.extend_from_slice(&(index as u32).to_le_bytes());
+            keys.extend_from_slice(&strings.add(key)?.to_le_bytes());
+        }
+
+        node_entries.push((strings.add(&node.name)?, parent, first_attribute));
+    }
+
+    for (index, (name_ref, parent, first_attribute)) in node_entries.into_iter().enumerate() {
+        nodes.extend_from_slice(&name_ref.to_le_bytes());
+        if has_adjacency {
+            nodes.extend_from_slice(&parent.to_le_bytes());
+            nodes.extend_from_slice(&next_sibling[index].to_le_bytes());
+            nodes.extend_from_slice(&first_attribute.to_le_bytes());
+        } else {
+            nodes.extend_from_slice(&first_attribute.to_le_bytes());
+            nodes.extend_from_slice(&parent.to_le_bytes());
+        }
+    }
+
+    Ok(Sections {
+        strings: strings.serialize(),
+        nodes,
+        attributes,
+        values,
+        keys,
+    })
+}
+
+/// Node keys are only stored when the metadata block has room for their sizes
+fn has_keys_section(lsf_version: u32, format: MetadataFormat) -> bool {
+    lsf_version >= VER_BG3_ADDITIONAL_BLOB && format == MetadataFormat::KeysAndAdjacency
+}
+
+/// Compress a substream; empty sections are stored as zero bytes
+fn compress_section(data: &[u8], flags: u8, lsf_version: u32, allow_chunked: bool) -> Result<Vec<u8>, String> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let chunked = allow_chunked && lsf_version >= VER_CHUNKED_COMPRESS;
+    binutils::compress(data, flags, chunked)
+}
+
+fn push_u32(out: &mut Vec<u8>, value: usize) -> Result<(), String> {
+    let value = u32::try_from(value)
+        .map_err(|_| format!("Value {} does not fit in a 32-bit LSF field", value))?;
+    out.extend_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+// ============================================================================
+// Value Decoding
+// ============================================================================
+
+/// File-level information needed to decode some value types
+struct ValueContext {
+    lsf_version: u32,
+    engine_version: EngineVersion,
+}
+
+impl ValueContext {
+    /// Whether TranslatedStrings store a version number instead of inline text
+    fn has_translated_string_version(&self) -> bool {
+        let v = self.engine_version;
+        self.lsf_version >= VER_BG3
+            || v.major > 4
+            || (v.major == 4 && (v.revision > 0 || v.build >= 0x1A))
+    }
+}
+
+fn read_attribute(
+    names: &[Vec<String>],
+    entry: &AttributeEntry,
+    values: &[u8],
+    context: &ValueContext,
+) -> Result<Attribute, String> {
+    let id = resolve_name(names, entry.name)?;
+    let attr_type = AttributeType::from_id(entry.type_id)
+        .ok_or_else(|| format!("Attribute {} has unknown type id {}", id, entry.type_id))?;
+
+    let end = entry.offset.checked_add(entry.length)
+        .filter(|&end| end <= values.len())
+        .ok_or_else(|| format!("Attribute {} value lies outside the value buffer", id))?;
+    let data = &values[entry.offset..end];
+
+    let value = read_value(data, attr_type, context)
+        .map_err(|e| format!("Attribute {}: {}", id, e))?;
+    Ok(Attribute::new(id, attr_type, value)?)
+}
+
+/// Decode the binary form of a value
+fn read_value(data: &[u8], attr_type: AttributeType, context: &ValueContext) -> Result<AttributeValue, String> {
+    let mut reader = ByteReader::new(data);
+    let value = match attr_type {
+        AttributeType::None => AttributeValue::None,
+        AttributeType::UInt8 => AttributeValue::UInt8(reader.read_u8()?),
+        AttributeType::Int8 => AttributeValue::Int8(reader.read_u8()? as i8),
+        AttributeType::Int16 => AttributeValue::Int16(reader.read_u16()? as i16),
+        AttributeType::UInt16 => AttributeValue::UInt16(reader.read_u16()?),
+        AttributeType::Int32 => AttributeValue::Int32(reader.read_i32()?),
+        AttributeType::UInt32 => AttributeValue::UInt32(reader.read_u32()?),
+        AttributeType::Int64 | AttributeType::OldInt64 => AttributeValue::Int64(reader.read_i64()?),
+        AttributeType::UInt64 => AttributeValue::UInt64(reader.read_u64()?),
+        AttributeType::Float => AttributeValue::Float(read_f32(&mut reader)?),
+        AttributeType::Double => AttributeValue::Double(f64::from_bits(reader.read_u64()?)),
+        AttributeType::Bool => AttributeValue::Bool(reader.read_u8()? != 0),
+        AttributeType::IVec2 | AttributeType::IVec3 | AttributeType::IVec4 => {
+            let components = (0..component_count(attr_type))
+                .map(|_| reader.read_i32())
+                .collect::<Result<_, _>>()?;
+            AttributeValue::Ints(components)
+        }
+        AttributeType::Vec2 | AttributeType::Vec3 | AttributeType::Vec4
+        | AttributeType::Mat2 | AttributeType::Mat3 | AttributeType::Mat3x4
+        | AttributeType::Mat4x3 | AttributeType::Mat4 => {
+            let components = (0..component_count(attr_type))
+                .map(|_| read_f32(&mut reader))
+                .collect::<Result<_, _>>()?;
+            AttributeValue::Floats(components)
+        }
+        AttributeType::String | AttributeType::Path | AttributeType::FixedString
+        | AttributeType::LSString | AttributeType::WString | AttributeType::LSWString => {
+            AttributeValue::String(decode_string(data))
+        }
+        AttributeType::Guid => {
+            let mut bytes = [0u8; 16];
+            bytes.copy_from_slice(reader.read_bytes(16)?);
+            AttributeValue::Guid(bytes)
+        }
+        AttributeType::ScratchBuffer => AttributeValue::ScratchBuffer(data.to_vec()),
+        AttributeType::TranslatedString => {
+            AttributeValue::TranslatedString(read_translated_string(&mut reader, context)?)
+        }
+        AttributeType::TranslatedFSString => {
+            AttributeValue::TranslatedFSString(read_translated_fs_string(&mut reader, context)?)
+        }
+    };
+    Ok(value)
+}
+
+/// Decode a NUL-terminated string value
+fn decode_string(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).to_string()
+}
+
+/// Read an i32 length-prefixed, NUL-terminated string
+fn read_length_prefixed_string(reader: &mut ByteReader) -> Result<String, String> {
+    let len = reader.read_i32()?;
+    if len < 0 {
+        return Err(format!("Negative string length {}", len));
+    }
+    Ok(decode_string(reader.read_bytes(len as usize)?))
+}
+
+fn read_translated_string(reader: &mut ByteReader, context: &ValueContext) -> Result<TranslatedString, String> {
+    let mut translated = TranslatedString::default();
+    if context.has_translated_string_version() {
+        translated.version = reader.read_u16()?;
+    } else {
+        translated.value = Some(read_length_prefixed_string(reader)?);
+    }
+    translated.handle = read_length_prefixed_string(reader)?;
+    Ok(translated)
+}
+
+fn read_translated_fs_string(reader: &mut ByteReader, context: &ValueContext) -> Result<TranslatedString, String> {
+    let mut translated = read_translated_string(reader, context)?;
+    let num_arguments = reader.read_i32()?;
+    for _ in 0..num_arguments.max(0) {
+        let key = read_length_prefixed_string(reader)?;
+        let string = read_translated_fs_string(reader, context)?;
+        let value = read_length_prefixed_string(reader)?;
+        translated.arguments.push(TranslatedFSArgument { key, string, value });
+    }
+    Ok(translated)
+}
+
+fn read_f32(reader: &mut ByteReader) -> Result<f32, String> {
+    Ok(f32::from_bits(reader.read_u32()?))
+}
+
+// ============================================================================
+// Value Encoding
+// ============================================================================
+
+/// Append the binary form of an attribute value to the value buffer
+fn write_attribute_value(out: &mut Vec<u8>, attribute: &Attribute, context: &ValueContext) -> Result<(), String> {
+    attribute.value.check_type(attribute.attr_type)?;
+    match &attribute.value {
+        AttributeValue::None => {}
+        AttributeValue::UInt8(v) => out.push(*v),
+        AttributeValue::Int8(v) => out.push(*v as u8),
+        AttributeValue::Int16(v) => out.extend_from_slice(&v.to_le_bytes()),
+        AttributeValue::UInt16(v) => out.extend_from_slice(&v.to_le_bytes()),
+        AttributeValue::Int32(v) => out.extend_from_slice(&v.to_le_bytes()),
+        AttributeValue::UInt32(v) => out.extend_from_slice(&v.to_le_bytes()),
+        AttributeValue::Int64(v) => out.extend_from_slice(&v.to_le_bytes()),
+        AttributeValue::UInt64(v) => out.extend_from_slice(&v.to_le_bytes()),
+        AttributeValue::Float(v) => out.extend_from_slice(&v.to_le_bytes()),
+        AttributeValue::Double(v) => out.extend_from_slice(&v.to_le_bytes()),
+        AttributeValue::Bool(v) => out.push(*v as u8),
+        AttributeValue::Ints(components) => {
+            for v in components {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        AttributeValue::Floats(components) => {
+            for v in components {
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        AttributeValue::String(s) => {
+            out.extend_from_slice(s.as_bytes());
+            out.push(0);
+        }
+        AttributeValue::Guid(bytes) => out.extend_from_slice(bytes),
+        AttributeValue::ScratchBuffer(bytes) => out.extend_from_slice(bytes),
+        AttributeValue::TranslatedString(translated) => write_translated_string(out, translated, context)?,
+        AttributeValue::TranslatedFSString(translated) => write_translated_fs_string(out, translated, context)?,
+    }
+    Ok(())
+}
+
+/// Write an i32 length-prefixed, NUL-terminated string
+fn write_length_prefixed_string(out: &mut Vec<u8>, s: &str) -> Result<(), String> {
+    let len = i32::try_from(s.len() + 1)
+        .map_err(|_| "string too long for LSF".to_string())?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+    Ok(())
+}
+
+fn write_translated_string(out: &mut Vec<u8>, translated: &TranslatedString, context: &ValueContext) -> Result<(), String> {
+    if context.has_translated_string_version() {
+        out.extend_from_slice(&translated.version.to_le_bytes());
+    } else {
+        write_length_prefixed_string(out, translated.value.as_deref().unwrap_or(""))?;
+    }
+    write_length_prefixed_string(out, &translated.handle)
+}
+
+fn write_translated_fs_string(out: &mut Vec<u8>, translated: &TranslatedString, context: &ValueContext) -> Result<(), String> {
+    write_translated_string(out, translated, context)?;
+    out.extend_from_slice(&(translated.arguments.len() as i32).to_le_bytes());
+    for argument in &translated.arguments {
+        write_length_prefixed_string(out, &argument.key)?;
+        write_translated_fs_string(out, &argument.string, context)?;
+        write_length_prefixed_string(out, &argument.value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every node, attribute and key name, stored in a single bucket
+    const NAMES: [&str; 12] = [
+        "Globals", "ItemList", "Item", "Stats", "Amount", "MapKey", "Types", "Flag", "Translate", "UUID", "Name", "Empty",
+    ];
+
+    /// An uncompressed LSF file laid out by hand: a gold stack with a node
+    /// key, a node with attributes of several types, and a second region
+    fn lsf_bytes(lsf_version: u32, format: MetadataFormat) -> Vec<u8> {
+        let guid = [0x06, 0x4f, 0xd7, 0x3e, 0x60, 0x3c, 0xdc, 0x42, 0xf6, 0x83, 0x34, 0xf0, 0x47, 0xcb, 0x79, 0xc6];
+        let translate: Vec<u8> = [-175.23f32, 24.91, 542.43].iter().flat_map(|v| v.to_le_bytes()).collect();
+        // (node index, name, type, value bytes)
+        let attributes: [(i32, &str, AttributeType, Vec<u8>); 6] = [
+            (2, "Stats", AttributeType::FixedString, b"LOOT_Gold_A\0".to_vec()),
+            (2, "Amount", AttributeType::Int32, 250i32.to_le_bytes().to_vec()),
+            (3, "Flag", AttributeType::Bool, vec![1]),
+            (3, "Translate", AttributeType::Vec3, translate),
+            (3, "UUID", AttributeType::Guid, guid.to_vec()),
+            (3, "Name", AttributeType::LSString, "héllo\0".as_bytes().to_vec()),
+        ];
+        // (name, parent, first attribute)
+        let nodes: [(&str, i32, i32); 5] = [("Globals", -1, -1), ("ItemList", 0, -1), ("Item", 1, 0), ("Types", 0, 2), ("Empty", -1, -1)];
+        let adjacency = lsf_version >= VER_EXTENDED_NODES && format == MetadataFormat::KeysAndAdjacency;
+
+        let mut strings = 1u32.to_le_bytes().to_vec();
+        strings.extend_from_slice(&(NAMES.len() as u16).to_le_bytes());
+        for name in NAMES {
+            strings.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            strings.extend_from_slice(name.as_bytes());
+        }
+        let name_ref = |name: &str| NAMES.iter().position(|n| *n == name).unwrap() as u32;
+
+        let mut node_table = Vec::new();
+        for (index, &(name, parent, first_attribute)) in nodes.iter().enumerate() {
+            node_table.extend_from_slice(&name_ref(name).to_le_bytes());
+            if adjacency {
+                let next_sibling = nodes.iter().skip(index + 1).position(|n| n.1 == parent).map_or(-1, |p| (index + 1 + p) as i32);
+                node_table.extend_from_slice(&parent.to_le_bytes());
+                node_table.extend_from_slice(&next_sibling.to_le_bytes());
+                node_table.extend_from_slice(&first_attribute.to_le_bytes());
+            } else {
+                node_table.extend_from_slice(&first_attribute.to_le_bytes());
+                node_table.extend_from_slice(&parent.to_le_bytes());
+            }
+        }
+
+        let mut attribute_table = Vec::new();
+        let mut values = Vec::new();
+        for (index, (node, name, attr_type, value)) in attributes.iter().enumerate() {
+            attribute_table.extend_from_slice(&name_ref(name).to_le_bytes());
+            attribute_table.extend_from_slice(&(attr_type.id() | ((value.len() as u32) << 6)).to_le_bytes());
+            if adjacency {
+                let next = attributes.get(index + 1).filter(|a| a.0 == *node).map_or(-1, |_| index as i32 + 1);
+                attribute_table.extend_from_slice(&next.to_le_bytes());
+                attribute_table.extend_from_slice(&(values.len() as u32).to_le_bytes());
+            } else {
+                attribute_table.extend_from_slice(&node.to_le_bytes());
+            }
+            values.extend_from_slice(value);
+        }
+
+        let mut keys = Vec::new();
+        if format == MetadataFormat::KeysAndAdjacency {
+            keys.extend_from_slice(&2u32.to_le_bytes());
+            keys.extend_from_slice(&name_ref("MapKey").to_le_bytes());
+        }
+
+        let version = EngineVersion { major: 4, minor: 0, revision: 9, build: 331 };
+        let mut bytes = LSF_SIGNATURE.to_vec();
+        bytes.extend_from_slice(&lsf_version.to_le_bytes());
+        if lsf_version >= VER_BG3_EXTENDED_HEADER {
+            bytes.extend_from_slice(&version.to_packed_i64().to_le_bytes());
+        } else {
+            bytes.extend_from_slice(&version.to_packed_i32().to_le_bytes());
+        }
+        let mut sizes = vec![strings.len()];
+        if lsf_version >= VER_BG3_ADDITIONAL_BLOB {
+            sizes.push(keys.len());
+        }
+        sizes.extend([node_table.len(), attribute_table.len(), values.len()]);
+        for size in sizes {
+            // Uncompressed size, then 0 on disk for an uncompressed section
+            bytes.extend_from_slice(&(size as u32).to_le_bytes());
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        bytes.extend_from_slice(&format.to_u32().to_le_bytes());
+        for section in [strings, node_table, attribute_table, values, keys] {
+            bytes.extend_from_slice(&section);
+        }
+        bytes
+    }
+
+    fn value(document: &Document, node: &str, attribute: &str) -> String {
+        document.node(document.find_nodes(node)[0]).attribute(attribute).unwrap().value.to_string()
+    }
+
+    #[test]
+    fn reads_every_version() {
+        for lsf_version in 1..=VER_BG3_PATCH3 {
+            let document = read(&lsf_bytes(lsf_version, MetadataFormat::None)).unwrap();
+
+            assert_eq!(document.lsf_version, lsf_version);
+            assert_eq!(document.version, EngineVersion { major: 4, minor: 0, revision: 9, build: 331 }, "version {}", lsf_version);
+            let regions: Vec<&str> = document.regions.iter().map(|r| r.id.as_str()).collect();
+            assert_eq!(regions, ["Globals", "Empty"], "version {}", lsf_version);
+            assert_eq!(value(&document, "Item", "Stats"), "LOOT_Gold_A");
+            assert_eq!(value(&document, "Item", "Amount"), "250");
+            assert_eq!(value(&document, "Types", "Flag"), "True");
+            assert_eq!(value(&document, "Types", "Translate"), "-175.23 24.91 542.43");
+            assert_eq!(value(&document, "Types", "UUID"), "3ed74f06-3c60-42dc-83f6-f034cb47c679");
+            assert_eq!(value(&document, "Types", "Name"), "héllo");
+        }
+    }
+
+    #[test]
+    fn reads_node_keys_and_adjacency() {
+        let document = read(&lsf_bytes(VER_BG3_PATCH3, MetadataFormat::KeysAndAdjacency)).unwrap();
+        assert_eq!(document.format, MetadataFormat::KeysAndAdjacency);
+        let item = document.find_nodes("Item")[0];
+        assert_eq!(document.node(item).key.as_deref(), Some("MapKey"));
+        assert_eq!(value(&document, "Item", "Amount"), "250");
+        assert_eq!(document.node(document.find_nodes("Types")[0]).attributes.len(), 4);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let mut data = LSF_SIGNATURE.to_vec();
+        data.extend_from_slice(&(VER_BG3_PATCH3 + 1).to_le_bytes());
+        assert!(read(&data).is_err());
+        assert!(read(b"LSPK").is_err());
+    }
+
+    #[test]
+    fn write_read_write_is_byte_identical() {
+        for lsf_version in 1..=VER_BG3_PATCH3 {
+            for format in [MetadataFormat::None, MetadataFormat::KeysAndAdjacency] {
+                let original = read(&lsf_bytes(lsf_version, format)).unwrap();
+                let written = write(&original).unwrap();
+                let document = read(&written).unwrap();
+
+                assert_eq!((document.lsf_version, document.format), (lsf_version, format));
+                assert_eq!(document.version, original.version, "version {}", lsf_version);
+                for name in ["Item", "Types"] {
+                    let attributes = |document: &Document| document.node(document.find_nodes(name)[0]).attributes.clone();
+                    assert_eq!(attributes(&document), attributes(&original), "version {} {:?}", lsf_version, format);
+                }
+                let key = |document: &Document| document.node(document.find_nodes("Item")[0]).key.clone();
+                assert_eq!(key(&document), key(&original));
+                assert_eq!(write(&document).unwrap(), written, "version {} {:?}", lsf_version, format);
+            }
+        }
+    }
+
+    #[test]
+    fn refuses_to_write_unknown_versions() {
+        let mut document = read(&lsf_bytes(VER_BG3_PATCH3, MetadataFormat::None)).unwrap();
+        document.lsf_version = 0;
+        assert!(write(&document).is_err());
+        document.lsf_version = VER_BG3_PATCH3 + 1;
+        assert!(write(&document).is_err());
+    }
+}
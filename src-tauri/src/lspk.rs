@@ -0,0 +1,602 @@
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use md5::{Digest, Md5};
+use crate::binutils::{self, ByteReader, CompressionMethod};
+use crate::error::AppError;
+
+// ============================================================================
+// Format Constants
+// ============================================================================
+
+/// Magic bytes at the start of every LSPK package (v10 and later)
+pub const LSPK_SIGNATURE: &[u8; 4] = b"LSPK";
+
+/// Early Access BG3 packages
+pub const VERSION_15: u32 = 15;
+/// Early Access BG3 packages with multi-part support
+pub const VERSION_16: u32 = 16;
+/// BG3 release packages (saves and game data)
+pub const VERSION_18: u32 = 18;
+
+/// Size of the header that follows the signature (`LSPKHeader15`)
+const HEADER_15_SIZE: usize = 34;
+/// Size of the header that follows the signature (`LSPKHeader16`)
+const HEADER_16_SIZE: usize = 36;
+/// Size of a single file table entry in v15/v16 packages
+const FILE_ENTRY_15_SIZE: usize = 296;
+/// Size of a single file table entry in v18 packages
+const FILE_ENTRY_18_SIZE: usize = 272;
+/// Size of the NUL-padded name field in every file table entry
+const FILE_NAME_SIZE: usize = 256;
+/// Largest offset representable by the 48-bit v18 entry offset field
+const MAX_OFFSET_18: u64 = (1 << 48) - 1;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// A single file stored inside an LSPK package
+#[derive(Debug, Clone)]
+pub struct PackagedFile {
+    pub name: String,
+    pub offset: u64,
+    pub size_on_disk: u64,
+    pub uncompressed_size: u64,
+    pub archive_part: u32,
+    pub flags: u8,
+}
+
+/// An opened LSPK package with its parsed header and file table
+#[derive(Debug)]
+pub struct Package {
+    pub version: u32,
+    pub flags: u8,
+    pub priority: u8,
+    pub files: Vec<PackagedFile>,
+    path: PathBuf,
+}
+
+// ============================================================================
+// Package Reading
+// ============================================================================
+
+impl Package {
+    /// Open a package and read its header and file table
+    pub fn open(path: &Path) -> Result<Package, AppError> {
+        let mut file = File::open(path).map_err(|e| AppError::io(path, e))?;
+        let parse_error = |e: String| AppError::parse(e).in_file(path);
+
+        let mut signature = [0u8; 4];
+        file.read_exact(&mut signature).map_err(|e| AppError::io(path, e))?;
+        if &signature != LSPK_SIGNATURE {
+            return Err(parse_error("Not an LSPK package".to_string()));
+        }
+
+        let mut version_bytes = [0u8; 4];
+        file.read_exact(&mut version_bytes).map_err(|e| AppError::io(path, e))?;
+        let version = u32::from_le_bytes(version_bytes);
+
+        let header_size = match version {
+            VERSION_15 => HEADER_15_SIZE,
+            VERSION_16 | VERSION_18 => HEADER_16_SIZE,
+            other => return Err(AppError::UnsupportedVersion { format: "LSPK package".to_string(), version: other }),
+        };
+
+        let header_bytes = read_at(&mut file, path, LSPK_SIGNATURE.len() as u64, header_size)?;
+        let mut header = ByteReader::new(&header_bytes);
+        let _version = header.read_u32().map_err(parse_error)?;
+        let file_list_offset = header.read_u64().map_err(parse_error)?;
+        let _file_list_size = header.read_u32().map_err(parse_error)?;
+        let flags = header.read_u8().map_err(parse_error)?;
+        let priority = header.read_u8().map_err(parse_error)?;
+        let _md5 = header.read_bytes(16).map_err(parse_error)?;
+
+        let files = read_file_list(&mut file, path, version, file_list_offset)?;
+        println!("Opened LSPK v{} package with {} files", version, files.len());
+
+        Ok(Package {
+            version,
+            flags,
+            priority,
+            files,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Find a packaged file by its path inside the package
+    pub fn find_file(&self, name: &str) -> Option<&PackagedFile> {
+        self.files.iter().find(|f| f.name == name)
+    }
+
+    /// Read and decompress the contents of a packaged file
+    pub fn read_file(&self, entry: &PackagedFile) -> Result<Vec<u8>, AppError> {
+        if entry.archive_part != 0 {
+            return Err(AppError::validation(format!(
+                "{} is stored in archive part {}; multi-part packages are not supported",
+                entry.name, entry.archive_part
+            )));
+        }
+
+        let mut file = File::open(&self.path).map_err(|e| AppError::io(&self.path, e))?;
+        let stored = read_at(&mut file, &self.path, entry.offset, entry.size_on_disk as usize)?;
+
+        let parse_error = |e: String| AppError::parse(format!("{}: {}", entry.name, e)).in_file(&self.path);
+        if CompressionMethod::from_flags(entry.flags).map_err(parse_error)? == CompressionMethod::None {
+            return Ok(stored);
+        }
+
+        binutils::decompress(&stored, entry.uncompressed_size as usize, entry.flags, false)
+            .map_err(parse_error)
+    }
+
+    /// Extract every packaged file into `output_dir`, preserving relative paths
+    pub fn extract_all(&self, output_dir: &Path) -> Result<usize, AppError> {
+        for entry in &self.files {
+            let target = output_dir.join(safe_relative_path(&entry.name)?);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+            }
+
+            let contents = self.read_file(entry)?;
+            std::fs::write(&target, contents).map_err(|e| AppError::io(&target, e))?;
+        }
+
+        println!("Extracted {} files to {:?}", self.files.len(), output_dir);
+        Ok(self.files.len())
+    }
+}
+
+/// Extract a package to a directory in one step
+pub fn extract_package(pkg_path: &Path, output_dir: &Path) -> Result<usize, AppError> {
+    Package::open(pkg_path)?.extract_all(output_dir)
+}
+
+// ============================================================================
+// Package Writing
+// ============================================================================
+
+/// A file queued for writing, with the compression flags it will be stored with
+struct PendingFile {
+    name: String,
+    data: Vec<u8>,
+    flags: u8,
+}
+
+/// Builds a v18 LSPK package from in-memory files or an extracted directory
+pub struct PackageWriter {
+    pub flags: u8,
+    pub priority: u8,
+    pub compression: CompressionMethod,
+    files: Vec<PendingFile>,
+}
+
+impl PackageWriter {
+    /// Create an empty writer with LZ4 compression, flags 0 and priority 0 (as used by saves)
+    pub fn new() -> Self {
+        PackageWriter {
+            flags: 0,
+            priority: 0,
+            compression: CompressionMethod::Lz4,
+            files: Vec::new(),
+        }
+    }
+
+    /// Create an empty writer that copies the header fields of an existing package
+    pub fn like(package: &Package) -> Self {
+        PackageWriter {
+            flags: package.flags,
+            priority: package.priority,
+            ..PackageWriter::new()
+        }
+    }
+
+    /// Queue a file using the writer's default compression
+    pub fn add_file(&mut self, name: &str, data: Vec<u8>) {
+        let flags = self.compression.to_flags();
+        self.add_file_with_flags(name, data, flags);
+    }
+
+    /// Queue a file with explicit per-entry compression flags
+    pub fn add_file_with_flags(&mut self, name: &str, data: Vec<u8>, flags: u8) {
+        // Empty files are never compressed
+        let flags = if data.is_empty() { 0 } else { flags };
+        self.files.push(PendingFile {
+            name: name.replace('\\', "/"),
+            data,
+            flags,
+        });
+    }
+
+    /// Queue every file under `dir`, skipping hidden files
+    pub fn add_directory(&mut self, dir: &Path) -> Result<usize, AppError> {
+        let mut paths = Vec::new();
+        collect_files(dir, &mut paths)?;
+        paths.sort();
+
+        for path in &paths {
+            let relative = path.strip_prefix(dir)
+                .map_err(|e| AppError::validation(format!("Invalid path {}: {}", path.display(), e)))?;
+            let name = relative.to_string_lossy().replace('\\', "/");
+            let data = std::fs::read(path).map_err(|e| AppError::io(path, e))?;
+            self.add_file(&name, data);
+        }
+        Ok(paths.len())
+    }
+
+    /// Compress all queued files and write the package to `output_path`
+    pub fn write(&self, output_path: &Path) -> Result<(), AppError> {
+        let file = File::create(output_path).map_err(|e| AppError::io(output_path, e))?;
+        let mut writer = BufWriter::new(file);
+        let io_err = |e: std::io::Error| AppError::io(output_path, e);
+
+        // Reserve space for the header; it is rewritten once offsets are known
+        writer.write_all(LSPK_SIGNATURE).map_err(io_err)?;
+        writer.write_all(&[0u8; HEADER_16_SIZE]).map_err(io_err)?;
+
+        let mut offset = (LSPK_SIGNATURE.len() + HEADER_16_SIZE) as u64;
+        let mut entries = Vec::with_capacity(self.files.len());
+        for pending in &self.files {
+            let stored = binutils::compress(&pending.data, pending.flags, false)
+                .map_err(|e| AppError::validation(format!("Failed to compress {}: {}", pending.name, e)))?;
+            if offset > MAX_OFFSET_18 || stored.len() > u32::MAX as usize || pending.data.len() > u32::MAX as usize {
+                return Err(AppError::validation(format!("{} does not fit in a v18 package", pending.name)));
+            }
+
+            writer.write_all(&stored).map_err(io_err)?;
+            let is_compressed = CompressionMethod::from_flags(pending.flags)
+                .map_err(AppError::validation)? != CompressionMethod::None;
+            entries.push(PackagedFile {
+                name: pending.name.clone(),
+                offset,
+                size_on_disk: stored.len() as u64,
+                uncompressed_size: if is_compressed { pending.data.len() as u64 } else { 0 },
+                archive_part: 0,
+                flags: pending.flags,
+            });
+            offset += stored.len() as u64;
+        }
+
+        let file_list = build_file_list_18(&entries)?;
+        writer.write_all(&file_list).map_err(io_err)?;
+
+        let header = build_header_16(self, offset, file_list.len() as u32, &self.compute_hash());
+        writer.seek(SeekFrom::Start(LSPK_SIGNATURE.len() as u64)).map_err(io_err)?;
+        writer.write_all(&header).map_err(io_err)?;
+        writer.flush().map_err(io_err)?;
+
+        println!("Wrote LSPK v{} package with {} files to {:?}", VERSION_18, entries.len(), output_path);
+        Ok(())
+    }
+
+    /// MD5 over the uncompressed contents of all files, with every byte incremented by one
+    fn compute_hash(&self) -> [u8; 16] {
+        let mut hasher = Md5::new();
+        for pending in &self.files {
+            hasher.update(&pending.data);
+        }
+        let mut hash: [u8; 16] = hasher.finalize().into();
+        for byte in hash.iter_mut() {
+            *byte = byte.wrapping_add(1);
+        }
+        hash
+    }
+}
+
+impl Default for PackageWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pack every file under `source_dir` into a new package in one step
+pub fn create_package(source_dir: &Path, output_path: &Path) -> Result<usize, AppError> {
+    let mut writer = PackageWriter::new();
+    let count = writer.add_directory(source_dir)?;
+    writer.write(output_path)?;
+    Ok(count)
+}
+
+/// Repack an extracted package, keeping the original file list, order,
+/// per-entry compression flags and header fields
+pub fn repack_like(original: &Path, extracted_dir: &Path, output_path: &Path) -> Result<usize, AppError> {
+    let package = Package::open(original)?;
+    let mut writer = PackageWriter::like(&package);
+
+    for entry in &package.files {
+        let path = extracted_dir.join(safe_relative_path(&entry.name)?);
+        let data = std::fs::read(&path).map_err(|e| AppError::io(&path, e))?;
+        writer.add_file_with_flags(&entry.name, data, entry.flags);
+    }
+
+    writer.write(output_path)?;
+    Ok(package.files.len())
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+/// Read `len` bytes starting at `offset`; running past the end is a parse error
+fn read_at(file: &mut File, path: &Path, offset: u64, len: usize) -> Result<Vec<u8>, AppError> {
+    file.seek(SeekFrom::Start(offset)).map_err(|e| AppError::io(path, e))?;
+    let mut buffer = vec![0u8; len];
+    file.read_exact(&mut buffer).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => {
+            AppError::parse(format!("Truncated data: wanted {} bytes at offset {}", len, offset)).in_file(path)
+        }
+        _ => AppError::io(path, e),
+    })?;
+    Ok(buffer)
+}
+
+/// Read and decompress the LZ4-compressed file table
+fn read_file_list(file: &mut File, path: &Path, version: u32, offset: u64) -> Result<Vec<PackagedFile>, AppError> {
+    let parse_error = |e: String| AppError::parse(e).in_file(path);
+    let counts = read_at(file, path, offset, 8)?;
+    let mut counts = ByteReader::new(&counts);
+    let num_files = counts.read_u32().map_err(parse_error)? as usize;
+    let compressed_size = counts.read_u32().map_err(parse_error)? as usize;
+
+    let entry_size = if version == VERSION_18 { FILE_ENTRY_18_SIZE } else { FILE_ENTRY_15_SIZE };
+    let compressed = read_at(file, path, offset + 8, compressed_size)?;
+    let table = lz4_flex::block::decompress(&compressed, num_files * entry_size)
+        .map_err(|e| parse_error(format!("Failed to decompress package file list: {}", e)))?;
+
+    let mut reader = ByteReader::new(&table);
+    let mut files = Vec::with_capacity(num_files);
+    for _ in 0..num_files {
+        let entry = if version == VERSION_18 {
+            read_file_entry_18(&mut reader)
+        } else {
+            read_file_entry_15(&mut reader)
+        };
+        files.push(entry.map_err(parse_error)?);
+    }
+    Ok(files)
+}
+
+/// Parse a `FileEntry18` record
+fn read_file_entry_18(reader: &mut ByteReader) -> Result<PackagedFile, String> {
+    let name = binutils::read_padded_string(reader.read_bytes(FILE_NAME_SIZE)?);
+    let offset_low = reader.read_u32()? as u64;
+    let offset_high = reader.read_u16()? as u64;
+    let archive_part = reader.read_u8()? as u32;
+    let flags = reader.read_u8()?;
+    let size_on_disk = reader.read_u32()? as u64;
+    let uncompressed_size = reader.read_u32()? as u64;
+
+    Ok(PackagedFile {
+        name,
+        offset: offset_low | (offset_high << 32),
+        size_on_disk,
+        uncompressed_size,
+        archive_part,
+        flags,
+    })
+}
+
+/// Parse a `FileEntry15` record
+fn read_file_entry_15(reader: &mut ByteReader) -> Result<PackagedFile, String> {
+    let name = binutils::read_padded_string(reader.read_bytes(FILE_NAME_SIZE)?);
+    let offset = reader.read_u64()?;
+    let size_on_disk = reader.read_u64()?;
+    let uncompressed_size = reader.read_u64()?;
+    let archive_part = reader.read_u32()?;
+    let flags = reader.read_u32()? as u8;
+    let _crc = reader.read_u32()?;
+    let _unknown = reader.read_u32()?;
+
+    Ok(PackagedFile {
+        name,
+        offset,
+        size_on_disk,
+        uncompressed_size,
+        archive_part,
+        flags,
+    })
+}
+
+/// Serialize and LZ4-compress the v18 file table
+fn build_file_list_18(entries: &[PackagedFile]) -> Result<Vec<u8>, AppError> {
+    let mut table = Vec::with_capacity(entries.len() * FILE_ENTRY_18_SIZE);
+    for entry in entries {
+        let name = entry.name.as_bytes();
+        if name.len() >= FILE_NAME_SIZE {
+            return Err(AppError::validation(format!("File name too long for package: {}", entry.name)));
+        }
+        let mut name_field = [0u8; FILE_NAME_SIZE];
+        name_field[..name.len()].copy_from_slice(name);
+
+        table.extend_from_slice(&name_field);
+        table.extend_from_slice(&(entry.offset as u32).to_le_bytes());
+        table.extend_from_slice(&((entry.offset >> 32) as u16).to_le_bytes());
+        table.push(entry.archive_part as u8);
+        table.push(entry.flags);
+        table.extend_from_slice(&(entry.size_on_disk as u32).to_le_bytes());
+        table.extend_from_slice(&(entry.uncompressed_size as u32).to_le_bytes());
+    }
+
+    let compressed = lz4_flex::block::compress(&table);
+    let mut file_list = Vec::with_capacity(8 + compressed.len());
+    file_list.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    file_list.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    file_list.extend_from_slice(&compressed);
+    Ok(file_list)
+}
+
+/// Serialize an `LSPKHeader16` (the part following the signature)
+fn build_header_16(writer: &PackageWriter, file_list_offset: u64, file_list_size: u32, md5: &[u8; 16]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_16_SIZE);
+    header.extend_from_slice(&VERSION_18.to_le_bytes());
+    header.extend_from_slice(&file_list_offset.to_le_bytes());
+    header.extend_from_slice(&file_list_size.to_le_bytes());
+    header.push(writer.flags);
+    header.push(writer.priority);
+    header.extend_from_slice(md5);
+    header.extend_from_slice(&1u16.to_le_bytes()); // NumParts
+    header
+}
+
+/// Recursively list regular files under `dir`, skipping hidden entries
+fn collect_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    let entries = std::fs::read_dir(dir).map_err(|e| AppError::io(dir, e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if is_hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Reject packaged names that would escape the extraction directory
+fn safe_relative_path(name: &str) -> Result<PathBuf, AppError> {
+    let path = PathBuf::from(name.replace('\\', "/"));
+    let is_safe = path.components().all(|c| matches!(c, Component::Normal(_)));
+    if !is_safe || name.is_empty() {
+        return Err(AppError::validation(format!("Refusing to extract unsafe path from package: {}", name)));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir for one test
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lspk-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A v18 package laid out by hand: header, file data, then the LZ4 file table
+    fn package_bytes(files: &[(&str, &[u8], u8)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut table = Vec::new();
+        let data_start = (LSPK_SIGNATURE.len() + HEADER_16_SIZE) as u32;
+        for (name, contents, flags) in files {
+            let stored = match CompressionMethod::from_flags(*flags).unwrap() {
+                CompressionMethod::None => contents.to_vec(),
+                CompressionMethod::Lz4 => lz4_flex::block::compress(contents),
+                other => panic!("no test encoder for {:?}", other),
+            };
+            let mut name_field = [0u8; FILE_NAME_SIZE];
+            name_field[..name.len()].copy_from_slice(name.as_bytes());
+            table.extend_from_slice(&name_field);
+            table.extend_from_slice(&(data_start + data.len() as u32).to_le_bytes());
+            table.extend_from_slice(&0u16.to_le_bytes());
+            table.push(0);
+            table.push(*flags);
+            table.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            table.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            data.extend_from_slice(&stored);
+        }
+        let table = lz4_flex::block::compress(&table);
+
+        let mut bytes = LSPK_SIGNATURE.to_vec();
+        bytes.extend_from_slice(&VERSION_18.to_le_bytes());
+        bytes.extend_from_slice(&(data_start as u64 + data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(8 + table.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&[0; 16]);
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&data);
+        bytes.extend_from_slice(&(files.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(table.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&table);
+        bytes
+    }
+
+    #[test]
+    fn reads_file_list_and_contents() {
+        let dir = scratch_dir("read");
+        let path = dir.join("test.lsv");
+        let globals = vec![7; 5000];
+        std::fs::write(&path, package_bytes(&[
+            ("SaveInfo.json", b"{\"Version\":1}", 0),
+            ("Globals.lsf", &globals, 2),
+            ("LevelCache/WLD_Main_A.lsf", &[3; 50], 0),
+            ("Empty.txt", &[], 0),
+        ])).unwrap();
+
+        let package = Package::open(&path).unwrap();
+        assert_eq!(package.version, VERSION_18);
+        let names: Vec<&str> = package.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["SaveInfo.json", "Globals.lsf", "LevelCache/WLD_Main_A.lsf", "Empty.txt"]);
+
+        let read = |name: &str| package.read_file(package.find_file(name).unwrap()).unwrap();
+        assert_eq!(read("SaveInfo.json"), b"{\"Version\":1}");
+        assert_eq!(read("Globals.lsf"), globals);
+        assert_eq!(read("LevelCache/WLD_Main_A.lsf"), vec![3; 50]);
+        assert!(read("Empty.txt").is_empty());
+        assert!(package.find_file("Missing.lsf").is_none());
+
+        let extracted = dir.join("extracted");
+        assert_eq!(extract_package(&path, &extracted).unwrap(), 4);
+        assert_eq!(std::fs::read(extracted.join("LevelCache/WLD_Main_A.lsf")).unwrap(), vec![3; 50]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_unsafe_paths() {
+        let dir = scratch_dir("unsafe");
+        let path = dir.join("test.lsv");
+        std::fs::write(&path, package_bytes(&[("../outside.txt", b"x", 0)])).unwrap();
+        assert!(extract_package(&path, &dir.join("extracted")).is_err());
+        assert!(!dir.join("outside.txt").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn written_packages_read_back() {
+        let dir = scratch_dir("write");
+        let path = dir.join("test.lsv");
+        let mut writer = PackageWriter::new();
+        writer.add_file("SaveInfo.json", b"{\"Version\":1}".to_vec());
+        writer.add_file_with_flags("Globals.lsf", vec![7; 5000], CompressionMethod::Zlib.to_flags());
+        writer.add_file_with_flags("LevelCache/WLD_Main_A.lsf", vec![3; 50], CompressionMethod::None.to_flags());
+        writer.add_file("Empty.txt", Vec::new());
+        writer.write(&path).unwrap();
+
+        let package = Package::open(&path).unwrap();
+        assert_eq!(package.version, VERSION_18);
+        let read = |name: &str| package.read_file(package.find_file(name).unwrap()).unwrap();
+        assert_eq!(read("SaveInfo.json"), b"{\"Version\":1}");
+        assert_eq!(read("Globals.lsf"), vec![7; 5000]);
+        assert_eq!(read("LevelCache/WLD_Main_A.lsf"), vec![3; 50]);
+        assert!(read("Empty.txt").is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn create_extract_repack_is_byte_identical() {
+        let dir = scratch_dir("repack");
+        let source = dir.join("source");
+        std::fs::create_dir_all(source.join("LevelCache")).unwrap();
+        std::fs::write(source.join("SaveInfo.json"), b"{\"Version\":1}").unwrap();
+        std::fs::write(source.join("Globals.lsf"), (0..20000u32).map(|i| (i % 251) as u8).collect::<Vec<u8>>()).unwrap();
+        std::fs::write(source.join("LevelCache/WLD_Main_A.lsf"), vec![9; 100000]).unwrap();
+        std::fs::write(source.join(".source_path"), b"hidden").unwrap();
+
+        let original = dir.join("original.lsv");
+        assert_eq!(create_package(&source, &original).unwrap(), 3);
+        let extracted = dir.join("extracted");
+        assert_eq!(extract_package(&original, &extracted).unwrap(), 3);
+        assert!(!extracted.join(".source_path").exists());
+
+        let repacked = dir.join("repacked.lsv");
+        assert_eq!(repack_like(&original, &extracted, &repacked).unwrap(), 3);
+        assert_eq!(std::fs::read(&original).unwrap(), std::fs::read(&repacked).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,740 @@
+use std::collections::HashMap;
+use std::path::Path;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use crate::document::{
+    Attribute, AttributeSource, AttributeType, Document, EngineVersion, LsxSource, MetadataFormat,
+    NodeId, NodeSource, Region, RegionSource, SourceSpan, TranslatedFSArgument, TranslatedString,
+};
+use crate::error::AppError;
+use crate::values::AttributeValue;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// The element currently being parsed, kept on a stack that mirrors the XML nesting
+enum Frame {
+    Save,
+    Version,
+    Region(String),
+    Node(NodeId),
+    /// `<children>` of the given node
+    Children(NodeId),
+    /// Owning node and index of the attribute in its attribute list
+    Attribute(NodeId, usize),
+    Arguments,
+    Argument(TranslatedFSArgument),
+    String(TranslatedString),
+    /// Elements LSLib ignores (e.g. the DOS2 `<header>`)
+    Ignored,
+}
+
+/// An open element with the text needed to reproduce its start tag
+struct OpenElement {
+    frame: Frame,
+    start: usize,
+    open: SourceSpan,
+}
+
+/// Streaming LSX parser state
+struct Parser<'a> {
+    text: &'a str,
+    document: Document,
+    source: LsxSource,
+    stack: Vec<OpenElement>,
+    /// Whitespace, comments and declarations since the last tag
+    pending: String,
+}
+
+/// Line break, indentation and empty-tag style used for generated markup
+struct Layout {
+    newline: String,
+    indent: String,
+    empty_tag_end: String,
+}
+
+// ============================================================================
+// Public API Functions
+// ============================================================================
+
+/// Read an LSX file from disk into a document tree
+pub fn read_file(path: &Path) -> Result<Document, AppError> {
+    let text = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+    read(&text).map_err(|e| e.in_file(path))
+}
+
+/// Parse LSX text into a document tree, keeping the original markup so
+/// unchanged parts can be written back byte-for-byte
+pub fn read(text: &str) -> Result<Document, AppError> {
+    let stripped = text.strip_prefix('\u{feff}');
+    let text = stripped.unwrap_or(text);
+
+    let mut parser = Parser {
+        text,
+        document: Document::new(),
+        source: LsxSource {
+            bom: stripped.is_some(),
+            prolog: String::new(),
+            save_open: String::new(),
+            version: None,
+            original_version: EngineVersion::default(),
+            original_format: MetadataFormat::None,
+            lslib_meta: None,
+            save_close: SourceSpan::default(),
+            epilogue: String::new(),
+            newline: detect_newline(text).to_string(),
+            indent: String::new(),
+            empty_tag_end: " />".to_string(),
+        },
+        stack: Vec::new(),
+        pending: String::new(),
+    };
+    parser.parse()?;
+
+    let mut document = parser.document;
+    document.lsx_source = Some(parser.source);
+    Ok(document)
+}
+
+/// Serialize a document tree to an LSX file on disk
+pub fn write_file(document: &Document, path: &Path) -> Result<(), AppError> {
+    std::fs::write(path, write(document)).map_err(|e| AppError::io(path, e))
+}
+
+/// Serialize a document tree to LSX text. Parts of a parsed document that
+/// were not modified are written back exactly as they were read; new or
+/// changed elements are formatted like LSLib, following the file's layout.
+pub fn write(document: &Document) -> String {
+    let source = document.lsx_source.as_ref();
+    let layout = match source {
+        Some(source) => Layout {
+            newline: source.newline.clone(),
+            indent: source.indent.clone(),
+            empty_tag_end: source.empty_tag_end.clone(),
+        },
+        None => Layout {
+            newline: "\r\n".to_string(),
+            indent: "\t".to_string(),
+            empty_tag_end: " />".to_string(),
+        },
+    };
+
+    let mut out = String::new();
+    match source {
+        Some(source) => {
+            if source.bom {
+                out.push('\u{feff}');
+            }
+            out.push_str(&source.prolog);
+            out.push_str(&source.save_open);
+        }
+        None => {
+            out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>");
+            out.push_str(&layout.newline);
+            out.push_str("<save>");
+        }
+    }
+
+    write_version(&mut out, document, &layout);
+    for region in &document.regions {
+        write_region(&mut out, document, region, &layout);
+    }
+
+    match source {
+        Some(source) => {
+            push_span(&mut out, &source.save_close);
+            out.push_str(&source.epilogue);
+        }
+        None => {
+            out.push_str(&layout.newline);
+            out.push_str("</save>");
+        }
+    }
+    out
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+impl Parser<'_> {
+    fn parse(&mut self) -> Result<(), AppError> {
+        let text = self.text;
+        let mut reader = Reader::from_str(text);
+        let mut last = 0usize;
+
+        loop {
+            let event = reader.read_event().map_err(|e| {
+                error_at(text, reader.error_position() as usize, format!("Invalid XML: {}", e))
+            })?;
+            let start = last;
+            last = reader.buffer_position() as usize;
+            let located = |message: String| error_at(text, start, message);
+
+            match event {
+                Event::Start(element) => {
+                    let frame = self.open_element(&element, start, last, &reader).map_err(located)?;
+                    let open = self.take_span(start, last);
+                    self.stack.push(OpenElement { frame, start, open });
+                }
+                Event::Empty(element) => {
+                    let frame = self.open_element(&element, start, last, &reader).map_err(located)?;
+                    let open = self.take_span(start, last);
+                    self.close_element(OpenElement { frame, start, open }, None, last).map_err(located)?;
+                }
+                Event::End(_) => {
+                    let element = self.stack.pop()
+                        .ok_or_else(|| located("Unbalanced closing tag".to_string()))?;
+                    let close = self.take_span(start, last);
+                    self.close_element(element, Some(close), last).map_err(located)?;
+                }
+                Event::Eof => break,
+                _ => self.pending.push_str(&text[start..last]),
+            }
+        }
+
+        if !self.stack.is_empty() {
+            return Err(error_at(text, last, "Unexpected end of file inside an element".to_string()));
+        }
+        self.source.epilogue = std::mem::take(&mut self.pending);
+        Ok(())
+    }
+
+    /// The pending trivia and the tag text between `start` and `end`
+    fn take_span(&mut self, start: usize, end: usize) -> SourceSpan {
+        SourceSpan {
+            leading: std::mem::take(&mut self.pending),
+            raw: self.text[start..end].to_string(),
+        }
+    }
+
+    /// Apply an opening tag to the document and return the frame it starts
+    fn open_element(
+        &mut self,
+        element: &BytesStart,
+        start: usize,
+        end: usize,
+        reader: &Reader<&[u8]>,
+    ) -> Result<Frame, String> {
+        let tag = String::from_utf8_lossy(element.name().as_ref()).to_string();
+        let attrs = element_attributes(element, reader)?;
+        let document = &mut self.document;
+
+        let frame = match (self.stack.last().map(|e| &e.frame), tag.as_str()) {
+            (None, "save") => {
+                self.source.prolog = std::mem::take(&mut self.pending);
+                self.source.save_open = self.text[start..end].to_string();
+                Frame::Save
+            }
+            (Some(Frame::Save), "version") => {
+                document.version = read_version(&attrs)?;
+                if let Some(meta) = attrs.get("lslib_meta") {
+                    document.format = metadata_format_from_meta(meta);
+                }
+                self.source.original_version = document.version;
+                self.source.original_format = document.format;
+                self.source.lslib_meta = attrs.get("lslib_meta").cloned();
+                self.source.indent = self.pending.rsplit('\n').next().unwrap_or("").to_string();
+                if self.text[start..end].ends_with("/>") && !self.text[start..end].ends_with(" />") {
+                    self.source.empty_tag_end = "/>".to_string();
+                }
+                Frame::Version
+            }
+            (Some(Frame::Save), "region") => Frame::Region(required(&attrs, "id", "region")?.to_string()),
+            (Some(Frame::Region(_)), "node") => Frame::Node(add_node(document, None, &attrs)?),
+            (Some(Frame::Node(node)), "children") => Frame::Children(*node),
+            (Some(Frame::Children(parent)), "node") => Frame::Node(add_node(document, Some(*parent), &attrs)?),
+            (Some(Frame::Node(node)), "attribute") => {
+                let attribute = read_attribute(&attrs)?;
+                let attributes = &mut document.node_mut(*node).attributes;
+                attributes.push(attribute);
+                Frame::Attribute(*node, attributes.len() - 1)
+            }
+            (Some(Frame::Attribute(..)), "arguments") | (Some(Frame::String(_)), "arguments") => Frame::Arguments,
+            (Some(Frame::Arguments), "argument") => Frame::Argument(TranslatedFSArgument {
+                key: attrs.get("key").cloned().unwrap_or_default(),
+                string: TranslatedString::default(),
+                value: attrs.get("value").cloned().unwrap_or_default(),
+            }),
+            (Some(Frame::Argument(_)), "string") => Frame::String(read_translated_string(&attrs)?),
+            (Some(Frame::Save), _) => Frame::Ignored,
+            (Some(Frame::Ignored), _) => Frame::Ignored,
+            (_, other) => return Err(format!("Unexpected <{}> element", other)),
+        };
+        Ok(frame)
+    }
+
+    /// Finish an element: record its markup and attach nested
+    /// TranslatedFSString arguments to their owner. `close` is `None`
+    /// for self-closing elements.
+    fn close_element(&mut self, element: OpenElement, close: Option<SourceSpan>, end: usize) -> Result<(), String> {
+        let document = &mut self.document;
+        match element.frame {
+            Frame::Save => {
+                self.source.save_close = close.unwrap_or_default();
+            }
+            Frame::Version => {
+                let raw = self.text[element.start..end].to_string();
+                self.source.version = Some(SourceSpan { leading: element.open.leading, raw });
+            }
+            Frame::Region(id) => {
+                let region = document.regions.iter_mut().find(|r| r.id == id)
+                    .ok_or_else(|| format!("Region {} has no root node", id))?;
+                region.source = Some(RegionSource {
+                    open: element.open,
+                    id,
+                    close: close.unwrap_or_default(),
+                });
+            }
+            Frame::Node(node) => {
+                // A node directly inside <region> is the region's root
+                if let Some(Frame::Region(id)) = self.stack.last().map(|e| &e.frame) {
+                    if document.region(id).is_some() {
+                        return Err(format!("Region {} has more than one root node", id));
+                    }
+                    document.add_region(id, node);
+                }
+                let name = document.node(node).name.clone();
+                let key = document.node(node).key.clone();
+                let source = document.node_mut(node).source.get_or_insert_with(NodeSource::default);
+                source.open = element.open;
+                source.name = name;
+                source.key = key;
+                source.close = close;
+            }
+            Frame::Children(node) => {
+                let source = document.node_mut(node).source.get_or_insert_with(NodeSource::default);
+                source.children_open = Some(element.open);
+                source.children_close = close;
+            }
+            Frame::Attribute(node, index) => {
+                let attribute = &mut document.node_mut(node).attributes[index];
+                let span = SourceSpan {
+                    leading: element.open.leading,
+                    raw: self.text[element.start..end].to_string(),
+                };
+                attribute.source = Some(AttributeSource { span, original: Box::new(attribute.clone()) });
+            }
+            Frame::String(string) => {
+                if let Some(Frame::Argument(argument)) = self.stack.last_mut().map(|e| &mut e.frame) {
+                    argument.string = string;
+                }
+            }
+            Frame::Argument(argument) => {
+                let owner = self.stack.len().checked_sub(2).map(|i| &mut self.stack[i].frame);
+                match owner {
+                    Some(Frame::Attribute(node, index)) => {
+                        let attribute = &mut document.node_mut(*node).attributes[*index];
+                        match &mut attribute.value {
+                            AttributeValue::TranslatedFSString(string) => string.arguments.push(argument),
+                            _ => return Err(format!("Attribute {} cannot have arguments", attribute.id)),
+                        }
+                    }
+                    Some(Frame::String(string)) => string.arguments.push(argument),
+                    _ => return Err("<argument> is not inside an <arguments> list".to_string()),
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Parse error pointing at the line containing byte `offset`
+fn error_at(text: &str, offset: usize, message: String) -> AppError {
+    let offset = offset.min(text.len());
+    let line = text.as_bytes()[..offset].iter().filter(|&&b| b == b'\n').count() + 1;
+    AppError::Parse { file: None, line: Some(line), message }
+}
+
+/// Line break style of the file, or "" when it is all on one line
+fn detect_newline(text: &str) -> &'static str {
+    if text.contains("\r\n") {
+        "\r\n"
+    } else if text.contains('\n') {
+        "\n"
+    } else {
+        ""
+    }
+}
+
+/// Decode and unescape all XML attributes of an element
+fn element_attributes(element: &BytesStart, reader: &Reader<&[u8]>) -> Result<HashMap<String, String>, String> {
+    let mut attrs = HashMap::new();
+    for attr in element.attributes() {
+        let attr = attr.map_err(|e| format!("Invalid XML attribute: {}", e))?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+        let value = attr.decode_and_unescape_value(reader.decoder())
+            .map_err(|e| format!("Invalid value for XML attribute {}: {}", key, e))?;
+        attrs.insert(key, value.to_string());
+    }
+    Ok(attrs)
+}
+
+fn required<'a>(attrs: &'a HashMap<String, String>, name: &str, tag: &str) -> Result<&'a str, String> {
+    attrs.get(name)
+        .map(|s| s.as_str())
+        .ok_or_else(|| format!("<{}> is missing the '{}' attribute", tag, name))
+}
+
+fn add_node(document: &mut Document, parent: Option<NodeId>, attrs: &HashMap<String, String>) -> Result<NodeId, String> {
+    let name = required(attrs, "id", "node")?;
+    let node = document.add_node(parent, name);
+    document.node_mut(node).key = attrs.get("key").cloned();
+    Ok(node)
+}
+
+// ============================================================================
+// Value Parsing
+// ============================================================================
+
+/// Parse the `<version>` element's engine version
+fn read_version(attrs: &HashMap<String, String>) -> Result<EngineVersion, String> {
+    let part = |name: &str| -> Result<u32, String> {
+        required(attrs, name, "version")?
+            .parse::<u32>()
+            .map_err(|e| format!("Invalid version {}: {}", name, e))
+    };
+    Ok(EngineVersion {
+        major: part("major")?,
+        minor: part("minor")?,
+        revision: part("revision")?,
+        build: part("build")?,
+    })
+}
+
+/// Map the `lslib_meta` flags to the LSF metadata format they were converted from
+fn metadata_format_from_meta(meta: &str) -> MetadataFormat {
+    let flags: Vec<&str> = meta.split(',').map(|f| f.trim()).collect();
+    if flags.contains(&"lsf_keys_adjacency") {
+        MetadataFormat::KeysAndAdjacency
+    } else if flags.contains(&"lsf_adjacency") {
+        MetadataFormat::None2
+    } else {
+        MetadataFormat::None
+    }
+}
+
+/// `lslib_meta` flags describing a metadata format
+fn meta_from_metadata_format(format: MetadataFormat) -> String {
+    match format {
+        MetadataFormat::KeysAndAdjacency => "v1,bswap_guids,lsf_keys_adjacency",
+        MetadataFormat::None2 => "v1,bswap_guids,lsf_adjacency",
+        MetadataFormat::None => "v1,bswap_guids",
+    }.to_string()
+}
+
+fn read_attribute(attrs: &HashMap<String, String>) -> Result<Attribute, String> {
+    let id = required(attrs, "id", "attribute")?;
+    let type_name = required(attrs, "type", "attribute")?;
+    let attr_type = AttributeType::from_name(type_name)
+        .ok_or_else(|| format!("Attribute {} has unknown type {}", id, type_name))?;
+
+    let value = match attr_type {
+        AttributeType::TranslatedString => AttributeValue::TranslatedString(read_translated_string(attrs)?),
+        AttributeType::TranslatedFSString => AttributeValue::TranslatedFSString(read_translated_string(attrs)?),
+        _ => AttributeValue::parse(attr_type, required(attrs, "value", "attribute")?)
+            .map_err(|e| format!("Attribute {}: {}", id, e))?,
+    };
+    Ok(Attribute::new(id, attr_type, value)?)
+}
+
+/// Read the handle/version/value of a TranslatedString or TranslatedFSString.
+/// Arguments are attached later from the nested `<arguments>` element.
+fn read_translated_string(attrs: &HashMap<String, String>) -> Result<TranslatedString, String> {
+    let version = match attrs.get("version") {
+        Some(version) => version.parse::<u16>()
+            .map_err(|e| format!("Invalid TranslatedString version: {}", e))?,
+        None => 0,
+    };
+    Ok(TranslatedString {
+        value: attrs.get("value").cloned(),
+        handle: attrs.get("handle").cloned().unwrap_or_default(),
+        version,
+        arguments: Vec::new(),
+    })
+}
+
+// ============================================================================
+// Writing
+// ============================================================================
+
+impl Layout {
+    /// Line break plus indentation for an element at `depth` (`<save>` is 0)
+    fn line(&self, depth: usize) -> String {
+        if self.newline.is_empty() {
+            return String::new();
+        }
+        format!("{}{}", self.newline, self.indent.repeat(depth))
+    }
+}
+
+fn push_span(out: &mut String, span: &SourceSpan) {
+    out.push_str(&span.leading);
+    out.push_str(&span.raw);
+}
+
+fn write_version(out: &mut String, document: &Document, layout: &Layout) {
+    let source = document.lsx_source.as_ref();
+    let span = source.and_then(|s| s.version.as_ref());
+    let unchanged = source.is_some_and(|s| {
+        s.original_version == document.version && s.original_format == document.format
+    });
+    if let (Some(span), true) = (span, unchanged) {
+        push_span(out, span);
+        return;
+    }
+
+    let meta = source
+        .filter(|s| s.original_format == document.format)
+        .and_then(|s| s.lslib_meta.clone())
+        .unwrap_or_else(|| meta_from_metadata_format(document.format));
+    let v = document.version;
+    out.push_str(&span.map(|s| s.leading.clone()).unwrap_or_else(|| layout.line(1)));
+    out.push_str(&format!(
+        "<version major=\"{}\" minor=\"{}\" revision=\"{}\" build=\"{}\" lslib_meta=\"{}\"{}",
+        v.major, v.minor, v.revision, v.build, escape(&meta), layout.empty_tag_end
+    ));
+}
+
+fn write_region(out: &mut String, document: &Document, region: &Region, layout: &Layout) {
+    let source = region.source.as_ref();
+    match source {
+        Some(source) if source.id == region.id => push_span(out, &source.open),
+        Some(source) => {
+            out.push_str(&source.open.leading);
+            out.push_str(&format!("<region id=\"{}\">", escape(&region.id)));
+        }
+        None => out.push_str(&format!("{}<region id=\"{}\">", layout.line(1), escape(&region.id))),
+    }
+
+    write_node(out, document, region.root, 2, layout);
+
+    match source {
+        Some(source) => push_span(out, &source.close),
+        None => out.push_str(&format!("{}</region>", layout.line(1))),
+    }
+}
+
+fn write_node(out: &mut String, document: &Document, id: NodeId, depth: usize, layout: &Layout) {
+    let node = document.node(id);
+    let source = node.source.as_ref();
+    let has_content = !node.attributes.is_empty() || !node.children.is_empty();
+    let self_closing = !has_content && source.is_none_or(|s| s.close.is_none());
+
+    let leading = source.map(|s| s.open.leading.clone()).unwrap_or_else(|| layout.line(depth));
+    let reuse_open = source.filter(|s| {
+        s.name == node.name && s.key == node.key && s.close.is_none() == self_closing
+    });
+    match reuse_open {
+        Some(source) => push_span(out, &source.open),
+        None => {
+            out.push_str(&leading);
+            out.push_str(&format!("<node id=\"{}\"", escape(&node.name)));
+            if let Some(key) = &node.key {
+                out.push_str(&format!(" key=\"{}\"", escape(key)));
+            }
+            out.push_str(if self_closing { &layout.empty_tag_end } else { ">" });
+        }
+    }
+    if self_closing {
+        return;
+    }
+
+    for attribute in &node.attributes {
+        write_attribute(out, attribute, depth + 1, layout);
+    }
+
+    let children_open = source.and_then(|s| s.children_open.as_ref());
+    let children_close = source.and_then(|s| s.children_close.as_ref());
+    match (children_open, children_close) {
+        // Originally `<children />` and still empty
+        (Some(open), None) if node.children.is_empty() => push_span(out, open),
+        (None, _) if node.children.is_empty() => {}
+        (open, close) => {
+            match open {
+                Some(open) if close.is_some() => push_span(out, open),
+                Some(open) => out.push_str(&format!("{}<children>", open.leading)),
+                None => out.push_str(&format!("{}<children>", layout.line(depth + 1))),
+            }
+            for &child in &node.children {
+                write_node(out, document, child, depth + 2, layout);
+            }
+            match close {
+                Some(close) => push_span(out, close),
+                None => out.push_str(&format!("{}</children>", layout.line(depth + 1))),
+            }
+        }
+    }
+
+    match source.and_then(|s| s.close.as_ref()) {
+        Some(close) => push_span(out, close),
+        None => out.push_str(&format!("{}</node>", layout.line(depth))),
+    }
+}
+
+/// Whether an attribute still holds the value it was parsed with
+fn is_unchanged(attribute: &Attribute, original: &Attribute) -> bool {
+    attribute.id == original.id
+        && attribute.attr_type == original.attr_type
+        && attribute.value == original.value
+}
+
+fn write_attribute(out: &mut String, attribute: &Attribute, depth: usize, layout: &Layout) {
+    if let Some(source) = &attribute.source {
+        if is_unchanged(attribute, &source.original) {
+            push_span(out, &source.span);
+            return;
+        }
+        out.push_str(&source.span.leading);
+    } else {
+        out.push_str(&layout.line(depth));
+    }
+
+    out.push_str(&format!(
+        "<attribute id=\"{}\" type=\"{}\"",
+        escape(&attribute.id),
+        attribute.attr_type.name()
+    ));
+    match &attribute.value {
+        AttributeValue::TranslatedString(translated) => {
+            out.push_str(&format!(" handle=\"{}\"", escape(&translated.handle)));
+            match &translated.value {
+                Some(value) => out.push_str(&format!(" value=\"{}\"", escape(value))),
+                None => out.push_str(&format!(" version=\"{}\"", translated.version)),
+            }
+            out.push_str(&layout.empty_tag_end);
+        }
+        AttributeValue::TranslatedFSString(translated) => {
+            write_translated_fs_string(out, translated, depth, layout);
+            if !translated.arguments.is_empty() {
+                out.push_str(&format!("{}</attribute>", layout.line(depth)));
+            }
+        }
+        value => {
+            out.push_str(&format!(" value=\"{}\"{}", escape(&value.to_string()), layout.empty_tag_end));
+        }
+    }
+}
+
+/// Write the value/handle/arguments of a TranslatedFSString onto an already
+/// opened tag, closing the tag (and nesting `<arguments>` when there are any)
+fn write_translated_fs_string(out: &mut String, translated: &TranslatedString, depth: usize, layout: &Layout) {
+    out.push_str(&format!(
+        " value=\"{}\" handle=\"{}\" arguments=\"{}\"",
+        escape(translated.value.as_deref().unwrap_or("")),
+        escape(&translated.handle),
+        translated.arguments.len()
+    ));
+    if translated.arguments.is_empty() {
+        out.push_str(&layout.empty_tag_end);
+        return;
+    }
+
+    out.push('>');
+    out.push_str(&format!("{}<arguments>", layout.line(depth + 1)));
+    for argument in &translated.arguments {
+        out.push_str(&format!(
+            "{}<argument key=\"{}\" value=\"{}\">",
+            layout.line(depth + 2),
+            escape(&argument.key),
+            escape(&argument.value)
+        ));
+        out.push_str(&format!("{}<string", layout.line(depth + 3)));
+        write_translated_fs_string(out, &argument.string, depth + 3, layout);
+        if !argument.string.arguments.is_empty() {
+            out.push_str(&format!("{}</string>", layout.line(depth + 3)));
+        }
+        out.push_str(&format!("{}</argument>", layout.line(depth + 2)));
+    }
+    out.push_str(&format!("{}</arguments>", layout.line(depth + 1)));
+}
+
+/// Escape text for a double-quoted XML attribute, as .NET's XmlWriter does
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#xA;"),
+            '\r' => escaped.push_str("&#xD;"),
+            '\t' => escaped.push_str("&#x9;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// LSLib-style output: BOM, CRLF, tab indentation, trailing newline
+    const LSLIB_SAMPLE: &str = "\u{feff}<?xml version=\"1.0\" encoding=\"utf-8\"?>\r\n<save>\r\n\t<version major=\"4\" minor=\"0\" revision=\"9\" build=\"331\" lslib_meta=\"v1,bswap_guids,lsf_keys_adjacency\" />\r\n\t<region id=\"Globals\">\r\n\t\t<node id=\"Globals\">\r\n\t\t\t<children>\r\n\t\t\t\t<node id=\"ItemList\">\r\n\t\t\t\t\t<children>\r\n\t\t\t\t\t\t<node id=\"Item\" key=\"k1\">\r\n\t\t\t\t\t\t\t<attribute id=\"Amount\" type=\"int32\" value=\"250\" />\r\n\t\t\t\t\t\t\t<attribute id=\"Stats\" type=\"FixedString\" value=\"LOOT_Gold_A &amp; &quot;x&quot;&#xA;\" />\r\n\t\t\t\t\t\t\t<attribute id=\"Name\" type=\"TranslatedString\" handle=\"h1\" version=\"3\" />\r\n\t\t\t\t\t\t\t<attribute id=\"Desc\" type=\"TranslatedFSString\" value=\"\" handle=\"h2\" arguments=\"1\">\r\n\t\t\t\t\t\t\t\t<arguments>\r\n\t\t\t\t\t\t\t\t\t<argument key=\"k\" value=\"v\">\r\n\t\t\t\t\t\t\t\t\t\t<string value=\"\" handle=\"h3\" arguments=\"0\" />\r\n\t\t\t\t\t\t\t\t\t</argument>\r\n\t\t\t\t\t\t\t\t</arguments>\r\n\t\t\t\t\t\t\t</attribute>\r\n\t\t\t\t\t\t</node>\r\n\t\t\t\t\t\t<node id=\"Item\">\r\n\t\t\t\t\t\t\t<attribute id=\"Amount\" type=\"int32\" value=\"5\" />\r\n\t\t\t\t\t\t</node>\r\n\t\t\t\t\t</children>\r\n\t\t\t\t</node>\r\n\t\t\t</children>\r\n\t\t</node>\r\n\t</region>\r\n</save>\r\n";
+
+    /// Hand-edited style: LF, spaces, comments, odd quoting and empty tags, no trailing newline
+    const EDITED_SAMPLE: &str = "<?xml version='1.0' encoding='utf-8'?>\n<!-- exported -->\n<save>\n    <version major=\"4\" minor=\"0\" revision=\"9\" build=\"331\"/>\n    <region id='Globals'>\n        <node id=\"Root\" >\n            <attribute  id=\"Flag\" type=\"bool\" value=\"True\"/>\n            <attribute id=\"Apos\" type=\"LSString\" value=\"it&apos;s &#60;ok&#62;\"/>\n            <children />\n        </node>\n    </region>\n    <region id=\"Empty\"><node id=\"Empty\"></node></region>\n</save>";
+
+    #[test]
+    fn unmodified_lslib_file_round_trips_byte_for_byte() {
+        let document = read(LSLIB_SAMPLE).unwrap();
+        assert_eq!(write(&document), LSLIB_SAMPLE);
+    }
+
+    #[test]
+    fn unmodified_hand_edited_file_round_trips_byte_for_byte() {
+        let document = read(EDITED_SAMPLE).unwrap();
+        assert_eq!(write(&document), EDITED_SAMPLE);
+    }
+
+    #[test]
+    fn modified_attribute_only_changes_its_own_line() {
+        let mut document = read(LSLIB_SAMPLE).unwrap();
+        let item = document.find_nodes("Item")[0];
+        document.node_mut(item).attribute_mut("Amount").unwrap().set_int(1000).unwrap();
+
+        let expected = LSLIB_SAMPLE.replace("value=\"250\"", "value=\"1000\"");
+        assert_eq!(write(&document), expected);
+    }
+
+    #[test]
+    fn new_nodes_follow_the_file_layout() {
+        let mut document = read(EDITED_SAMPLE).unwrap();
+        let root = document.region("Globals").unwrap().root;
+        let child = document.add_node(Some(root), "Item");
+        document.node_mut(child).attributes.push(Attribute::new("Amount", AttributeType::Int32, AttributeValue::Int32(7)).unwrap());
+
+        let written = write(&document);
+        assert!(written.contains(
+            "\n            <children>\n                <node id=\"Item\">\n                    <attribute id=\"Amount\" type=\"int32\" value=\"7\"/>\n                </node>\n            </children>\n        </node>"
+        ));
+
+        let reparsed = read(&written).unwrap();
+        assert_eq!(reparsed.find_nodes("Item").len(), 1);
+        assert_eq!(write(&reparsed), written);
+    }
+
+    #[test]
+    fn documents_without_lsx_source_are_written_like_lslib() {
+        let document = read(LSLIB_SAMPLE).unwrap();
+        let mut fresh = document.clone();
+        fresh.lsx_source = None;
+        for region in &mut fresh.regions {
+            region.source = None;
+        }
+        for id in 0..fresh.node_count() {
+            let node = fresh.node_mut(id);
+            node.source = None;
+            for attribute in &mut node.attributes {
+                attribute.source = None;
+            }
+        }
+
+        let expected = LSLIB_SAMPLE.trim_start_matches('\u{feff}').trim_end_matches("\r\n");
+        assert_eq!(write(&fresh), expected);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::document::{Document, NodeId};
+use crate::error::AppError;
+use crate::inventory::{self, InventoryOwner};
+use crate::values::AttributeValue;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PartyLocation {
+    /// In the active party listed in SaveInfo.json
+    Active,
+    /// Recruited but not in the active party
+    Camp,
+    /// SaveInfo.json has no party list to tell
+    Unknown,
+}
+
+/// A character node within a resource
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CharacterNode {
+    pub resource: String,
+    pub node: NodeId,
+}
+
+/// A player character or recruited companion
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CharacterInfo {
+    /// UUID, origin and display name, as used for inventory owners
+    pub character: InventoryOwner,
+    pub is_player: bool,
+    pub level: Option<i64>,
+    pub class: Option<String>,
+    pub hp: Option<i64>,
+    pub max_hp: Option<i64>,
+    pub location: PartyLocation,
+    /// Index of the character's own "Active Party" entry; None when it has
+    /// none or shares its origin with another entry or character
+    #[serde(skip)]
+    pub save_info_entry: Option<usize>,
+    /// Every copy of the character (Globals and each level), first one is read
+    pub nodes: Vec<CharacterNode>,
+}
+
+// ============================================================================
+// Known Names
+// ============================================================================
+
+/// Flags marking a character as controlled by a player
+const PLAYER_FLAG_ATTRIBUTES: [&str; 3] = ["IsPlayer", "Player", "IsPlayerCharacter"];
+
+const LEVEL_ATTRIBUTES: [&str; 2] = ["Level", "CharacterLevel"];
+const CLASS_ATTRIBUTES: [&str; 3] = ["Class", "ClassName", "MainClass"];
+pub const HP_ATTRIBUTES: [&str; 4] = ["CurrentHP", "CurrentHp", "HP", "Hp"];
+pub const MAX_HP_ATTRIBUTES: [&str; 3] = ["MaxHP", "MaxHp", "MaxHitPoints"];
+
+/// SaveInfo.json origin of a custom player character
+const GENERIC_ORIGIN: &str = "Generic";
+
+// ============================================================================
+// Party Resolution
+// ============================================================================
+
+/// Player characters and origin companions among the document's Character
+/// nodes; NPCs are left out. `resource` is recorded on each node.
+pub fn characters_in(doc: &Document, resource: &str) -> Vec<CharacterInfo> {
+    doc.find_nodes("Character").into_iter()
+        .filter_map(|node| {
+            let character = inventory::describe_entity(doc, node);
+            let own = inventory::own_nodes(doc, node);
+            let attribute = |ids: &[&str]| ids.iter().find_map(|id| own.iter().find_map(|&n| doc.node(n).attribute(id)));
+            let int = |ids: &[&str]| attribute(ids).and_then(|a| a.value.as_int());
+
+            let is_player = PLAYER_FLAG_ATTRIBUTES.iter().any(|id| {
+                own.iter()
+                    .filter_map(|&n| doc.node(n).attribute(id))
+                    .any(|a| a.value.as_bool().unwrap_or(false) || a.value.as_int().is_some_and(|v| v != 0))
+            });
+            if !is_player && character.origin.is_none() {
+                return None;
+            }
+
+            Some(CharacterInfo {
+                is_player,
+                level: int(&LEVEL_ATTRIBUTES),
+                class: attribute(&CLASS_ATTRIBUTES).and_then(|a| a.value.as_str()).map(str::to_string),
+                hp: int(&HP_ATTRIBUTES),
+                max_hp: int(&MAX_HP_ATTRIBUTES),
+                location: PartyLocation::Unknown,
+                save_info_entry: None,
+                nodes: vec![CharacterNode { resource: resource.to_string(), node }],
+                character,
+            })
+        })
+        .collect()
+}
+
+/// Add characters from another resource, merging copies of the same character
+pub fn merge_characters(into: &mut Vec<CharacterInfo>, other: Vec<CharacterInfo>) {
+    for character in other {
+        let key = character.character.key();
+        match into.iter_mut().find(|existing| existing.character.key() == key) {
+            Some(existing) => {
+                existing.is_player |= character.is_player;
+                existing.level = existing.level.or(character.level);
+                existing.class = existing.class.take().or(character.class);
+                existing.hp = existing.hp.or(character.hp);
+                existing.max_hp = existing.max_hp.or(character.max_hp);
+                existing.nodes.extend(character.nodes);
+            }
+            None => into.push(character),
+        }
+    }
+}
+
+/// Mark who is in the active party from SaveInfo.json's "Active Party" list
+/// (matched by origin, "Generic" being the custom player) and fill in level
+/// and class where the tree has none. Players come first, then by name.
+pub fn apply_save_info(characters: &mut [CharacterInfo], save_info: Option<&serde_json::Value>) {
+    let active: Vec<&serde_json::Value> = save_info
+        .and_then(|info| info.get("Active Party")?.get("Characters")?.as_array())
+        .map(|entries| entries.iter().collect())
+        .unwrap_or_default();
+
+    // An entry is only written through when it and the character match each
+    // other alone, e.g. not with two custom ("Generic") players
+    let own_entries: Vec<Option<usize>> = characters.iter()
+        .map(|character| {
+            let mut matching = active.iter().enumerate().filter(|(_, entry)| is_entry_of(character, entry));
+            match (matching.next(), matching.next()) {
+                (Some((index, entry)), None) if characters.iter().filter(|c| is_entry_of(c, entry)).count() == 1 => Some(index),
+                _ => None,
+            }
+        })
+        .collect();
+
+    let mut claimed = vec![false; active.len()];
+    for (character, own_entry) in characters.iter_mut().zip(own_entries) {
+        character.save_info_entry = own_entry;
+        if active.is_empty() {
+            character.location = PartyLocation::Unknown;
+            continue;
+        }
+        let entry = active.iter().enumerate()
+            .find(|&(index, entry)| !claimed[index] && is_entry_of(character, entry));
+
+        match entry {
+            Some((index, entry)) => {
+                claimed[index] = true;
+                character.location = PartyLocation::Active;
+                if character.level.is_none() {
+                    character.level = entry.get("Level").and_then(|l| l.as_i64());
+                }
+                if character.class.is_none() {
+                    character.class = entry.get("Classes")
+                        .and_then(|classes| classes.get(0)?.get("Main")?.as_str())
+                        .map(str::to_string);
+                }
+            }
+            None => character.location = PartyLocation::Camp,
+        }
+    }
+
+    characters.sort_by(|a, b| {
+        b.is_player.cmp(&a.is_player).then_with(|| a.character.name.cmp(&b.character.name))
+    });
+}
+
+/// The character's own entry in SaveInfo.json's "Active Party" list, as
+/// found by `apply_save_info`; None when it is not the character's alone
+pub fn save_info_entry<'a>(character: &CharacterInfo, save_info: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
+    save_info.get("Active Party")?
+        .get("Characters")?
+        .get(character.save_info_entry?)
+        .filter(|entry| is_entry_of(character, entry))
+}
+
+pub fn save_info_entry_mut<'a>(character: &CharacterInfo, save_info: &'a mut serde_json::Value) -> Option<&'a mut serde_json::Value> {
+    save_info.get_mut("Active Party")?
+        .get_mut("Characters")?
+        .get_mut(character.save_info_entry?)
+        .filter(|entry| is_entry_of(character, entry))
+}
+
+/// Whether an "Active Party" entry's origin is the character's
+fn is_entry_of(character: &CharacterInfo, entry: &serde_json::Value) -> bool {
+    let origin = character.character.origin.as_deref()
+        .unwrap_or(if character.is_player { GENERIC_ORIGIN } else { "" });
+    entry.get("Origin").and_then(|o| o.as_str())
+        .is_some_and(|o| normalize(o) == normalize(origin))
+}
+
+/// Origin names without case or punctuation, so "Lae'zel" matches "Laezel"
+fn normalize(origin: &str) -> String {
+    origin.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+// ============================================================================
+// Character Fields
+// ============================================================================
+
+/// Check that an integer attribute is still on `node` and `value` fits its type
+pub fn check_int_field(doc: &Document, node: NodeId, attribute: &str, value: i64) -> Result<(), AppError> {
+    let found = doc.contains(node)
+        .then(|| doc.node(node).attribute(attribute))
+        .flatten()
+        .ok_or_else(|| AppError::not_found(format!("{} is no longer on node {}", attribute, node)))?;
+    AttributeValue::from_int(found.attr_type, value)?;
+    Ok(())
+}
+
+pub fn write_int_field(doc: &mut Document, node: NodeId, attribute: &str, value: i64) -> Result<(), AppError> {
+    check_int_field(doc, node, attribute, value)?;
+    if let Some(found) = doc.node_mut(node).attribute_mut(attribute) {
+        found.set_int(value)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsx;
+
+    /// Two custom players, Shadowheart, Lae'zel and an NPC
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<save>
+	<version major="4" minor="0" revision="9" build="331" />
+	<region id="Characters">
+		<node id="Characters">
+			<children>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="aaaaaaaa-0000-0000-0000-000000000001" />
+					<attribute id="Name" type="LSString" value="Tav" />
+					<attribute id="IsPlayer" type="bool" value="True" />
+				</node>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="aaaaaaaa-0000-0000-0000-000000000002" />
+					<attribute id="Name" type="LSString" value="Durge" />
+					<attribute id="IsPlayer" type="bool" value="True" />
+				</node>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="S_Player_ShadowHeart_3ed74f06-3c60-42dc-83f6-f034cb47c679" />
+					<attribute id="Level" type="int32" value="4" />
+				</node>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="S_Player_Laezel_58a69333-40bf-8358-1d17-fff240d7b12d" />
+				</node>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="bbbbbbbb-0000-0000-0000-000000000003" />
+					<attribute id="Name" type="LSString" value="Volo" />
+				</node>
+			</children>
+		</node>
+	</region>
+</save>"#;
+
+    fn party(save_info: &serde_json::Value) -> Vec<CharacterInfo> {
+        let mut characters = characters_in(&lsx::read(SAMPLE).unwrap(), "Globals.lsf");
+        apply_save_info(&mut characters, Some(save_info));
+        characters
+    }
+
+    fn named<'a>(characters: &'a [CharacterInfo], name: &str) -> &'a CharacterInfo {
+        characters.iter().find(|c| c.character.name == name).unwrap()
+    }
+
+    #[test]
+    fn npcs_are_not_party_members() {
+        let characters = party(&serde_json::json!({}));
+        let names: Vec<&str> = characters.iter().map(|c| c.character.name.as_str()).collect();
+        assert_eq!(names, vec!["Durge", "Tav", "Lae'zel", "Shadowheart"]);
+        assert!(characters.iter().all(|c| c.location == PartyLocation::Unknown));
+    }
+
+    #[test]
+    fn entries_are_matched_by_origin() {
+        let info = serde_json::json!({ "Active Party": { "Characters": [
+            { "Origin": "Shadowheart", "Level": 5, "Classes": [{ "Main": "Cleric" }] },
+            { "Origin": "Laezel", "Level": 5, "Classes": [{ "Main": "Fighter" }] },
+        ] } });
+        let characters = party(&info);
+        let shadowheart = named(&characters, "Shadowheart");
+        assert_eq!((shadowheart.location, shadowheart.level, shadowheart.class.as_deref()), (PartyLocation::Active, Some(4), Some("Cleric")));
+        assert_eq!(save_info_entry(shadowheart, &info).unwrap()["Classes"][0]["Main"], "Cleric");
+        let laezel = named(&characters, "Lae'zel");
+        assert_eq!((laezel.location, laezel.level), (PartyLocation::Active, Some(5)));
+        assert_eq!(named(&characters, "Tav").location, PartyLocation::Camp);
+    }
+
+    #[test]
+    fn shared_generic_entries_are_not_written_through() {
+        let mut info = serde_json::json!({ "Active Party": { "Characters": [
+            { "Origin": "Generic", "Level": 3 },
+            { "Origin": "Generic", "Level": 6 },
+        ] } });
+        let characters = party(&info);
+        for name in ["Tav", "Durge"] {
+            let player = named(&characters, name);
+            assert_eq!(player.location, PartyLocation::Active);
+            assert_eq!(player.save_info_entry, None);
+            assert!(save_info_entry(player, &info).is_none());
+            assert!(save_info_entry_mut(player, &mut info).is_none());
+        }
+
+        // One Generic entry for two players is not either player's alone
+        let info = serde_json::json!({ "Active Party": { "Characters": [{ "Origin": "Generic" }] } });
+        let characters = party(&info);
+        assert!(characters.iter().filter(|c| c.is_player).all(|c| c.save_info_entry.is_none()));
+    }
+
+    #[test]
+    fn stale_entry_indexes_are_ignored() {
+        let info = serde_json::json!({ "Active Party": { "Characters": [{ "Origin": "Shadowheart" }] } });
+        let characters = party(&info);
+        let shadowheart = named(&characters, "Shadowheart");
+        assert_eq!(shadowheart.save_info_entry, Some(0));
+        let swapped = serde_json::json!({ "Active Party": { "Characters": [{ "Origin": "Laezel" }] } });
+        assert!(save_info_entry(shadowheart, &swapped).is_none());
+    }
+}
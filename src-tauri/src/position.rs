@@ -0,0 +1,452 @@
+use std::path::Path;
+use crate::document::{Document, NodeId, Subtree};
+use crate::error::AppError;
+use crate::inventory::{self, InventoryOwner};
+use crate::party::{self, CharacterInfo, CharacterNode};
+use crate::values::AttributeValue;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PositionRole {
+    /// World coordinates (x, y, z)
+    Translate,
+    /// Facing, as a quaternion or rotation matrix
+    Rotate,
+}
+
+/// One stored position or rotation of a character
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PositionField {
+    pub resource: String,
+    pub node: NodeId,
+    pub attribute: String,
+    pub role: PositionRole,
+    pub value: Vec<f32>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CharacterPosition {
+    pub character: InventoryOwner,
+    /// Level file the character is in, e.g. WLD_Main_A
+    pub level: Option<String>,
+    pub position: Option<Vec<f32>>,
+    pub rotation: Option<Vec<f32>>,
+    pub fields: Vec<PositionField>,
+}
+
+/// Where to move characters
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "to", rename_all = "camelCase")]
+pub enum TeleportTarget {
+    /// These coordinates, keeping the facing unless a rotation is given, in
+    /// `level` when given or else the level each character is in
+    Coordinates {
+        position: Vec<f32>,
+        rotation: Option<Vec<f32>>,
+        #[serde(default)]
+        level: Option<String>,
+    },
+    /// Another character's position, facing and level (by owner key)
+    Character { character: String },
+}
+
+/// Where characters end up, as resolved from a target
+#[derive(Debug, Clone, PartialEq)]
+pub struct Destination {
+    pub position: Vec<f32>,
+    pub rotation: Option<Vec<f32>>,
+    /// Level file to be in, e.g. SCL_Main_A; None keeps each character's level
+    pub level: Option<String>,
+}
+
+/// Move one or more characters (by owner key), e.g. the whole party
+#[derive(Debug, serde::Deserialize)]
+pub struct TeleportEdit {
+    pub characters: Vec<String>,
+    pub target: TeleportTarget,
+}
+
+// ============================================================================
+// Known Names
+// ============================================================================
+
+const TRANSLATE_ATTRIBUTES: [&str; 2] = ["Translate", "Position"];
+const ROTATE_ATTRIBUTES: [&str; 2] = ["Rotate", "Rotation"];
+
+/// SaveInfo.json keys holding a position mirror
+const SAVE_INFO_POSITION_KEYS: [&str; 2] = ["Position", "Translate"];
+
+/// SaveInfo.json key naming the level the party is in
+const SAVE_INFO_LEVEL_KEY: &str = "Current Level";
+
+/// Attribute naming the level an entity is in
+const LEVEL_NAME_ATTRIBUTE: &str = "LevelName";
+
+// ============================================================================
+// Locating Positions
+// ============================================================================
+
+/// Translate/Rotate attributes on a character node or the nodes below it
+/// (its transform), skipping its items
+pub fn position_fields(doc: &Document, character: NodeId, resource: &str) -> Vec<PositionField> {
+    let mut fields = Vec::new();
+    for node in inventory::own_nodes(doc, character) {
+        for attribute in &doc.node(node).attributes {
+            let role = if TRANSLATE_ATTRIBUTES.contains(&attribute.id.as_str()) {
+                PositionRole::Translate
+            } else if ROTATE_ATTRIBUTES.contains(&attribute.id.as_str()) {
+                PositionRole::Rotate
+            } else {
+                continue;
+            };
+            let AttributeValue::Floats(value) = &attribute.value else {
+                continue;
+            };
+            if role == PositionRole::Translate && value.len() != 3 {
+                continue;
+            }
+            fields.push(PositionField {
+                resource: resource.to_string(),
+                node,
+                attribute: attribute.id.clone(),
+                role,
+                value: value.clone(),
+            });
+        }
+    }
+    fields
+}
+
+/// Level a resource is, e.g. WLD_Main_A for LevelCache/WLD_Main_A.lsf;
+/// None for Globals
+fn level_of(resource: &str) -> Option<String> {
+    if !resource.contains("LevelCache/") {
+        return None;
+    }
+    Path::new(resource).file_stem().map(|stem| stem.to_string_lossy().to_string())
+}
+
+/// A character's position from the first resource storing it; the level is
+/// the first LevelCache file holding the character
+pub fn summarize(character: &CharacterInfo, fields: Vec<PositionField>) -> CharacterPosition {
+    let first = |role: PositionRole| fields.iter().find(|f| f.role == role).map(|f| f.value.clone());
+    let level = character.nodes.iter().find_map(|node| level_of(&node.resource));
+    CharacterPosition {
+        character: character.character.clone(),
+        level,
+        position: first(PositionRole::Translate),
+        rotation: first(PositionRole::Rotate),
+        fields,
+    }
+}
+
+// ============================================================================
+// Teleporting
+// ============================================================================
+
+/// The position, optional rotation and level characters are moved to; a
+/// character target brings them into its level
+pub fn resolve_target(target: &TeleportTarget, destination: Option<&CharacterPosition>) -> Result<Destination, AppError> {
+    let (position, rotation, level) = match (target, destination) {
+        (TeleportTarget::Coordinates { position, rotation, level }, _) => (position.clone(), rotation.clone(), level.clone()),
+        (TeleportTarget::Character { .. }, Some(destination)) => {
+            let position = destination.position.clone().ok_or_else(|| AppError::not_found(format!(
+                "No position found for {}", destination.character.name
+            )))?;
+            (position, destination.rotation.clone(), destination.level.clone())
+        }
+        (TeleportTarget::Character { character }, None) => {
+            return Err(AppError::not_found(format!("No party member {}", character)));
+        }
+    };
+
+    if position.len() != 3 || position.iter().any(|v| !v.is_finite()) {
+        return Err(AppError::validation(format!("A position needs 3 finite coordinates, got {:?}", position)));
+    }
+    if let Some(rotation) = rotation.as_ref().filter(|r| r.iter().any(|v| !v.is_finite())) {
+        return Err(AppError::validation(format!("Rotation must be finite, got {:?}", rotation)));
+    }
+    Ok(Destination { position, rotation, level })
+}
+
+// ============================================================================
+// Changing Levels
+// ============================================================================
+
+/// The copy of a character to move into `level`: its node in the level it is
+/// in now, or None when it already has one in `level`
+pub fn node_to_move<'a>(character: &'a CharacterInfo, level: &str) -> Result<Option<&'a CharacterNode>, AppError> {
+    if character.nodes.iter().any(|node| level_of(&node.resource).as_deref() == Some(level)) {
+        return Ok(None);
+    }
+    character.nodes.iter()
+        .find(|node| level_of(&node.resource).is_some())
+        .map(Some)
+        .ok_or_else(|| AppError::validation(format!(
+            "{} is not in any level, so it cannot be moved to {}", character.character.name, level
+        )))
+}
+
+/// Copy a character node, with its inventory, to put it in another level
+/// file. Characters whose inventory lists are stored outside their node are
+/// refused, as those would be left behind.
+pub fn copy_character(doc: &Document, character: NodeId) -> Result<Subtree, AppError> {
+    let inside = doc.descendants(character);
+    let outside = inventory::inventories(doc).into_iter()
+        .any(|list| list.entity == Some(character) && !inside.contains(&list.node));
+    if outside {
+        return Err(AppError::validation(format!(
+            "{} has inventory lists stored outside its node and can only stay in its level",
+            inventory::describe_entity(doc, character).name
+        )));
+    }
+    Ok(doc.subtree(character))
+}
+
+/// Put a copied character under the first node named `parent` in a level
+/// file, e.g. its Characters list, with its `LevelName`s set to `level`
+pub fn place_character(doc: &mut Document, parent: &str, character: &Subtree, level: &str) -> Result<NodeId, AppError> {
+    let parent_node = doc.find_nodes(parent).into_iter().next()
+        .ok_or_else(|| AppError::not_found(format!("No {} node in {} to put the character in", parent, level)))?;
+    let node = doc.insert_subtree(Some(parent_node), character);
+    for entry in std::iter::once(node).chain(doc.descendants(node)) {
+        if let Some(attribute) = doc.node_mut(entry).attribute_mut(LEVEL_NAME_ATTRIBUTE)
+            .filter(|a| a.value.as_str().is_some_and(|name| !name.is_empty()))
+        {
+            attribute.set(AttributeValue::String(level.to_string()))?;
+        }
+    }
+    Ok(node)
+}
+
+/// Set SaveInfo.json's "Current Level"; returns how many keys were changed
+pub fn update_current_level(save_info: &mut serde_json::Value, level: &str) -> usize {
+    match save_info.get_mut(SAVE_INFO_LEVEL_KEY) {
+        Some(value) if value.is_string() && value.as_str() != Some(level) => {
+            *value = serde_json::Value::from(level);
+            1
+        }
+        _ => 0,
+    }
+}
+
+/// New values of a character's Translate fields and, when a rotation is
+/// given, of its Rotate fields. A rotation only replaces fields of the same
+/// kind (quaternion or matrix).
+pub fn plan_teleport(character: &CharacterPosition, position: &[f32], rotation: Option<&[f32]>) -> Result<Vec<(PositionField, Vec<f32>)>, AppError> {
+    if !character.fields.iter().any(|f| f.role == PositionRole::Translate) {
+        return Err(AppError::not_found(format!("No position found for {}", character.character.name)));
+    }
+    let mut changes = Vec::new();
+    for field in &character.fields {
+        let value = match (field.role, rotation) {
+            (PositionRole::Translate, _) => position,
+            (PositionRole::Rotate, Some(rotation)) if rotation.len() == field.value.len() => rotation,
+            (PositionRole::Rotate, _) => continue,
+        };
+        changes.push((field.clone(), value.to_vec()));
+    }
+    Ok(changes)
+}
+
+/// Check that a field is still there and can take `value`
+pub fn check_field(doc: &Document, field: &PositionField, value: &[f32]) -> Result<(), AppError> {
+    let attribute = doc.contains(field.node)
+        .then(|| doc.node(field.node).attribute(&field.attribute))
+        .flatten()
+        .ok_or_else(|| AppError::not_found(format!("{} is no longer on node {}", field.attribute, field.node)))?;
+    AttributeValue::Floats(value.to_vec()).check_type(attribute.attr_type)?;
+    Ok(())
+}
+
+pub fn write_field(doc: &mut Document, field: &PositionField, value: &[f32]) -> Result<(), AppError> {
+    check_field(doc, field, value)?;
+    if let Some(attribute) = doc.node_mut(field.node).attribute_mut(&field.attribute) {
+        attribute.set(AttributeValue::Floats(value.to_vec()))?;
+    }
+    Ok(())
+}
+
+/// Set the position mirrors of SaveInfo.json: the character's "Active Party"
+/// entry and, for the party's only player character, the top-level position.
+/// Returns how many were changed.
+pub fn update_save_info(character: &CharacterInfo, characters: &[CharacterInfo], save_info: &mut serde_json::Value, position: &[f32]) -> usize {
+    let mut changed = set_position_keys(party::save_info_entry_mut(character, save_info), position);
+    if character.is_player && characters.iter().filter(|c| c.is_player).count() == 1 {
+        changed += set_position_keys(Some(save_info), position);
+    }
+    changed
+}
+
+/// Replace 3-number position arrays under the known keys
+fn set_position_keys(object: Option<&mut serde_json::Value>, position: &[f32]) -> usize {
+    let Some(object) = object.and_then(|o| o.as_object_mut()) else {
+        return 0;
+    };
+    let mut changed = 0;
+    for key in SAVE_INFO_POSITION_KEYS {
+        let Some(value) = object.get_mut(key) else {
+            continue;
+        };
+        if value.as_array().is_some_and(|a| a.len() == 3 && a.iter().all(|v| v.is_number())) {
+            // Through the f32's shortest text, so 24.91 is not written as 24.909999847
+            let coordinates: Vec<f64> = position.iter().map(|v| v.to_string().parse().unwrap_or_default()).collect();
+            *value = serde_json::Value::from(coordinates);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsx;
+
+    /// A level file with a player character facing by quaternion and a companion
+    const LEVEL: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<save>
+	<version major="4" minor="0" revision="9" build="331" />
+	<region id="Characters">
+		<node id="Characters">
+			<children>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="aaaaaaaa-0000-0000-0000-000000000001" />
+					<attribute id="Name" type="LSString" value="Tav" />
+					<attribute id="IsPlayer" type="bool" value="True" />
+					<attribute id="LevelName" type="FixedString" value="WLD_Main_A" />
+					<attribute id="Translate" type="fvec3" value="1 2 3" />
+					<attribute id="Rotate" type="fvec4" value="0 0 0 1" />
+					<children>
+						<node id="InventoryList">
+							<children>
+								<node id="Item">
+									<attribute id="UUID" type="FixedString" value="sword-1" />
+									<attribute id="LevelName" type="FixedString" value="WLD_Main_A" />
+								</node>
+							</children>
+						</node>
+					</children>
+				</node>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="S_Player_ShadowHeart_3ed74f06-3c60-42dc-83f6-f034cb47c679" />
+					<attribute id="Translate" type="fvec3" value="4 5 6" />
+				</node>
+			</children>
+		</node>
+	</region>
+</save>"#;
+
+    /// Another level file with an empty character list
+    const OTHER_LEVEL: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<save>
+	<version major="4" minor="0" revision="9" build="331" />
+	<region id="Characters">
+		<node id="Characters" />
+	</region>
+</save>"#;
+
+    const RESOURCE: &str = "LevelCache/WLD_Main_A.lsf";
+
+    fn characters(doc: &Document) -> Vec<CharacterInfo> {
+        party::characters_in(doc, RESOURCE)
+    }
+
+    fn position_of(doc: &Document, character: &CharacterInfo) -> CharacterPosition {
+        summarize(character, position_fields(doc, character.nodes[0].node, RESOURCE))
+    }
+
+    fn coordinates(position: Vec<f32>, level: Option<&str>) -> TeleportTarget {
+        TeleportTarget::Coordinates { position, rotation: None, level: level.map(str::to_string) }
+    }
+
+    #[test]
+    fn targets_are_resolved_with_their_level() {
+        let doc = lsx::read(LEVEL).unwrap();
+        let party = characters(&doc);
+        let tav = position_of(&doc, &party[0]);
+        assert_eq!(tav.level.as_deref(), Some("WLD_Main_A"));
+
+        let destination = resolve_target(&coordinates(vec![7.0, 8.0, 9.0], Some("SCL_Main_A")), None).unwrap();
+        assert_eq!(destination.level.as_deref(), Some("SCL_Main_A"));
+        let destination = resolve_target(&TeleportTarget::Character { character: "x".to_string() }, Some(&tav)).unwrap();
+        assert_eq!((destination.position, destination.level.as_deref()), (vec![1.0, 2.0, 3.0], Some("WLD_Main_A")));
+
+        assert!(resolve_target(&coordinates(vec![f32::NAN, 0.0, 0.0], None), None).is_err());
+        assert!(resolve_target(&coordinates(vec![1.0, 2.0], None), None).is_err());
+        assert!(resolve_target(&TeleportTarget::Character { character: "x".to_string() }, None).is_err());
+    }
+
+    #[test]
+    fn rotations_only_replace_fields_of_the_same_kind() {
+        let doc = lsx::read(LEVEL).unwrap();
+        let party = characters(&doc);
+        let tav = position_of(&doc, &party[0]);
+
+        let changes = plan_teleport(&tav, &[7.0, 8.0, 9.0], None).unwrap();
+        assert_eq!(changes.iter().map(|(f, _)| f.attribute.as_str()).collect::<Vec<_>>(), vec!["Translate"]);
+        let changes = plan_teleport(&tav, &[7.0, 8.0, 9.0], Some(&[0.0, 1.0, 0.0, 0.0])).unwrap();
+        assert_eq!(changes.len(), 2);
+        let changes = plan_teleport(&tav, &[7.0, 8.0, 9.0], Some(&[0.0; 9])).unwrap();
+        assert_eq!(changes.len(), 1);
+
+        let mut nowhere = tav.clone();
+        nowhere.fields.clear();
+        assert!(plan_teleport(&nowhere, &[7.0, 8.0, 9.0], None).is_err());
+    }
+
+    #[test]
+    fn save_info_positions_are_replaced_as_written() {
+        let mut entry = serde_json::json!({ "Position": [1, 2, 3], "Translate": "elsewhere", "Rotate": [0, 0, 0, 1] });
+        assert_eq!(set_position_keys(Some(&mut entry), &[-175.23, 24.91, 542.43]), 1);
+        assert_eq!(entry["Position"], serde_json::json!([-175.23, 24.91, 542.43]));
+        assert_eq!(entry["Translate"], "elsewhere");
+        assert_eq!(set_position_keys(None, &[0.0, 0.0, 0.0]), 0);
+
+        let doc = lsx::read(LEVEL).unwrap();
+        let mut party = characters(&doc);
+        let mut info = serde_json::json!({
+            "Position": [1, 2, 3],
+            "Current Level": "WLD_Main_A",
+            "Active Party": { "Characters": [{ "Origin": "Generic", "Position": [1, 2, 3] }] },
+        });
+        party::apply_save_info(&mut party, Some(&info));
+        assert_eq!(update_save_info(&party[0], &party, &mut info, &[7.0, 8.0, 9.0]), 2);
+        assert_eq!(update_save_info(&party[1], &party, &mut info, &[7.0, 8.0, 9.0]), 0);
+
+        assert_eq!(update_current_level(&mut info, "SCL_Main_A"), 1);
+        assert_eq!(update_current_level(&mut info, "SCL_Main_A"), 0);
+        assert_eq!(info["Current Level"], "SCL_Main_A");
+    }
+
+    #[test]
+    fn characters_move_between_level_files() {
+        let mut source = lsx::read(LEVEL).unwrap();
+        let mut target = lsx::read(OTHER_LEVEL).unwrap();
+        let party = characters(&source);
+        assert!(node_to_move(&party[0], "WLD_Main_A").unwrap().is_none());
+        let node = node_to_move(&party[0], "SCL_Main_A").unwrap().unwrap().node;
+
+        let copy = copy_character(&source, node).unwrap();
+        let placed = place_character(&mut target, "Characters", &copy, "SCL_Main_A").unwrap();
+        source.remove_node(node);
+
+        let moved = party::characters_in(&target, "LevelCache/SCL_Main_A.lsf");
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].character.name, "Tav");
+        assert_eq!(characters(&source).len(), 1);
+        let level_names: Vec<String> = std::iter::once(placed).chain(target.descendants(placed))
+            .filter_map(|n| target.node(n).attribute(LEVEL_NAME_ATTRIBUTE).map(|a| a.value.to_string()))
+            .collect();
+        assert_eq!(level_names, vec!["SCL_Main_A", "SCL_Main_A"]);
+
+        assert!(place_character(&mut target, "Party", &copy, "SCL_Main_A").is_err());
+        let mut globals_only = party[0].clone();
+        globals_only.nodes[0].resource = "Globals.lsf".to_string();
+        assert!(node_to_move(&globals_only, "SCL_Main_A").is_err());
+    }
+}
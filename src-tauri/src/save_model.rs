@@ -1,229 +1,587 @@
+use crate::document::{Attribute, AttributeType, Document, NodeId, Subtree};
+use crate::error::AppError;
+use crate::inventory::{self, InventoryOwner, OwnerKind};
+use crate::values::{AttributeValue, ValueError};
+
 // ============================================================================
 // Data Structures
 // ============================================================================
 
 #[derive(Debug, serde::Serialize)]
 pub struct SaveState {
-    pub total_gold: i32,
+    pub total_gold: i64,
+    /// Gold grouped by the inventory owner holding it
+    pub owners: Vec<OwnerGold>,
+}
+
+/// Gold held by one owner, plus how many items their inventories hold
+#[derive(Debug, serde::Serialize)]
+pub struct OwnerGold {
+    pub owner: InventoryOwner,
+    pub total_gold: i64,
     pub items: Vec<GoldItemDisplay>,
+    pub item_count: usize,
+    /// Whether the owner is a player character or origin companion
+    pub in_party: bool,
+    /// Where the owner's inventory lists are, for adding a stack
+    #[serde(skip)]
+    pub inventories: Vec<InventoryLocation>,
+}
+
+/// An inventory list node within a resource
+#[derive(Debug, Clone)]
+pub struct InventoryLocation {
+    pub resource: String,
+    pub node: NodeId,
 }
 
 #[derive(Debug, serde::Serialize)]
 pub struct GoldItemDisplay {
     pub name: String,
     pub amount: i32,
+    /// Resource file (relative to the session workspace) holding the stack
+    pub resource: String,
+    /// Item node within that resource
+    pub node: NodeId,
 }
 
 // ============================================================================
-// Helper Functions for XML/LSX Parsing
+// Gold Detection on the Document Tree
 // ============================================================================
 
-/// Extract an attribute value from XML/LSX text by attribute ID
-/// Searches for `id="attr_id"` and then finds the corresponding `value="..."`
-fn extract_attribute_value(text: &str, attr_id: &str) -> Option<String> {
-    let search_str = format!("id=\"{}\"", attr_id);
-    let attr_idx = text.find(&search_str)?;
-    
-    // Look for value=" after the attribute ID
-    let value_search = "value=\"";
-    let value_idx = text[attr_idx..].find(value_search)?;
-    let absolute_value_idx = attr_idx + value_idx + value_search.len();
-    
-    // Find the closing quote
-    let value_end = text[absolute_value_idx..].find('"')?;
-    
-    Some(text[absolute_value_idx..absolute_value_idx + value_end].to_string())
-}
-
-/// Check if an item contains gold indicators
-fn is_gold_item(item_text: &str) -> bool {
-    item_text.contains("LOOT_Gold") || item_text.contains("OBJ_Gold")
+/// Check if an item references a gold template, on its own nodes only so a
+/// bag holding gold is not gold itself
+fn is_gold_item(doc: &Document, item: NodeId) -> bool {
+    inventory::own_nodes(doc, item).into_iter()
+        .flat_map(|n| doc.node(n).attributes.iter())
+        .filter_map(|a| a.value.as_str())
+        .any(|value| value.contains("LOOT_Gold") || value.contains("OBJ_Gold"))
 }
 
-/// Parse an amount value, defaulting to 1 if parsing fails
-fn parse_amount(value_str: &str) -> i32 {
-    value_str.parse::<i32>().unwrap_or(1)
+/// Stack size of an item; items without an Amount attribute are a single item
+fn item_amount(doc: &Document, item: NodeId) -> Result<i32, ValueError> {
+    let Some(amount) = inventory::item_attribute(doc, item, "Amount") else {
+        return Ok(1);
+    };
+    let value = amount.value.as_int()
+        .ok_or(ValueError::TypeMismatch { attr_type: amount.attr_type, found: "integer" })?;
+    i32::try_from(value)
+        .map_err(|_| ValueError::OutOfRange { attr_type: amount.attr_type, value: value.to_string() })
 }
 
 /// Find and sum all gold in character inventory
-/// Returns a SaveState with total gold and itemized breakdown
-pub fn get_gold_info(content: &str) -> SaveState {
-    let mut items = Vec::new();
-    let mut total_gold = 0;
-    let mut count = 0;
-
-    // Log to debug file
-    let mut debug_file = std::fs::File::create("C:\\Git\\BG3 savegame editor\\gold_debug.txt").ok();
-    if let Some(ref mut f) = debug_file {
-        use std::io::Write;
-        let _ = writeln!(f, "\nDEBUG: Searching for gold in ItemList nodes\n");
-    }
-    
-    // Find all ItemList sections
-    let inventory_parts: Vec<&str> = content.split("<node id=\"ItemList\">").collect();
-    
-    if let Some(ref mut f) = debug_file {
-        use std::io::Write;
-        let _ = writeln!(f, "Found {} ItemList sections\n", inventory_parts.len());
-    }
-    
-    // Process each inventory section (skip the part before the first InventoryList)
-    for inv_part in inventory_parts.iter().skip(1) {
-        process_inventory_section(inv_part, &mut items, &mut total_gold, &mut count, &mut debug_file);
-    }
-    
-    if let Some(ref mut f) = debug_file {
-        use std::io::Write;
-        let _ = writeln!(f, "\nTOTAL GOLD FOUND: {} (across {} items)\n", total_gold, count);
-    }
-    
-    SaveState { total_gold, items }
-}
-
-/// Process a single inventory section to extract gold items
-fn process_inventory_section(
-    inv_part: &str,
-    items: &mut Vec<GoldItemDisplay>,
-    total_gold: &mut i32,
-    count: &mut i32,
-    debug_file: &mut Option<std::fs::File>,
-) {
-    use std::io::Write;
-    
-    // Limit scope to just this inventory section
-    let end_inventory = inv_part.find("</node>").unwrap_or(inv_part.len());
-    let inv_section = &inv_part[..end_inventory];
-    
-    // Find all Item nodes within this inventory
-    let item_parts: Vec<&str> = inv_section.split("<node id=\"Item\">").collect();
-    
-    // Process each item (skip the part before the first Item)
-    for item_part in item_parts.iter().skip(1) {
-        if is_gold_item(item_part) {
-            *count += 1;
-            
-            if let Some(ref mut f) = debug_file {
-                let _ = writeln!(f, "\n=== GOLD ITEM #{} ===\n", count);
-                let preview_len = std::cmp::min(1000, item_part.len());
-                let _ = writeln!(f, "{}\n", &item_part[..preview_len]);
-            }
-            
+/// Returns a SaveState with total gold and a breakdown per owner, including
+/// characters holding none until `SaveState::mark_party` keeps only party
+/// members among those; `resource` is recorded on each stack so it can be edited later
+pub fn get_gold_info(doc: &Document, resource: &str) -> Result<SaveState, AppError> {
+    let mut state = SaveState { total_gold: 0, owners: Vec::new() };
+    let mut gold_items = 0;
+
+    for inventory in inventory::inventories(doc) {
+        let mut group = OwnerGold {
+            owner: inventory.owner,
+            total_gold: 0,
+            items: Vec::new(),
+            item_count: inventory.items.len(),
+            in_party: false,
+            inventories: vec![InventoryLocation { resource: resource.to_string(), node: inventory.node }],
+        };
+
+        for &item in inventory.items.iter().filter(|&&item| is_gold_item(doc, item)) {
             // Extract gold amount
-            let amount = extract_attribute_value(item_part, "Amount")
-                .map(|v| parse_amount(&v))
-                .unwrap_or(1);
-            
+            let amount = item_amount(doc, item)
+                .map_err(|e| AppError::validation(format!("Invalid gold amount: {}", e)))?;
+
             // Extract item name for display
-            let name = extract_attribute_value(item_part, "ItemName")
+            let name = inventory::item_attribute(doc, item, "ItemName")
+                .map(|a| a.value.to_string())
                 .unwrap_or_else(|| "Gold".to_string());
-            
-            if let Some(ref mut f) = debug_file {
-                let _ = writeln!(f, "Amount: {}, Name: {}\n", amount, name);
+
+            group.total_gold += amount as i64;
+            group.items.push(GoldItemDisplay { name, amount, resource: resource.to_string(), node: item });
+        }
+
+        if !group.items.is_empty() || group.owner.kind == OwnerKind::Character {
+            gold_items += group.items.len();
+            state.add_owner(group);
+        }
+    }
+
+    println!("Total gold found: {} (across {} items)", state.total_gold, gold_items);
+    Ok(state)
+}
+
+impl SaveState {
+    /// Mark the owners that are party members, by owner key, and drop the
+    /// other owners holding no gold
+    pub fn mark_party(&mut self, party: &[String]) {
+        for group in &mut self.owners {
+            group.in_party = party.contains(&group.owner.key());
+        }
+        self.owners.retain(|group| group.in_party || !group.items.is_empty());
+    }
+
+    /// Combine the gold found in another resource, merging groups of the same owner
+    pub fn merge(&mut self, other: SaveState) {
+        for group in other.owners {
+            self.add_owner(group);
+        }
+    }
+
+    fn add_owner(&mut self, group: OwnerGold) {
+        self.total_gold += group.total_gold;
+        let key = group.owner.key();
+        match self.owners.iter_mut().find(|g| g.owner.key() == key) {
+            Some(existing) => {
+                existing.total_gold += group.total_gold;
+                existing.item_count += group.item_count;
+                existing.items.extend(group.items);
+                existing.inventories.extend(group.inventories);
             }
-            
-            *total_gold += amount;
-            items.push(GoldItemDisplay { name, amount });
+            None => self.owners.push(group),
         }
     }
 }
 
 #[allow(dead_code)]
-pub fn parse_and_sum_gold(content: &str) -> i32 {
-    get_gold_info(content).total_gold
-}
-
-/// Modify gold amount in character inventory
-/// Consolidates all gold into the first gold item and sets others to 1
-pub fn modify_gold(content: &str, new_amount: i32) -> Result<String, String> {
-    validate_gold_amount(new_amount)?;
-    
-    let lines: Vec<&str> = content.lines().collect();
-    let mut result_lines = Vec::new();
-    let mut found_gold_items = 0;
-    let mut modified_first = false;
-    let mut in_item_node = false;
-    let mut current_item_is_gold = false;
-    
-    for (i, line) in lines.iter().enumerate() {
-        let trimmed = line.trim_start();
-        
-        // Check if entering an Item node
-        if trimmed.starts_with("<node id=\"Item\">") {
-            in_item_node = true;
-            current_item_is_gold = check_item_is_gold(&lines, i);
+pub fn parse_and_sum_gold(doc: &Document) -> Result<i64, AppError> {
+    Ok(get_gold_info(doc, "")?.total_gold)
+}
+
+// ============================================================================
+// Gold Modification
+// ============================================================================
+
+/// A gold change requested by the frontend
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "mode", rename_all = "camelCase")]
+pub enum GoldEdit {
+    /// Set one owner's total, spread over the stacks they already hold
+    #[serde(rename_all = "camelCase")]
+    Owner { owner: String, amount: i32 },
+    /// Set the exact amount of a single stack
+    Stack { resource: String, node: NodeId, amount: i32 },
+    /// Split the party's gold (or `total`) evenly across party members
+    EvenSplit { total: Option<i32> },
+}
+
+/// A change to one owner's gold stacks
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoldChange {
+    /// New amount for an existing stack
+    Set { resource: String, node: NodeId, amount: i32 },
+    /// New stack in an inventory list of an owner holding no gold
+    Create { resource: String, inventory: NodeId, owner: InventoryOwner, amount: i32 },
+    /// Stack set to 0, deleted with the references to it
+    Remove { resource: String, node: NodeId },
+}
+
+/// Work out which stacks change for an edit, from the combined gold state.
+/// Only stacks whose amount actually changes are returned.
+pub fn plan_gold_edit(state: &SaveState, edit: &GoldEdit) -> Result<Vec<GoldChange>, AppError> {
+    let mut changes = Vec::new();
+
+    match edit {
+        GoldEdit::Stack { resource, node, amount } => {
+            validate_gold_amount(*amount)?;
+            let stack = state.owners.iter()
+                .flat_map(|group| group.items.iter())
+                .find(|item| &item.resource == resource && item.node == *node)
+                .ok_or_else(|| AppError::not_found(format!("No gold stack {} in {}", node, resource)))?;
+            if *amount == 0 {
+                changes.push(GoldChange::Remove { resource: resource.clone(), node: *node });
+            } else if stack.amount != *amount {
+                changes.push(GoldChange::Set { resource: resource.clone(), node: *node, amount: *amount });
+            }
         }
-        
-        // Try to modify Amount attribute in gold items
-        if current_item_is_gold && in_item_node && is_amount_attribute(trimmed) {
-            found_gold_items += 1;
-            let amount_to_set = if !modified_first {
-                modified_first = true;
-                new_amount
-            } else {
-                1 // Keep other stacks minimal
+        GoldEdit::Owner { owner, amount } => {
+            validate_gold_amount(*amount)?;
+            let group = state.owners.iter()
+                .find(|group| &group.owner.key() == owner)
+                .ok_or_else(|| AppError::not_found(format!("No inventory found for {}", owner)))?;
+            changes.extend(distribute_over(group, *amount)?);
+        }
+        GoldEdit::EvenSplit { total } => {
+            let party: Vec<&OwnerGold> = state.owners.iter()
+                .filter(|group| group.in_party)
+                .collect();
+            if party.is_empty() {
+                return Err(AppError::not_found("No party members found"));
+            }
+            let total = match total {
+                Some(total) => *total,
+                None => {
+                    let sum: i64 = party.iter().map(|group| group.total_gold).sum();
+                    i32::try_from(sum).map_err(|_| AppError::validation(format!(
+                        "The party holds {} gold, more than can be split", sum
+                    )))?
+                }
             };
-            
-            if let Some(modified_line) = replace_attribute_value(line, "value=\"", amount_to_set) {
-                result_lines.push(modified_line);
-                continue;
+            validate_gold_amount(total)?;
+            for (group, share) in party.iter().zip(split_evenly(total, party.len())) {
+                changes.extend(distribute_over(group, share)?);
             }
         }
-        
-        // Check if exiting Item node
-        if in_item_node && trimmed == "</node>" {
-            in_item_node = false;
-            current_item_is_gold = false;
+    }
+
+    Ok(changes)
+}
+
+/// The changes that bring an owner to a new total: their stacks adjusted,
+/// or a new stack in their first inventory if they hold none and are in the party
+fn distribute_over(group: &OwnerGold, total: i32) -> Result<Vec<GoldChange>, AppError> {
+    if group.items.is_empty() {
+        if total == 0 {
+            return Ok(Vec::new());
+        }
+        if !group.in_party {
+            return Err(AppError::validation(format!("{} is not in the party and holds no gold", group.owner.name)));
+        }
+        let inventory = group.inventories.first()
+            .ok_or_else(|| AppError::not_found(format!("No inventory found for {}", group.owner.name)))?;
+        return Ok(vec![GoldChange::Create {
+            resource: inventory.resource.clone(),
+            inventory: inventory.node,
+            owner: group.owner.clone(),
+            amount: total,
+        }]);
+    }
+
+    let current: Vec<i32> = group.items.iter().map(|item| item.amount).collect();
+    let amounts = distribute_gold(&current, total)
+        .map_err(|e| AppError::validation(format!("{}: {}", group.owner.name, e)))?;
+    Ok(group.items.iter()
+        .zip(amounts)
+        .filter(|(item, amount)| item.amount != *amount)
+        .map(|(item, amount)| match amount {
+            0 => GoldChange::Remove { resource: item.resource.clone(), node: item.node },
+            _ => GoldChange::Set { resource: item.resource.clone(), node: item.node, amount },
+        })
+        .collect())
+}
+
+/// Change stack amounts so they add up to `target`, keeping exact amounts
+/// where possible: a raise goes to the first stack, a cut is taken from the
+/// last stacks first. Stacks cut to 0 are to be deleted.
+pub fn distribute_gold(current: &[i32], target: i32) -> Result<Vec<i32>, String> {
+    let mut amounts = current.to_vec();
+    let Some(first) = amounts.first_mut() else {
+        return Err("no gold stacks to change".to_string());
+    };
+
+    let total: i64 = current.iter().map(|&a| a as i64).sum();
+    let target_wide = target as i64;
+    if target_wide >= total {
+        let raised = *first as i64 + (target_wide - total);
+        *first = i32::try_from(raised).map_err(|_| format!("{} gold does not fit in one stack", raised))?;
+        return Ok(amounts);
+    }
+
+    let mut remove = total - target_wide;
+    for amount in amounts.iter_mut().rev() {
+        let take = remove.min((*amount as i64).max(0));
+        *amount -= take as i32;
+        remove -= take;
+    }
+    Ok(amounts)
+}
+
+/// `total` split into `parts` shares that differ by at most 1, larger shares first
+pub fn split_evenly(total: i32, parts: usize) -> Vec<i32> {
+    if parts == 0 {
+        return Vec::new();
+    }
+    let parts_i32 = parts as i32;
+    let (share, remainder) = (total / parts_i32, total % parts_i32);
+    (0..parts_i32)
+        .map(|i| share + i32::from(i < remainder))
+        .collect()
+}
+
+/// Set the stack size of a gold item, adding the Amount attribute if it is missing
+pub fn set_stack_amount(doc: &mut Document, item: NodeId, amount: i32) -> Result<(), AppError> {
+    validate_gold_amount(amount)?;
+    if !doc.contains(item) || doc.node(item).name != "Item" || !is_gold_item(doc, item) {
+        return Err(AppError::validation(format!("Node {} is not a gold item", item)));
+    }
+    write_amount(doc, item, amount)
+}
+
+/// Write the Amount attribute `item_amount` reads, or add one to the item
+fn write_amount(doc: &mut Document, item: NodeId, amount: i32) -> Result<(), AppError> {
+    let holder = inventory::own_nodes(doc, item).into_iter()
+        .find(|&node| doc.node(node).attribute("Amount").is_some());
+    match holder.and_then(|node| doc.node_mut(node).attribute_mut("Amount")) {
+        Some(attribute) => attribute.set_int(amount as i64)?,
+        None => {
+            let attribute = Attribute::new("Amount", AttributeType::Int32, AttributeValue::Int32(amount))?;
+            doc.node_mut(item).attributes.push(attribute);
         }
-        
-        result_lines.push(line.to_string());
     }
-    
-    if found_gold_items == 0 {
-        return Err("No gold inventory items found in save file".to_string());
+    Ok(())
+}
+
+// ============================================================================
+// Stack Amounts
+// ============================================================================
+
+/// Attributes an item may carry with its own stack limit
+const STACK_LIMIT_ATTRIBUTES: [&str; 3] = ["MaxStackAmount", "MaxAmount", "StackLimit"];
+
+/// A stack size change for items other than gold
+#[derive(Debug, serde::Deserialize)]
+pub struct StackEdit {
+    pub item: inventory::ItemSelector,
+    pub amount: i32,
+}
+
+/// Check that `amount` is a valid stack size for the item: it must be
+/// stackable, hold at least one, and stay within its stack limit and the
+/// range of its Amount attribute
+pub fn check_item_amount(doc: &Document, item: NodeId, amount: i32) -> Result<(), AppError> {
+    if !doc.contains(item) || doc.node(item).name != "Item" || !inventory::is_stackable(doc, item) {
+        return Err(AppError::validation(format!("Node {} is not a stackable item", item)));
+    }
+    if amount < 1 {
+        return Err(AppError::validation("A stack must hold at least 1 item"));
     }
-    
-    println!("Modified {} gold inventory items. Total set to {}", found_gold_items, new_amount);
-    Ok(result_lines.join("\n"))
+
+    let limit = STACK_LIMIT_ATTRIBUTES.iter()
+        .filter_map(|id| inventory::item_attribute(doc, item, id)?.value.as_int())
+        .find(|&limit| limit > 0);
+    if let Some(limit) = limit.filter(|&limit| amount as i64 > limit) {
+        return Err(AppError::validation(format!("Stack limit is {}, got {}", limit, amount)));
+    }
+
+    if let Some(attribute) = inventory::item_attribute(doc, item, "Amount") {
+        AttributeValue::from_int(attribute.attr_type, amount as i64)?;
+    }
+    Ok(())
+}
+
+/// Set the stack size of any stackable item (potions, arrows, scrolls, camp
+/// supplies, gold), adding the Amount attribute if it is missing
+pub fn set_item_amount(doc: &mut Document, item: NodeId, amount: i32) -> Result<(), AppError> {
+    check_item_amount(doc, item, amount)?;
+    write_amount(doc, item, amount)
+}
+
+/// Add a gold stack to an inventory list, copied from `prototype`: an
+/// existing gold item, possibly from another resource, or one built from
+/// `templates::gold`
+pub fn create_gold_stack(
+    doc: &mut Document,
+    inventory: NodeId,
+    owner: &InventoryOwner,
+    prototype: &Subtree,
+    amount: i32,
+) -> Result<NodeId, AppError> {
+    validate_gold_amount(amount)?;
+    let node = inventory::place_item(doc, inventory, owner, &prototype.clone().into(), false)?;
+    set_stack_amount(doc, node, amount)?;
+    println!("Created gold stack {} with {} gold for {}", node, amount, owner.name);
+    Ok(node)
 }
 
 /// Validate gold amount is non-negative
-fn validate_gold_amount(amount: i32) -> Result<(), String> {
+pub fn validate_gold_amount(amount: i32) -> Result<(), AppError> {
     if amount < 0 {
-        Err("Gold amount cannot be negative".to_string())
+        Err(AppError::validation("Gold amount cannot be negative"))
     } else {
         Ok(())
     }
 }
 
-/// Check if an item (at given line index) is a gold item by looking ahead
-fn check_item_is_gold(lines: &[&str], start_idx: usize) -> bool {
-    // Look up to 50 lines ahead for gold indicators or closing tag
-    for j in (start_idx + 1)..std::cmp::min(start_idx + 50, lines.len()) {
-        let line = lines[j].trim();
-        if line.starts_with("</node>") {
-            return false; // Item ended without finding gold
-        }
-        if lines[j].contains("LOOT_Gold") || lines[j].contains("OBJ_Gold") {
-            return true;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsx;
+
+    /// A character holding a gold stack and a bag with a second stack inside
+    const BAG_SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<save>
+	<version major="4" minor="0" revision="9" build="331" />
+	<region id="Characters">
+		<node id="Characters">
+			<children>
+				<node id="Character">
+					<attribute id="UUID" type="FixedString" value="aaaaaaaa-0000-0000-0000-000000000001" />
+					<children>
+						<node id="InventoryList">
+							<children>
+								<node id="Item">
+									<attribute id="UUID" type="FixedString" value="gold-1" />
+									<attribute id="Stats" type="FixedString" value="LOOT_Gold_A" />
+									<attribute id="Amount" type="int32" value="100" />
+								</node>
+								<node id="Item">
+									<attribute id="UUID" type="FixedString" value="bag-1" />
+									<attribute id="Stats" type="FixedString" value="CONT_Bag_A" />
+									<children>
+										<node id="InventoryList">
+											<children>
+												<node id="Item">
+													<attribute id="UUID" type="FixedString" value="gold-2" />
+													<attribute id="Stats" type="FixedString" value="LOOT_Gold_A" />
+													<attribute id="Amount" type="int32" value="40" />
+												</node>
+											</children>
+										</node>
+									</children>
+								</node>
+							</children>
+						</node>
+					</children>
+				</node>
+			</children>
+		</node>
+	</region>
+</save>"#;
+
+    fn item(doc: &Document, uuid: &str) -> NodeId {
+        inventory::find_items(doc, &inventory::ItemSelector::Uuid { uuid: uuid.to_string() })[0]
+    }
+
+    #[test]
+    fn bags_holding_gold_are_not_gold() {
+        let doc = lsx::read(BAG_SAMPLE).unwrap();
+        let state = get_gold_info(&doc, "Globals.lsf").unwrap();
+        assert_eq!(state.total_gold, 140);
+        let stacks: Vec<NodeId> = state.owners.iter().flat_map(|group| group.items.iter().map(|i| i.node)).collect();
+        assert!(stacks.contains(&item(&doc, "gold-1")) && stacks.contains(&item(&doc, "gold-2")));
+        assert!(!stacks.contains(&item(&doc, "bag-1")));
+    }
+
+    #[test]
+    fn amounts_are_read_and_written_on_the_item_itself() {
+        let mut doc = lsx::read(BAG_SAMPLE).unwrap();
+        let bag = item(&doc, "bag-1");
+        assert_eq!(item_amount(&doc, bag).unwrap(), 1);
+        assert!(set_stack_amount(&mut doc, bag, 5).is_err());
+
+        let gold = item(&doc, "gold-2");
+        set_stack_amount(&mut doc, gold, 75).unwrap();
+        assert_eq!(item_amount(&doc, gold).unwrap(), 75);
+        assert_eq!(doc.node(bag).attribute("Amount"), None);
+    }
+
+    #[test]
+    fn stacks_stay_within_their_limits() {
+        let text = BAG_SAMPLE
+            .replace(r#"value="100" />"#, r#"value="100" />
+									<attribute id="MaxStackAmount" type="int32" value="150" />"#)
+            .replace(r#"<attribute id="Amount" type="int32" value="40" />"#, r#"<attribute id="Amount" type="uint8" value="40" />"#);
+        let mut doc = lsx::read(&text).unwrap();
+        let (limited, small) = (item(&doc, "gold-1"), item(&doc, "gold-2"));
+
+        assert!(check_item_amount(&doc, item(&doc, "bag-1"), 2).is_err());
+        assert!(check_item_amount(&doc, limited, 0).is_err());
+        assert!(check_item_amount(&doc, limited, 151).is_err());
+        assert!(check_item_amount(&doc, small, 256).is_err());
+
+        set_item_amount(&mut doc, limited, 150).unwrap();
+        set_item_amount(&mut doc, small, 255).unwrap();
+        assert_eq!((item_amount(&doc, limited).unwrap(), item_amount(&doc, small).unwrap()), (150, 255));
+    }
+
+    /// An owner holding one stack per amount, stacks numbered from `first_node`
+    fn owner(key: &str, kind: OwnerKind, in_party: bool, amounts: &[i32], first_node: NodeId) -> OwnerGold {
+        OwnerGold {
+            owner: InventoryOwner { kind, uuid: Some(key.to_string()), name: key.to_string(), origin: None },
+            total_gold: amounts.iter().map(|&a| a as i64).sum(),
+            items: amounts.iter().enumerate()
+                .map(|(i, &amount)| GoldItemDisplay {
+                    name: "Gold".to_string(),
+                    amount,
+                    resource: "Globals.lsf".to_string(),
+                    node: first_node + i,
+                })
+                .collect(),
+            item_count: amounts.len(),
+            in_party,
+            inventories: vec![InventoryLocation { resource: "Globals.lsf".to_string(), node: first_node + 100 }],
         }
     }
-    false
-}
 
-/// Check if a line is an Amount attribute
-fn is_amount_attribute(line: &str) -> bool {
-    line.contains("id=\"Amount\"") && line.contains("type=\"int32\"") && line.contains("value=\"")
-}
+    #[test]
+    fn distribute_gold_raises_the_first_stack_and_cuts_from_the_last() {
+        assert_eq!(distribute_gold(&[10, 20], 50), Ok(vec![30, 20]));
+        assert_eq!(distribute_gold(&[10, 20], 15), Ok(vec![10, 5]));
+        assert_eq!(distribute_gold(&[10, 20], 5), Ok(vec![5, 0]));
+        assert_eq!(distribute_gold(&[10, 20], 0), Ok(vec![0, 0]));
+        assert!(distribute_gold(&[], 10).is_err());
+        assert!(distribute_gold(&[i32::MAX, 1], i32::MAX).is_ok());
+        assert!(distribute_gold(&[i32::MAX - 1, 0], i32::MAX).is_ok());
+    }
+
+    #[test]
+    fn split_evenly_gives_the_remainder_to_the_first_shares() {
+        assert_eq!(split_evenly(10, 3), vec![4, 3, 3]);
+        assert_eq!(split_evenly(9, 3), vec![3, 3, 3]);
+        assert_eq!(split_evenly(1, 2), vec![1, 0]);
+        assert!(split_evenly(10, 0).is_empty());
+    }
+
+    #[test]
+    fn emptied_stacks_are_removed() {
+        let state = SaveState { total_gold: 30, owners: vec![owner("hero", OwnerKind::Character, true, &[10, 20], 1)] };
+        let edit = GoldEdit::Owner { owner: "hero".to_string(), amount: 0 };
+        assert_eq!(plan_gold_edit(&state, &edit).unwrap(), vec![
+            GoldChange::Remove { resource: "Globals.lsf".to_string(), node: 1 },
+            GoldChange::Remove { resource: "Globals.lsf".to_string(), node: 2 },
+        ]);
+        let edit = GoldEdit::Stack { resource: "Globals.lsf".to_string(), node: 2, amount: 0 };
+        assert_eq!(plan_gold_edit(&state, &edit).unwrap(), vec![
+            GoldChange::Remove { resource: "Globals.lsf".to_string(), node: 2 },
+        ]);
+    }
 
-/// Replace an attribute value in a line
-/// Handles format like: `<attribute id="Amount" type="int32" value="123" />`
-fn replace_attribute_value(line: &str, value_marker: &str, new_value: i32) -> Option<String> {
-    let value_start = line.find(value_marker)?;
-    let absolute_value_idx = value_start + value_marker.len();
-    let value_end = line[absolute_value_idx..].find('"')?;
-    
-    let before_value = &line[..absolute_value_idx];
-    let after_value = &line[absolute_value_idx + value_end..];
-    
-    Some(format!("{}{}{}", before_value, new_value, after_value))
+    #[test]
+    fn even_split_goes_to_party_members_only() {
+        let state = SaveState {
+            total_gold: 160,
+            owners: vec![
+                owner("hero", OwnerKind::Character, true, &[90], 1),
+                owner("npc", OwnerKind::Character, false, &[60], 10),
+                owner("companion", OwnerKind::Character, true, &[10], 20),
+            ],
+        };
+        let changes = plan_gold_edit(&state, &GoldEdit::EvenSplit { total: None }).unwrap();
+        assert_eq!(changes, vec![
+            GoldChange::Set { resource: "Globals.lsf".to_string(), node: 1, amount: 50 },
+            GoldChange::Set { resource: "Globals.lsf".to_string(), node: 20, amount: 50 },
+        ]);
+    }
+
+    #[test]
+    fn party_members_are_marked_by_owner_key() {
+        let mut state = SaveState {
+            total_gold: 0,
+            owners: vec![
+                owner("hero", OwnerKind::Character, false, &[], 1),
+                owner("npc", OwnerKind::Character, false, &[], 10),
+            ],
+        };
+        state.mark_party(&["hero".to_string()]);
+        assert_eq!(state.owners.len(), 1);
+        assert!(state.owners[0].in_party);
+    }
+
+    #[test]
+    fn new_stacks_go_to_party_members_only() {
+        let mut state = SaveState {
+            total_gold: 0,
+            owners: vec![
+                owner("hero", OwnerKind::Character, true, &[], 1),
+                owner("npc", OwnerKind::Character, false, &[], 10),
+            ],
+        };
+        let edit = GoldEdit::Owner { owner: "hero".to_string(), amount: 25 };
+        assert!(matches!(plan_gold_edit(&state, &edit).unwrap()[..], [GoldChange::Create { amount: 25, .. }]));
+        let edit = GoldEdit::Owner { owner: "npc".to_string(), amount: 25 };
+        assert!(plan_gold_edit(&state, &edit).is_err());
+
+        state.mark_party(&["hero".to_string()]);
+        assert!(plan_gold_edit(&state, &edit).is_err());
+    }
 }
@@ -0,0 +1,464 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use crate::bg3_io;
+use crate::document::Document;
+use crate::error::AppError;
+use crate::lsf;
+use crate::lspk;
+use crate::lsx;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// An extracted save being viewed or edited
+pub struct SaveSession {
+    pub id: String,
+    /// The .lsv the session was opened from
+    pub source_path: PathBuf,
+    /// Private extraction folder in the OS cache dir
+    pub workspace: PathBuf,
+    /// Level the party is in, from SaveInfo.json's "Current Level"
+    pub current_level: Option<String>,
+    /// Resources loaded so far, keyed by path relative to the workspace
+    documents: HashMap<PathBuf, Document>,
+    /// Loaded resources that were changed since the last save
+    modified: BTreeSet<PathBuf>,
+    /// Resources handed out for editing by the command now running
+    editing: BTreeSet<PathBuf>,
+    /// Modified resources as they were before the command now running edited them
+    snapshots: HashMap<PathBuf, Document>,
+    /// Text files (e.g. SaveInfo.json) replaced since the last save, by relative path
+    texts: BTreeMap<String, String>,
+    /// Text files replaced by the command now running
+    editing_texts: BTreeMap<String, String>,
+    pub dirty: bool,
+}
+
+/// Session summary sent to the frontend
+#[derive(Debug, serde::Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub source_path: String,
+    pub workspace: String,
+    pub current_level: Option<String>,
+    /// Every level in LevelCache, current level first
+    pub levels: Vec<String>,
+    pub dirty: bool,
+}
+
+/// Result of writing a session back to a package
+#[derive(Debug, serde::Serialize)]
+pub struct SavedSession {
+    pub backup_path: String,
+    pub output_path: String,
+}
+
+/// All open sessions, kept in Tauri state. Each session has its own lock,
+/// so a long save only blocks commands on that session.
+#[derive(Default)]
+pub struct SessionRegistry {
+    sessions: Mutex<HashMap<String, Arc<Mutex<SaveSession>>>>,
+    next_id: AtomicU64,
+}
+
+// ============================================================================
+// Workspace Helpers
+// ============================================================================
+
+/// Root folder for session workspaces, e.g. ~/.cache/bg3-save-editor/sessions
+fn workspace_root() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("bg3-save-editor")
+        .join("sessions")
+}
+
+/// Recursively clean directory and create fresh copy
+fn clean_and_create_directory(path: &Path) -> Result<(), AppError> {
+    if path.exists() {
+        std::fs::remove_dir_all(path).map_err(|e| AppError::io(path, e))?;
+    }
+    std::fs::create_dir_all(path).map_err(|e| AppError::io(path, e))
+}
+
+/// Whether a path is an LSF or LSX resource
+fn is_resource_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|s| s.to_str()), Some("lsf") | Some("lsx"))
+}
+
+/// Load an LSF or LSX resource into a document tree, based on its extension
+fn load_resource(path: &Path) -> Result<Document, AppError> {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("lsx") => lsx::read_file(path),
+        _ => lsf::read_file(path),
+    }
+}
+
+/// "Current Level" from the extracted SaveInfo.json, if it can be read
+fn read_current_level(workspace: &Path) -> Option<String> {
+    let path = workspace.join("SaveInfo.json");
+    let content = std::fs::read_to_string(&path).ok()?;
+    let info: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(info) => info,
+        Err(e) => {
+            println!("Failed to parse {:?}: {}", path, e);
+            return None;
+        }
+    };
+    info.get("Current Level")?.as_str().map(str::to_string)
+}
+
+/// Write a document back in the format its file extension calls for
+fn write_resource(document: &Document, path: &Path) -> Result<(), AppError> {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("lsx") => lsx::write_file(document, path),
+        _ => lsf::write_file(document, path),
+    }
+}
+
+// ============================================================================
+// Save Session
+// ============================================================================
+
+impl SaveSession {
+    /// Extract a save into a fresh workspace
+    fn open(id: String, source_path: &Path) -> Result<Self, AppError> {
+        if !source_path.is_file() {
+            return Err(AppError::FileNotFound { path: source_path.display().to_string() });
+        }
+
+        let workspace = workspace_root().join(&id);
+        clean_and_create_directory(&workspace)?;
+        if let Err(e) = lspk::extract_package(source_path, &workspace) {
+            let _ = std::fs::remove_dir_all(&workspace);
+            return Err(e);
+        }
+
+        let current_level = read_current_level(&workspace);
+        println!("Opened session {} for {:?} in {:?} (current level: {:?})", id, source_path, workspace, current_level);
+        Ok(SaveSession {
+            id,
+            source_path: source_path.to_path_buf(),
+            workspace,
+            current_level,
+            documents: HashMap::new(),
+            modified: BTreeSet::new(),
+            editing: BTreeSet::new(),
+            snapshots: HashMap::new(),
+            texts: BTreeMap::new(),
+            editing_texts: BTreeMap::new(),
+            dirty: false,
+        })
+    }
+
+    pub fn info(&self) -> SessionInfo {
+        let levels = self.level_files()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+            .collect();
+        SessionInfo {
+            id: self.id.clone(),
+            source_path: self.source_path.to_string_lossy().to_string(),
+            workspace: self.workspace.to_string_lossy().to_string(),
+            current_level: self.current_level.clone(),
+            levels,
+            dirty: self.dirty,
+        }
+    }
+
+    /// Find a resource by name, preferring the binary .lsf over a converted .lsx.
+    /// Returns the path relative to the workspace.
+    pub fn find_resource(&self, stem: &str) -> Option<PathBuf> {
+        ["lsf", "lsx"].iter()
+            .map(|ext| PathBuf::from(format!("{}.{}", stem, ext)))
+            .find(|relative| self.workspace.join(relative).exists())
+    }
+
+    /// The level files in LevelCache relative to the workspace: the current
+    /// level first, then the others sorted by name
+    pub fn level_files(&self) -> Result<Vec<PathBuf>, AppError> {
+        let level_dir = self.workspace.join("LevelCache");
+        if !level_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let entries = std::fs::read_dir(&level_dir)
+            .map_err(|e| AppError::io(&level_dir, e))?;
+
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| is_resource_file(path))
+            .filter_map(|path| path.strip_prefix(&self.workspace).ok().map(Path::to_path_buf))
+            .collect();
+        let is_current = |path: &PathBuf| {
+            path.file_stem().and_then(|s| s.to_str()) == self.current_level.as_deref()
+        };
+        paths.sort_by(|a, b| is_current(b).cmp(&is_current(a)).then_with(|| a.cmp(b)));
+        Ok(paths)
+    }
+
+    /// Globals plus every level, i.e. every resource that can hold party state
+    pub fn resource_files(&self) -> Result<Vec<PathBuf>, AppError> {
+        let mut paths: Vec<PathBuf> = self.find_resource("Globals").into_iter().collect();
+        paths.extend(self.level_files()?);
+        Ok(paths)
+    }
+
+    /// Read a file from the workspace, e.g. SaveInfo.json, with unsaved
+    /// replacements applied
+    pub fn read_text(&self, relative: &str) -> Result<String, AppError> {
+        if let Some(content) = self.editing_texts.get(relative).or_else(|| self.texts.get(relative)) {
+            return Ok(content.clone());
+        }
+        let path = self.workspace.join(relative);
+        std::fs::read_to_string(&path).map_err(|e| AppError::io(&path, e))
+    }
+
+    /// Replace a file in the workspace, e.g. SaveInfo.json. Like documents, it
+    /// is kept in memory, dropped if the command fails and written on save.
+    pub fn write_text(&mut self, relative: &str, content: &str) -> Result<(), AppError> {
+        self.editing_texts.insert(relative.to_string(), content.to_string());
+        Ok(())
+    }
+
+    /// A resource's document tree, loaded on first use
+    pub fn document(&mut self, relative: &Path) -> Result<&Document, AppError> {
+        self.load(relative).map(|document| &*document)
+    }
+
+    /// A resource's document tree for editing; it is marked modified when
+    /// the command succeeds (see `finish_edits`)
+    pub fn document_mut(&mut self, relative: &Path) -> Result<&mut Document, AppError> {
+        self.load(relative)?;
+        if self.editing.insert(relative.to_path_buf()) && self.modified.contains(relative) {
+            self.snapshots.insert(relative.to_path_buf(), self.documents[relative].clone());
+        }
+        Ok(self.documents.get_mut(relative).unwrap())
+    }
+
+    /// Settle the resources and text files a command edited: after a success
+    /// they are modified and the session dirty; after a failure, resources
+    /// with earlier changes go back to how they were before the command,
+    /// the others are dropped so they are read back unchanged on next use,
+    /// and replaced text files are forgotten
+    fn finish_edits(&mut self, succeeded: bool) {
+        let mut snapshots = std::mem::take(&mut self.snapshots);
+        for relative in std::mem::take(&mut self.editing) {
+            if succeeded {
+                self.modified.insert(relative);
+                self.dirty = true;
+            } else if self.modified.contains(&relative) {
+                if let Some(snapshot) = snapshots.remove(&relative) {
+                    self.documents.insert(relative, snapshot);
+                }
+            } else {
+                self.documents.remove(&relative);
+            }
+        }
+
+        let texts = std::mem::take(&mut self.editing_texts);
+        if succeeded && !texts.is_empty() {
+            self.texts.extend(texts);
+            self.dirty = true;
+        }
+    }
+
+    fn load(&mut self, relative: &Path) -> Result<&mut Document, AppError> {
+        if !self.documents.contains_key(relative) {
+            let document = load_resource(&self.workspace.join(relative))?;
+            self.documents.insert(relative.to_path_buf(), document);
+        }
+        Ok(self.documents.get_mut(relative).unwrap())
+    }
+
+    /// Write modified resources to the workspace, back up the original save
+    /// and repack next to it as `<name>_modified.lsv`
+    pub fn save(&mut self) -> Result<SavedSession, AppError> {
+        let source = self.source_path.to_string_lossy().to_string();
+        let backup_path = bg3_io::backup_save(&source)?;
+
+        for relative in &self.modified {
+            write_resource(&self.documents[relative], &self.workspace.join(relative))?;
+        }
+        for (relative, content) in &self.texts {
+            let path = self.workspace.join(relative);
+            std::fs::write(&path, content).map_err(|e| AppError::io(&path, e))?;
+        }
+
+        // Repack the save natively, keeping the original file list and compression
+        let output_path = format!("{}_modified.lsv", source.trim_end_matches(".lsv"));
+        lspk::repack_like(&self.source_path, &self.workspace, Path::new(&output_path))?;
+
+        self.modified.clear();
+        self.texts.clear();
+        self.dirty = false;
+        println!("Saved session {} to {}", self.id, output_path);
+        Ok(SavedSession { backup_path, output_path })
+    }
+
+    /// Remove the session's workspace
+    fn close(&self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.workspace) {
+            println!("Failed to remove workspace {:?}: {}", self.workspace, e);
+        }
+    }
+}
+
+// ============================================================================
+// Session Registry
+// ============================================================================
+
+impl SessionRegistry {
+    /// Extract a save into a new session and return its summary
+    pub fn open(&self, source_path: &Path) -> Result<SessionInfo, AppError> {
+        let id = format!(
+            "{}-{}",
+            chrono::Local::now().format("%Y%m%d%H%M%S"),
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        );
+        // Extract without holding the lock so other sessions stay usable
+        let session = SaveSession::open(id.clone(), source_path)?;
+        let info = session.info();
+        self.lock().insert(id, Arc::new(Mutex::new(session)));
+        Ok(info)
+    }
+
+    /// Run `f` on an open session, holding only that session's lock
+    pub fn with_session<T>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut SaveSession) -> Result<T, AppError>,
+    ) -> Result<T, AppError> {
+        let session = self.lock().get(id).cloned()
+            .ok_or_else(|| AppError::not_found(format!("Unknown session {}. Extract a save first.", id)))?;
+        let mut session = lock_session(&session);
+        let result = f(&mut session);
+        session.finish_edits(result.is_ok());
+        result
+    }
+
+    pub fn list(&self) -> Vec<SessionInfo> {
+        let sessions: Vec<Arc<Mutex<SaveSession>>> = self.lock().values().cloned().collect();
+        let mut sessions: Vec<SessionInfo> = sessions.iter().map(|session| lock_session(session).info()).collect();
+        sessions.sort_by(|a, b| a.id.cmp(&b.id));
+        sessions
+    }
+
+    /// Close a session and delete its workspace once commands running on it
+    /// finish; unknown ids are ignored
+    pub fn close(&self, id: &str) {
+        let session = self.lock().remove(id);
+        if let Some(session) = session {
+            lock_session(&session).close();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Arc<Mutex<SaveSession>>>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn lock_session(session: &Mutex<SaveSession>) -> MutexGuard<'_, SaveSession> {
+    session.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<save>
+	<version major="4" minor="0" revision="9" build="331" />
+	<region id="Characters">
+		<node id="Characters">
+			<attribute id="Name" type="LSString" value="Tav" />
+			<attribute id="Level" type="int32" value="5" />
+		</node>
+	</region>
+</save>"#;
+
+    /// A session over a workspace holding Globals.lsx and SaveInfo.json
+    fn session(name: &str) -> SaveSession {
+        let workspace = std::env::temp_dir().join(format!("session-{}-{}", name, std::process::id()));
+        clean_and_create_directory(&workspace).unwrap();
+        std::fs::write(workspace.join("Globals.lsx"), SAMPLE).unwrap();
+        std::fs::write(workspace.join("SaveInfo.json"), "{}").unwrap();
+        SaveSession {
+            id: name.to_string(),
+            source_path: workspace.join("test.lsv"),
+            workspace,
+            current_level: None,
+            documents: HashMap::new(),
+            modified: BTreeSet::new(),
+            editing: BTreeSet::new(),
+            snapshots: HashMap::new(),
+            texts: BTreeMap::new(),
+            editing_texts: BTreeMap::new(),
+            dirty: false,
+        }
+    }
+
+    /// Drop one attribute of the Characters node, as a command would edit it
+    fn edit(session: &mut SaveSession) {
+        let document = session.document_mut(Path::new("Globals.lsx")).unwrap();
+        let node = document.find_nodes("Characters")[0];
+        document.node_mut(node).attributes.pop();
+    }
+
+    fn attribute_count(session: &mut SaveSession) -> usize {
+        let document = session.document(Path::new("Globals.lsx")).unwrap();
+        document.node(document.find_nodes("Characters")[0]).attributes.len()
+    }
+
+    #[test]
+    fn failed_edits_are_dropped() {
+        let mut session = session("dropped");
+        edit(&mut session);
+        session.finish_edits(false);
+        assert_eq!(attribute_count(&mut session), 2);
+        assert!(!session.dirty && session.modified.is_empty());
+
+        edit(&mut session);
+        session.finish_edits(true);
+        assert_eq!(attribute_count(&mut session), 1);
+        assert!(session.dirty && session.modified.contains(Path::new("Globals.lsx")));
+        session.close();
+    }
+
+    #[test]
+    fn failed_edits_to_modified_documents_are_rolled_back() {
+        let mut session = session("rolled-back");
+        edit(&mut session);
+        session.finish_edits(true);
+
+        edit(&mut session);
+        edit(&mut session);
+        assert_eq!(attribute_count(&mut session), 0);
+        session.finish_edits(false);
+        assert_eq!(attribute_count(&mut session), 1);
+        assert!(session.modified.contains(Path::new("Globals.lsx")));
+        session.close();
+    }
+
+    #[test]
+    fn text_files_are_kept_until_saved() {
+        let mut session = session("texts");
+        session.write_text("SaveInfo.json", "{\"Level\":6}").unwrap();
+        assert_eq!(session.read_text("SaveInfo.json").unwrap(), "{\"Level\":6}");
+        session.finish_edits(false);
+        assert_eq!(session.read_text("SaveInfo.json").unwrap(), "{}");
+        assert!(!session.dirty);
+
+        session.write_text("SaveInfo.json", "{\"Level\":7}").unwrap();
+        session.finish_edits(true);
+        assert_eq!(session.read_text("SaveInfo.json").unwrap(), "{\"Level\":7}");
+        assert!(session.dirty);
+        assert_eq!(std::fs::read_to_string(session.workspace.join("SaveInfo.json")).unwrap(), "{}");
+        session.close();
+    }
+}
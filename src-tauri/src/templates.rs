@@ -0,0 +1,275 @@
+use crate::document::{Attribute, AttributeType, Document, Subtree};
+use crate::error::AppError;
+use crate::inventory;
+use crate::values::AttributeValue;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+/// What is needed to build an item of a root template
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ItemTemplate {
+    /// Root template UUID (or template name when the save has no UUID)
+    pub template_id: String,
+    pub stats: Option<String>,
+    /// Internal item name, e.g. LOOT_Gold_A
+    pub name: String,
+    pub level_name: String,
+    pub stackable: bool,
+    /// Flag attributes (IsTradable, CanBePickpocketed, ...) as seen on an existing item
+    #[serde(skip)]
+    pub flags: Vec<Attribute>,
+}
+
+/// A root template the save holds no item of, described by the caller
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TemplateDescription {
+    pub stats: Option<String>,
+    /// Internal item name; the stats id or template UUID when missing
+    pub name: Option<String>,
+    #[serde(default)]
+    pub level_name: String,
+    #[serde(default)]
+    pub stackable: bool,
+    /// Bool flags set on the new item, e.g. IsTradable
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+// ============================================================================
+// Known Templates
+// ============================================================================
+
+/// Gold item template, available even when the save holds no gold: the
+/// game's `LOOT_Gold_A` root template (Public/Shared/RootTemplates), the
+/// UUID scripts pass to `TemplateAddTo` to hand out gold
+const GOLD_ITEM_NAME: &str = "LOOT_Gold_A";
+const GOLD_TEMPLATE_ID: &str = "1c3c9c74-34a1-4685-989e-410dc080be6f";
+
+/// Marker in the name or stats id of every gold template
+const GOLD_MARKER: &str = "LOOT_Gold";
+
+/// The gold template of the items in the save when there is one, otherwise
+/// the game's `LOOT_Gold_A`
+pub fn gold(known: &[ItemTemplate]) -> ItemTemplate {
+    let in_save = known.iter().find(|t| {
+        t.name.contains(GOLD_MARKER) || t.stats.as_deref().is_some_and(|stats| stats.contains(GOLD_MARKER))
+    });
+    match in_save {
+        Some(template) => template.clone(),
+        None => ItemTemplate {
+            template_id: GOLD_TEMPLATE_ID.to_string(),
+            stats: None,
+            name: GOLD_ITEM_NAME.to_string(),
+            level_name: String::new(),
+            stackable: true,
+            flags: Vec::new(),
+        },
+    }
+}
+
+/// Templates of the items in a document, one per template id, first item wins
+pub fn templates_in(doc: &Document) -> Vec<ItemTemplate> {
+    let mut templates = Vec::new();
+    for item in inventory::inventories(doc).into_iter().flat_map(|inventory| inventory.items) {
+        let text = |id: &str| inventory::item_attribute(doc, item, id)
+            .and_then(|a| a.value.as_str())
+            .map(str::to_string);
+        let Some(template_id) = inventory::item_template(doc, item) else {
+            continue;
+        };
+        let flags = doc.node(item).attributes.iter()
+            .filter(|a| is_flag(a))
+            .cloned()
+            .map(|a| Attribute { source: None, ..a })
+            .collect();
+        merge_templates(&mut templates, vec![ItemTemplate {
+            stats: inventory::item_stats(doc, item),
+            name: text("ItemName").unwrap_or_else(|| template_id.clone()),
+            level_name: text("LevelName").unwrap_or_default(),
+            stackable: inventory::is_stackable(doc, item),
+            flags,
+            template_id,
+        }]);
+    }
+    templates
+}
+
+/// Add templates from another resource, keeping the first of each id
+pub fn merge_templates(into: &mut Vec<ItemTemplate>, other: Vec<ItemTemplate>) {
+    for template in other {
+        if !into.iter().any(|t| t.template_id.eq_ignore_ascii_case(&template.template_id)) {
+            into.push(template);
+        }
+    }
+}
+
+/// Find a template by root template UUID or stats id among the templates
+/// seen in the save and the built-in ones
+pub fn find_template(known: &[ItemTemplate], id: &str) -> Result<ItemTemplate, AppError> {
+    let id = id.trim();
+    let builtin = [gold(known)];
+    known.iter()
+        .chain(builtin.iter())
+        .find(|t| t.matches(id))
+        .cloned()
+        .ok_or_else(|| AppError::not_found(format!(
+            "Unknown item template {}. Give its stats, level name and flags to add it, \
+             or pick a template of an item already in the save.",
+            id
+        )))
+}
+
+/// Find a template as `find_template` does; an unknown one is built from
+/// `description` when one is given
+pub fn resolve_template(known: &[ItemTemplate], id: &str, description: Option<&TemplateDescription>) -> Result<ItemTemplate, AppError> {
+    match (find_template(known, id), description) {
+        (Err(_), Some(description)) => description.template(id),
+        (found, _) => found,
+    }
+}
+
+/// Bool flags and small Is/Can switches describing an item
+fn is_flag(attribute: &Attribute) -> bool {
+    match attribute.attr_type {
+        AttributeType::Bool => true,
+        AttributeType::UInt8 => attribute.id.starts_with("Is") || attribute.id.starts_with("Can"),
+        _ => false,
+    }
+}
+
+// ============================================================================
+// Building Items
+// ============================================================================
+
+impl TemplateDescription {
+    /// The template this describes, for root template UUID `template_id`
+    pub fn template(&self, template_id: &str) -> Result<ItemTemplate, AppError> {
+        let template_id = template_id.trim();
+        if uuid::Uuid::parse_str(template_id).is_err() {
+            return Err(AppError::validation(format!(
+                "A described template needs its root template UUID, got {}", template_id
+            )));
+        }
+        let non_empty = |text: &Option<String>| text.as_deref().map(str::trim).filter(|t| !t.is_empty()).map(str::to_string);
+
+        let mut flags: Vec<Attribute> = Vec::new();
+        for flag in self.flags.iter().map(|flag| flag.trim()).filter(|flag| !flag.is_empty()) {
+            if !flag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(AppError::validation(format!("Invalid flag name {:?}", flag)));
+            }
+            if !flags.iter().any(|f| f.id == flag) {
+                flags.push(Attribute::new(flag, AttributeType::Bool, AttributeValue::Bool(true))?);
+            }
+        }
+
+        let stats = non_empty(&self.stats);
+        Ok(ItemTemplate {
+            template_id: template_id.to_string(),
+            name: non_empty(&self.name).or_else(|| stats.clone()).unwrap_or_else(|| template_id.to_string()),
+            level_name: self.level_name.trim().to_string(),
+            stackable: self.stackable,
+            stats,
+            flags,
+        })
+    }
+}
+
+impl ItemTemplate {
+    /// Whether `id` is this template's UUID (alone or at the end of a
+    /// `Name_uuid` template name) or its stats id
+    pub fn matches(&self, id: &str) -> bool {
+        let template_id = self.template_id.to_lowercase();
+        let id_lower = id.to_lowercase();
+        template_id == id_lower
+            || template_id.ends_with(&format!("_{}", id_lower))
+            || self.stats.as_deref().is_some_and(|stats| stats.eq_ignore_ascii_case(id))
+    }
+
+    /// Check that a new item of this template can hold `amount`
+    pub fn check_amount(&self, amount: i32) -> Result<(), AppError> {
+        if amount < 1 {
+            return Err(AppError::validation("A new item must hold at least 1"));
+        }
+        if amount > 1 && !self.stackable {
+            return Err(AppError::validation(format!("{} does not stack", self.name)));
+        }
+        Ok(())
+    }
+
+    /// A minimal item node: the template's flags, name, level, template and
+    /// stats ids, an empty UUID (filled in when placed) and, for stacks, the amount
+    pub fn build(&self, amount: i32) -> Result<Subtree, AppError> {
+        let string = |id: &str, attr_type, value: &str| {
+            Attribute::new(id, attr_type, AttributeValue::String(value.to_string()))
+        };
+        let mut attributes = self.flags.clone();
+        attributes.push(string("ItemName", AttributeType::LSString, &self.name)?);
+        attributes.push(string("LevelName", AttributeType::LSString, &self.level_name)?);
+        attributes.push(string("UUID", AttributeType::FixedString, "")?);
+        attributes.push(string("TemplateID", AttributeType::FixedString, &self.template_id)?);
+        if let Some(stats) = &self.stats {
+            attributes.push(string("Stats", AttributeType::FixedString, stats)?);
+        }
+        if self.stackable {
+            attributes.push(Attribute::new("Amount", AttributeType::Int32, AttributeValue::Int32(amount))?);
+        }
+
+        Ok(Subtree { name: "Item".to_string(), key: None, attributes, children: Vec::new() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POTION_ID: &str = "d47006e9-8a51-453d-b500-b5fd1e3b2a8a";
+
+    fn description(stats: &str, flags: &[&str]) -> TemplateDescription {
+        TemplateDescription {
+            stats: Some(stats.to_string()),
+            name: None,
+            level_name: " WLD_Main_A ".to_string(),
+            stackable: true,
+            flags: flags.iter().map(|flag| flag.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn descriptions_need_a_template_uuid_and_plain_flags() {
+        let template = description("OBJ_Potion_Healing", &["IsTradable", " IsTradable ", ""])
+            .template(&format!(" {} ", POTION_ID))
+            .unwrap();
+        assert_eq!(template.template_id, POTION_ID);
+        assert_eq!(template.name, "OBJ_Potion_Healing");
+        assert_eq!(template.level_name, "WLD_Main_A");
+        assert_eq!(template.flags.iter().map(|f| f.id.as_str()).collect::<Vec<_>>(), vec!["IsTradable"]);
+
+        assert!(description("OBJ_Potion_Healing", &[]).template("OBJ_Potion_Healing").is_err());
+        assert!(description("OBJ_Potion_Healing", &["Is Tradable"]).template(POTION_ID).is_err());
+    }
+
+    #[test]
+    fn templates_match_by_uuid_template_name_or_stats() {
+        let mut template = description("OBJ_Potion_Healing", &[]).template(POTION_ID).unwrap();
+        assert!(template.matches(&POTION_ID.to_uppercase()));
+        assert!(template.matches("obj_potion_healing"));
+        assert!(!template.matches("d47006e9"));
+
+        template.template_id = format!("OBJ_Potion_Healing_{}", POTION_ID);
+        assert!(template.matches(POTION_ID));
+        assert!(!template.matches(&format!("x{}", POTION_ID)));
+    }
+
+    #[test]
+    fn gold_comes_from_the_save_when_it_has_some() {
+        assert_eq!(gold(&[]).template_id, GOLD_TEMPLATE_ID);
+        let mut in_save = gold(&[]);
+        in_save.template_id = "LOOT_Gold_A_1c3c9c74-34a1-4685-989e-410dc080be6f".to_string();
+        in_save.level_name = "WLD_Main_A".to_string();
+        let potion = description("OBJ_Potion_Healing", &[]).template(POTION_ID).unwrap();
+        assert_eq!(gold(&[potion.clone(), in_save.clone()]).level_name, "WLD_Main_A");
+        assert_eq!(find_template(&[potion], GOLD_TEMPLATE_ID).unwrap().name, GOLD_ITEM_NAME);
+    }
+}
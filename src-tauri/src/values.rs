@@ -0,0 +1,534 @@
+use std::fmt;
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
+use base64::Engine;
+use crate::document::{AttributeType, TranslatedString};
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// A value that does not fit the attribute type it is declared with
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueError {
+    /// Text that cannot be parsed as the declared type
+    Invalid { attr_type: AttributeType, text: String },
+    /// A number outside the range of the declared type
+    OutOfRange { attr_type: AttributeType, value: String },
+    /// Wrong number of vector or matrix components
+    ComponentCount { attr_type: AttributeType, expected: usize, found: usize },
+    /// A value of a different kind than the declared type (e.g. a string for an int32)
+    TypeMismatch { attr_type: AttributeType, found: &'static str },
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueError::Invalid { attr_type, text } => {
+                write!(f, "'{}' is not a valid {} value", text, attr_type.name())
+            }
+            ValueError::OutOfRange { attr_type, value } => {
+                write!(f, "{} is out of range for {}", value, attr_type.name())
+            }
+            ValueError::ComponentCount { attr_type, expected, found } => {
+                write!(f, "{} needs {} components, got {}", attr_type.name(), expected, found)
+            }
+            ValueError::TypeMismatch { attr_type, found } => {
+                write!(f, "{} value cannot be stored in a {} attribute", found, attr_type.name())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+impl From<ValueError> for String {
+    fn from(error: ValueError) -> Self {
+        error.to_string()
+    }
+}
+
+// ============================================================================
+// Attribute Values
+// ============================================================================
+
+/// A decoded attribute value. The variant is determined by the attribute's
+/// declared `AttributeType`; several types share a variant (e.g. all string
+/// types use `String`, fvec and matrix types use `Floats`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    None,
+    UInt8(u8),
+    Int8(i8),
+    Int16(i16),
+    UInt16(u16),
+    Int32(i32),
+    UInt32(u32),
+    /// int64 and old_int64
+    Int64(i64),
+    UInt64(u64),
+    Float(f32),
+    Double(f64),
+    Bool(bool),
+    /// ivec2/3/4 components
+    Ints(Vec<i32>),
+    /// fvec2/3/4 components, or matrix components in row-major order
+    Floats(Vec<f32>),
+    /// string, path, FixedString, LSString, WString and LSWString
+    String(String),
+    /// Binary GUID in LSF byte order
+    Guid([u8; 16]),
+    ScratchBuffer(Vec<u8>),
+    TranslatedString(TranslatedString),
+    TranslatedFSString(TranslatedString),
+}
+
+impl AttributeValue {
+    /// The zero/empty value of a type
+    pub fn default_for(attr_type: AttributeType) -> Self {
+        match attr_type {
+            AttributeType::None => AttributeValue::None,
+            AttributeType::UInt8 => AttributeValue::UInt8(0),
+            AttributeType::Int8 => AttributeValue::Int8(0),
+            AttributeType::Int16 => AttributeValue::Int16(0),
+            AttributeType::UInt16 => AttributeValue::UInt16(0),
+            AttributeType::Int32 => AttributeValue::Int32(0),
+            AttributeType::UInt32 => AttributeValue::UInt32(0),
+            AttributeType::Int64 | AttributeType::OldInt64 => AttributeValue::Int64(0),
+            AttributeType::UInt64 => AttributeValue::UInt64(0),
+            AttributeType::Float => AttributeValue::Float(0.0),
+            AttributeType::Double => AttributeValue::Double(0.0),
+            AttributeType::Bool => AttributeValue::Bool(false),
+            AttributeType::IVec2 | AttributeType::IVec3 | AttributeType::IVec4 => {
+                AttributeValue::Ints(vec![0; component_count(attr_type)])
+            }
+            AttributeType::Vec2 | AttributeType::Vec3 | AttributeType::Vec4
+            | AttributeType::Mat2 | AttributeType::Mat3 | AttributeType::Mat3x4
+            | AttributeType::Mat4x3 | AttributeType::Mat4 => {
+                AttributeValue::Floats(vec![0.0; component_count(attr_type)])
+            }
+            AttributeType::String | AttributeType::Path | AttributeType::FixedString
+            | AttributeType::LSString | AttributeType::WString | AttributeType::LSWString => {
+                AttributeValue::String(String::new())
+            }
+            AttributeType::Guid => AttributeValue::Guid([0; 16]),
+            AttributeType::ScratchBuffer => AttributeValue::ScratchBuffer(Vec::new()),
+            AttributeType::TranslatedString => AttributeValue::TranslatedString(TranslatedString::default()),
+            AttributeType::TranslatedFSString => AttributeValue::TranslatedFSString(TranslatedString::default()),
+        }
+    }
+
+    /// Parse the LSX `value="..."` text of a type. TranslatedStrings are
+    /// spread over several XML attributes; here `text` is taken as the handle.
+    pub fn parse(attr_type: AttributeType, text: &str) -> Result<Self, ValueError> {
+        let value = match attr_type {
+            AttributeType::None => AttributeValue::None,
+            AttributeType::UInt8 => AttributeValue::UInt8(parse_int(attr_type, text)?),
+            AttributeType::Int8 => AttributeValue::Int8(parse_int(attr_type, text)?),
+            AttributeType::Int16 => AttributeValue::Int16(parse_int(attr_type, text)?),
+            AttributeType::UInt16 => AttributeValue::UInt16(parse_int(attr_type, text)?),
+            AttributeType::Int32 => AttributeValue::Int32(parse_int(attr_type, text)?),
+            AttributeType::UInt32 => AttributeValue::UInt32(parse_int(attr_type, text)?),
+            AttributeType::Int64 | AttributeType::OldInt64 => AttributeValue::Int64(parse_int(attr_type, text)?),
+            AttributeType::UInt64 => AttributeValue::UInt64(parse_int(attr_type, text)?),
+            AttributeType::Float => AttributeValue::Float(parse_float(attr_type, text)?),
+            AttributeType::Double => AttributeValue::Double(parse_float(attr_type, text)?),
+            AttributeType::Bool => AttributeValue::Bool(match text.trim() {
+                t if t.eq_ignore_ascii_case("true") || t == "1" => true,
+                t if t.eq_ignore_ascii_case("false") || t == "0" => false,
+                _ => return Err(invalid(attr_type, text)),
+            }),
+            AttributeType::IVec2 | AttributeType::IVec3 | AttributeType::IVec4 => {
+                let parts = split_components(attr_type, text)?;
+                AttributeValue::Ints(parts.iter().map(|p| parse_int(attr_type, p)).collect::<Result<_, _>>()?)
+            }
+            AttributeType::Vec2 | AttributeType::Vec3 | AttributeType::Vec4
+            | AttributeType::Mat2 | AttributeType::Mat3 | AttributeType::Mat3x4
+            | AttributeType::Mat4x3 | AttributeType::Mat4 => {
+                let parts = split_components(attr_type, text)?;
+                AttributeValue::Floats(parts.iter().map(|p| parse_float(attr_type, p)).collect::<Result<_, _>>()?)
+            }
+            AttributeType::String | AttributeType::Path | AttributeType::FixedString
+            | AttributeType::LSString | AttributeType::WString | AttributeType::LSWString => {
+                AttributeValue::String(text.to_string())
+            }
+            AttributeType::Guid => AttributeValue::Guid(parse_guid(text)?),
+            AttributeType::ScratchBuffer => AttributeValue::ScratchBuffer(
+                base64::engine::general_purpose::STANDARD.decode(text.trim())
+                    .map_err(|_| invalid(attr_type, text))?,
+            ),
+            AttributeType::TranslatedString => AttributeValue::TranslatedString(TranslatedString {
+                handle: text.to_string(),
+                ..TranslatedString::default()
+            }),
+            AttributeType::TranslatedFSString => AttributeValue::TranslatedFSString(TranslatedString {
+                handle: text.to_string(),
+                ..TranslatedString::default()
+            }),
+        };
+        Ok(value)
+    }
+
+    /// Build an integer value of the given type, checking that it fits
+    pub fn from_int(attr_type: AttributeType, value: i64) -> Result<Self, ValueError> {
+        let out_of_range = || ValueError::OutOfRange { attr_type, value: value.to_string() };
+        let result = match attr_type {
+            AttributeType::UInt8 => AttributeValue::UInt8(value.try_into().map_err(|_| out_of_range())?),
+            AttributeType::Int8 => AttributeValue::Int8(value.try_into().map_err(|_| out_of_range())?),
+            AttributeType::Int16 => AttributeValue::Int16(value.try_into().map_err(|_| out_of_range())?),
+            AttributeType::UInt16 => AttributeValue::UInt16(value.try_into().map_err(|_| out_of_range())?),
+            AttributeType::Int32 => AttributeValue::Int32(value.try_into().map_err(|_| out_of_range())?),
+            AttributeType::UInt32 => AttributeValue::UInt32(value.try_into().map_err(|_| out_of_range())?),
+            AttributeType::Int64 | AttributeType::OldInt64 => AttributeValue::Int64(value),
+            AttributeType::UInt64 => AttributeValue::UInt64(value.try_into().map_err(|_| out_of_range())?),
+            _ => return Err(ValueError::TypeMismatch { attr_type, found: "integer" }),
+        };
+        Ok(result)
+    }
+
+    /// Integer value of any integer variant
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            AttributeValue::UInt8(v) => Some(v as i64),
+            AttributeValue::Int8(v) => Some(v as i64),
+            AttributeValue::Int16(v) => Some(v as i64),
+            AttributeValue::UInt16(v) => Some(v as i64),
+            AttributeValue::Int32(v) => Some(v as i64),
+            AttributeValue::UInt32(v) => Some(v as i64),
+            AttributeValue::Int64(v) => Some(v),
+            AttributeValue::UInt64(v) => i64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AttributeValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            AttributeValue::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn as_translated(&self) -> Option<&TranslatedString> {
+        match self {
+            AttributeValue::TranslatedString(t) | AttributeValue::TranslatedFSString(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Check that this value can be stored in an attribute of `attr_type`
+    pub fn check_type(&self, attr_type: AttributeType) -> Result<(), ValueError> {
+        let expected = AttributeValue::default_for(attr_type);
+        if std::mem::discriminant(self) != std::mem::discriminant(&expected) {
+            return Err(ValueError::TypeMismatch { attr_type, found: self.kind() });
+        }
+        let found = match self {
+            AttributeValue::Ints(v) => v.len(),
+            AttributeValue::Floats(v) => v.len(),
+            _ => return Ok(()),
+        };
+        let expected = component_count(attr_type);
+        if found != expected {
+            return Err(ValueError::ComponentCount { attr_type, expected, found });
+        }
+        Ok(())
+    }
+
+    /// Short description of the variant, used in error messages
+    fn kind(&self) -> &'static str {
+        match self {
+            AttributeValue::None => "empty",
+            AttributeValue::UInt8(_) | AttributeValue::Int8(_) | AttributeValue::Int16(_)
+            | AttributeValue::UInt16(_) | AttributeValue::Int32(_) | AttributeValue::UInt32(_)
+            | AttributeValue::Int64(_) | AttributeValue::UInt64(_) => "integer",
+            AttributeValue::Float(_) | AttributeValue::Double(_) => "floating point",
+            AttributeValue::Bool(_) => "bool",
+            AttributeValue::Ints(_) => "integer vector",
+            AttributeValue::Floats(_) => "float vector",
+            AttributeValue::String(_) => "string",
+            AttributeValue::Guid(_) => "guid",
+            AttributeValue::ScratchBuffer(_) => "binary",
+            AttributeValue::TranslatedString(_) | AttributeValue::TranslatedFSString(_) => "translated string",
+        }
+    }
+}
+
+/// Formats the LSX `value="..."` text (the handle for TranslatedStrings)
+impl fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeValue::None => Ok(()),
+            AttributeValue::UInt8(v) => write!(f, "{}", v),
+            AttributeValue::Int8(v) => write!(f, "{}", v),
+            AttributeValue::Int16(v) => write!(f, "{}", v),
+            AttributeValue::UInt16(v) => write!(f, "{}", v),
+            AttributeValue::Int32(v) => write!(f, "{}", v),
+            AttributeValue::UInt32(v) => write!(f, "{}", v),
+            AttributeValue::Int64(v) => write!(f, "{}", v),
+            AttributeValue::UInt64(v) => write!(f, "{}", v),
+            AttributeValue::Float(v) => write!(f, "{}", format_f32(*v)),
+            AttributeValue::Double(v) => write!(f, "{}", format_f64(*v)),
+            AttributeValue::Bool(v) => write!(f, "{}", if *v { "True" } else { "False" }),
+            AttributeValue::Ints(v) => write!(f, "{}", join(v)),
+            AttributeValue::Floats(v) => write!(f, "{}", v.iter().map(|&v| format_f32(v)).collect::<Vec<_>>().join(" ")),
+            AttributeValue::String(s) => write!(f, "{}", s),
+            AttributeValue::Guid(bytes) => write!(f, "{}", format_guid(bytes)),
+            AttributeValue::ScratchBuffer(bytes) => {
+                write!(f, "{}", base64::engine::general_purpose::STANDARD.encode(bytes))
+            }
+            AttributeValue::TranslatedString(t) | AttributeValue::TranslatedFSString(t) => {
+                write!(f, "{}", t.handle)
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Text Helpers
+// ============================================================================
+
+/// Number of components in vector and matrix types
+pub fn component_count(attr_type: AttributeType) -> usize {
+    match attr_type {
+        AttributeType::IVec2 | AttributeType::Vec2 => 2,
+        AttributeType::IVec3 | AttributeType::Vec3 => 3,
+        AttributeType::IVec4 | AttributeType::Vec4 | AttributeType::Mat2 => 4,
+        AttributeType::Mat3 => 9,
+        AttributeType::Mat3x4 | AttributeType::Mat4x3 => 12,
+        AttributeType::Mat4 => 16,
+        _ => 1,
+    }
+}
+
+fn invalid(attr_type: AttributeType, text: &str) -> ValueError {
+    ValueError::Invalid { attr_type, text: text.to_string() }
+}
+
+fn parse_int<T: FromStr<Err = ParseIntError>>(attr_type: AttributeType, text: &str) -> Result<T, ValueError> {
+    text.trim().parse::<T>().map_err(|e| match e.kind() {
+        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => ValueError::OutOfRange {
+            attr_type,
+            value: text.trim().to_string(),
+        },
+        _ => invalid(attr_type, text),
+    })
+}
+
+fn parse_float<T: FromStr>(attr_type: AttributeType, text: &str) -> Result<T, ValueError> {
+    text.trim().parse::<T>().map_err(|_| invalid(attr_type, text))
+}
+
+/// Split space-separated vector/matrix components, checking their count
+fn split_components(attr_type: AttributeType, text: &str) -> Result<Vec<String>, ValueError> {
+    let parts: Vec<String> = text.split_whitespace().map(|s| s.to_string()).collect();
+    let expected = component_count(attr_type);
+    if parts.len() != expected {
+        return Err(ValueError::ComponentCount { attr_type, expected, found: parts.len() });
+    }
+    Ok(parts)
+}
+
+fn join<T: fmt::Display>(values: &[T]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// Format a float32 as .NET (and so LSLib) does, e.g. 0.1 or 3.4028235E+38
+fn format_f32(value: f32) -> String {
+    format_float(value.to_string(), format!("{:e}", value), 9)
+}
+
+/// Format a float64 as .NET does, e.g. 3.14159 or 1E+100
+fn format_f64(value: f64) -> String {
+    format_float(value.to_string(), format!("{:e}", value), 15)
+}
+
+/// .NET's default float text: the shortest digits that read back the same,
+/// switching to exponent notation when the decimal point would sit more
+/// than `precision` (or the digit count) places right of the first digit,
+/// or more than 4 left of it
+fn format_float(plain: String, scientific: String, precision: usize) -> String {
+    if let Some(sign) = plain.strip_suffix("inf") {
+        return format!("{}Infinity", sign);
+    }
+    let Some((mantissa, exponent)) = scientific.split_once('e') else {
+        return plain;
+    };
+    let Ok(exponent) = exponent.parse::<i32>() else {
+        return plain;
+    };
+
+    let digits = mantissa.chars().filter(char::is_ascii_digit).count();
+    let scale = exponent + 1;
+    if scale > precision.max(digits) as i32 || scale < -3 {
+        format!("{}E{}{:02}", mantissa, if exponent < 0 { '-' } else { '+' }, exponent.abs())
+    } else {
+        plain
+    }
+}
+
+/// Format a binary GUID the way LSLib writes BG3 LSX files (`bswap_guids`):
+/// .NET field order, with the bytes of the last 8 swapped pairwise
+pub fn format_guid(bytes: &[u8; 16]) -> String {
+    let data1 = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let data2 = u16::from_le_bytes([bytes[4], bytes[5]]);
+    let data3 = u16::from_le_bytes([bytes[6], bytes[7]]);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        data1, data2, data3,
+        bytes[9], bytes[8],
+        bytes[11], bytes[10], bytes[13], bytes[12], bytes[15], bytes[14]
+    )
+}
+
+/// Parse an LSX (`bswap_guids`) GUID string back into its binary layout
+pub fn parse_guid(text: &str) -> Result<[u8; 16], ValueError> {
+    let invalid = || invalid(AttributeType::Guid, text);
+    let hex: String = text.trim().trim_matches(|c| c == '{' || c == '}').split('-').collect();
+    if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let mut raw = [0u8; 16];
+    for (i, byte) in raw.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+
+    // Inverse of format_guid: little-endian fields, then pairwise-swapped tail
+    Ok([
+        raw[3], raw[2], raw[1], raw[0],
+        raw[5], raw[4],
+        raw[7], raw[6],
+        raw[9], raw[8], raw[11], raw[10], raw[13], raw[12], raw[15], raw[14],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse and format back, checking the text survives unchanged
+    fn round_trip(attr_type: AttributeType, text: &str) -> AttributeValue {
+        let value = AttributeValue::parse(attr_type, text).unwrap();
+        assert_eq!(value.to_string(), text, "{:?}", attr_type);
+        value.check_type(attr_type).unwrap();
+        value
+    }
+
+    #[test]
+    fn integers_round_trip_within_range() {
+        assert_eq!(round_trip(AttributeType::UInt8, "255"), AttributeValue::UInt8(255));
+        assert_eq!(round_trip(AttributeType::Int8, "-128"), AttributeValue::Int8(-128));
+        assert_eq!(round_trip(AttributeType::Int16, "-300"), AttributeValue::Int16(-300));
+        assert_eq!(round_trip(AttributeType::UInt16, "65535"), AttributeValue::UInt16(65535));
+        assert_eq!(round_trip(AttributeType::Int32, "-2147483648"), AttributeValue::Int32(i32::MIN));
+        assert_eq!(round_trip(AttributeType::UInt32, "4294967295"), AttributeValue::UInt32(u32::MAX));
+        assert_eq!(round_trip(AttributeType::Int64, "-9"), AttributeValue::Int64(-9));
+        assert_eq!(round_trip(AttributeType::OldInt64, "9"), AttributeValue::Int64(9));
+        assert_eq!(round_trip(AttributeType::UInt64, "18446744073709551615"), AttributeValue::UInt64(u64::MAX));
+
+        assert!(matches!(AttributeValue::parse(AttributeType::UInt8, "256"), Err(ValueError::OutOfRange { .. })));
+        assert!(matches!(AttributeValue::parse(AttributeType::Int32, "12a"), Err(ValueError::Invalid { .. })));
+        assert_eq!(AttributeValue::from_int(AttributeType::UInt16, 7), Ok(AttributeValue::UInt16(7)));
+        assert!(matches!(AttributeValue::from_int(AttributeType::UInt16, -1), Err(ValueError::OutOfRange { .. })));
+        assert!(matches!(AttributeValue::from_int(AttributeType::Float, 1), Err(ValueError::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn floats_format_like_dotnet() {
+        round_trip(AttributeType::Float, "0.1");
+        round_trip(AttributeType::Float, "-175.23");
+        round_trip(AttributeType::Float, "0");
+        round_trip(AttributeType::Float, "-0");
+        round_trip(AttributeType::Float, "0.0001");
+        round_trip(AttributeType::Float, "123456790");
+        round_trip(AttributeType::Float, "3.4028235E+38");
+        round_trip(AttributeType::Float, "1E-05");
+        round_trip(AttributeType::Float, "1.5E+10");
+        round_trip(AttributeType::Float, "Infinity");
+        round_trip(AttributeType::Float, "-Infinity");
+        round_trip(AttributeType::Float, "NaN");
+        round_trip(AttributeType::Double, "3.14159");
+        round_trip(AttributeType::Double, "100000000000000");
+        round_trip(AttributeType::Double, "1E+15");
+        round_trip(AttributeType::Double, "1.7976931348623157E+308");
+        round_trip(AttributeType::Double, "5E-324");
+
+        assert_eq!(AttributeValue::parse(AttributeType::Float, "3.4028235e38").unwrap().to_string(), "3.4028235E+38");
+        assert_eq!(AttributeValue::Float(f32::MAX).to_string(), "3.4028235E+38");
+        assert_eq!(AttributeValue::Double(0.00001).to_string(), "1E-05");
+        assert!(AttributeValue::parse(AttributeType::Float, "1,5").is_err());
+    }
+
+    #[test]
+    fn vectors_check_their_component_count() {
+        assert_eq!(round_trip(AttributeType::IVec3, "1 -2 3"), AttributeValue::Ints(vec![1, -2, 3]));
+        round_trip(AttributeType::Vec2, "0.5 1E-05");
+        round_trip(AttributeType::Vec3, "-175.23 24.91 542.43");
+        round_trip(AttributeType::Vec4, "0 0 0 1");
+        round_trip(AttributeType::Mat3, "1 0 0 0 1 0 0 0 1");
+        round_trip(AttributeType::Mat4, "1 0 0 0 0 1 0 0 0 0 1 0 0 0 0 1");
+
+        assert_eq!(
+            AttributeValue::parse(AttributeType::Vec3, "1 2"),
+            Err(ValueError::ComponentCount { attr_type: AttributeType::Vec3, expected: 3, found: 2 })
+        );
+        assert!(AttributeValue::Floats(vec![0.0; 3]).check_type(AttributeType::Vec4).is_err());
+        assert!(AttributeValue::Ints(vec![0; 3]).check_type(AttributeType::Vec3).is_err());
+    }
+
+    #[test]
+    fn bools_strings_and_buffers() {
+        assert_eq!(round_trip(AttributeType::Bool, "True"), AttributeValue::Bool(true));
+        assert_eq!(round_trip(AttributeType::Bool, "False"), AttributeValue::Bool(false));
+        assert_eq!(AttributeValue::parse(AttributeType::Bool, "1"), Ok(AttributeValue::Bool(true)));
+        assert!(AttributeValue::parse(AttributeType::Bool, "yes").is_err());
+
+        for attr_type in [
+            AttributeType::String, AttributeType::Path, AttributeType::FixedString,
+            AttributeType::LSString, AttributeType::WString, AttributeType::LSWString,
+        ] {
+            assert_eq!(round_trip(attr_type, " héllo "), AttributeValue::String(" héllo ".to_string()));
+        }
+        assert_eq!(round_trip(AttributeType::ScratchBuffer, "AQID"), AttributeValue::ScratchBuffer(vec![1, 2, 3]));
+        assert!(AttributeValue::parse(AttributeType::ScratchBuffer, "not base64!").is_err());
+
+        let translated = round_trip(AttributeType::TranslatedString, "h123");
+        assert_eq!(translated.as_translated().map(|t| t.handle.as_str()), Some("h123"));
+        round_trip(AttributeType::TranslatedFSString, "h456");
+        assert_eq!(round_trip(AttributeType::None, ""), AttributeValue::None);
+    }
+
+    #[test]
+    fn guids_use_lslib_byte_order() {
+        let text = "3ed74f06-3c60-42dc-83f6-f034cb47c679";
+        let bytes = parse_guid(text).unwrap();
+        assert_eq!(bytes, [
+            0x06, 0x4f, 0xd7, 0x3e, 0x60, 0x3c, 0xdc, 0x42,
+            0xf6, 0x83, 0x34, 0xf0, 0x47, 0xcb, 0x79, 0xc6,
+        ]);
+        assert_eq!(format_guid(&bytes), text);
+        assert_eq!(round_trip(AttributeType::Guid, text), AttributeValue::Guid(bytes));
+        assert_eq!(parse_guid("{3ED74F06-3C60-42DC-83F6-F034CB47C679}"), Ok(bytes));
+        assert!(parse_guid("3ed74f06-3c60-42dc-83f6").is_err());
+        assert!(parse_guid("3ed74f06-3c60-42dc-83f6-f034cb47c67g").is_err());
+    }
+
+    #[test]
+    fn defaults_match_their_type() {
+        for attr_type in [
+            AttributeType::UInt8, AttributeType::Int64, AttributeType::Double, AttributeType::Bool,
+            AttributeType::IVec4, AttributeType::Mat4x3, AttributeType::LSString, AttributeType::Guid,
+            AttributeType::ScratchBuffer, AttributeType::TranslatedFSString,
+        ] {
+            AttributeValue::default_for(attr_type).check_type(attr_type).unwrap();
+        }
+        assert!(AttributeValue::Int32(1).check_type(AttributeType::UInt32).is_err());
+    }
+}
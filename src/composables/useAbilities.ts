@@ -0,0 +1,86 @@
+import { ref } from "vue";
+import { useInvokeCommand, errorMessage } from "./useApi";
+import { useSaveExtraction } from "./useSaveExtraction";
+import { useGoldEditor, type InventoryOwner } from "./useGoldEditor";
+
+export type Ability = "Strength" | "Dexterity" | "Constitution" | "Intelligence" | "Wisdom" | "Charisma";
+
+export interface AbilityScore {
+  ability: Ability;
+  base: number | null;
+  current: number | null;
+}
+
+export interface CharacterAbilities {
+  character: InventoryOwner;
+  scores: AbilityScore[];
+}
+
+/** Bounds the game enforces on ability scores */
+export const MIN_SCORE = 1;
+export const MAX_SCORE = 30;
+
+// Shared state (singleton pattern), keyed by character owner key
+const abilities = ref<Record<string, CharacterAbilities>>({});
+const isLoading = ref(false);
+const abilityStatus = ref("");
+
+export function useAbilities() {
+  const { session } = useSaveExtraction();
+  // Ability edits are saved with the gold changes
+  const { hasUnsavedChanges } = useGoldEditor();
+
+  /**
+   * Load a character's ability scores
+   */
+  async function loadAbilities(character: string) {
+    if (!session.value) return;
+    isLoading.value = true;
+    abilityStatus.value = "";
+    try {
+      const result = await useInvokeCommand<CharacterAbilities>("get_ability_scores",
+        { sessionId: session.value.id, character });
+      if (result) abilities.value[character] = result;
+    } catch (e) {
+      abilityStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Set a character's base score; current scores and modifiers follow it
+   */
+  async function setAbilityScore(character: string, ability: Ability, score: number) {
+    if (!session.value) return;
+    isLoading.value = true;
+    abilityStatus.value = "";
+    try {
+      const result = await useInvokeCommand<CharacterAbilities>("set_ability_score",
+        { sessionId: session.value.id, edit: { character, ability, score } });
+      if (result) abilities.value[character] = result;
+      hasUnsavedChanges.value = true;
+    } catch (e) {
+      abilityStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Reset ability state
+   */
+  function reset() {
+    abilities.value = {};
+    abilityStatus.value = "";
+  }
+
+  return {
+    abilities,
+    isLoading,
+    abilityStatus,
+    loadAbilities,
+    setAbilityScore,
+    reset
+  };
+}
@@ -1,12 +1,55 @@
 import { invoke } from "@tauri-apps/api/core";
 
 /**
- * Generic error handler that logs and returns user-friendly message
+ * Structured error returned by backend commands
  */
-function handleError(context: string, error: any): string {
-  const message = typeof error === 'string' ? error : error?.message || 'Unknown error';
+export interface AppError {
+  kind: "toolMissing" | "toolFailed" | "fileNotFound" | "parse" | "unsupportedVersion"
+    | "validation" | "notFound" | "io";
+  message: string;
+  [field: string]: unknown;
+}
+
+/**
+ * Error thrown by useInvokeCommand, keeping the backend error details
+ */
+export class CommandError extends Error {
+  readonly kind: AppError["kind"] | "unknown";
+  readonly details: AppError | null;
+
+  constructor(context: string, error: unknown) {
+    const details = isAppError(error) ? error : null;
+    const message = details?.message
+      ?? (typeof error === 'string' ? error : (error as any)?.message || 'Unknown error');
+    super(`❌ ${context}: ${message}`);
+    this.name = "CommandError";
+    this.kind = details?.kind ?? "unknown";
+    this.details = details;
+  }
+}
+
+function isAppError(error: unknown): error is AppError {
+  return typeof error === 'object' && error !== null
+    && typeof (error as AppError).kind === 'string'
+    && typeof (error as AppError).message === 'string';
+}
+
+/**
+ * Generic error handler that logs and wraps the error for display
+ */
+function handleError(context: string, error: unknown): CommandError {
   console.error(`${context}:`, error);
-  return `❌ ${context}: ${message}`;
+  return new CommandError(context, error);
+}
+
+/**
+ * User-friendly message for anything caught from a command call
+ */
+export function errorMessage(error: unknown): string {
+  if (error instanceof Error) {
+    return error.message;
+  }
+  return typeof error === 'string' ? error : 'Unknown error';
 }
 
 /**
@@ -14,6 +57,7 @@ function handleError(context: string, error: any): string {
  * @param command - The Tauri command name
  * @param args - Command arguments (optional)
  * @returns The command result or null on error
+ * @throws CommandError with the backend error kind and details
  */
 export async function useInvokeCommand<T>(command: string, args: any = {}): Promise<T | null> {
   try {
@@ -0,0 +1,87 @@
+import { ref } from "vue";
+import { useInvokeCommand, errorMessage } from "./useApi";
+import { useSaveExtraction } from "./useSaveExtraction";
+import { useGoldEditor, type InventoryOwner } from "./useGoldEditor";
+
+export interface CharacterExperience {
+  character: InventoryOwner;
+  level: number | null;
+  experience: number | null;
+  available_level: number | null;
+  next_level_experience: number | null;
+  save_info_keys: string[];
+}
+
+export type ExperienceTarget =
+  | { to: "experience"; experience: number }
+  | { to: "level"; level: number };
+
+/** Highest level the game allows */
+export const MAX_LEVEL = 12;
+
+// Shared state (singleton pattern), keyed by character owner key
+const experience = ref<Record<string, CharacterExperience>>({});
+const isLoading = ref(false);
+const experienceStatus = ref("");
+
+export function useExperience() {
+  const { session, readSaveInfo } = useSaveExtraction();
+  // Experience edits are saved with the gold changes
+  const { hasUnsavedChanges } = useGoldEditor();
+
+  /**
+   * Load a character's experience
+   */
+  async function loadExperience(character: string) {
+    if (!session.value) return;
+    isLoading.value = true;
+    experienceStatus.value = "";
+    try {
+      const result = await useInvokeCommand<CharacterExperience>("get_experience",
+        { sessionId: session.value.id, character });
+      if (result) experience.value[character] = result;
+    } catch (e) {
+      experienceStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Set a character's total experience, or exactly the experience of a level;
+   * SaveInfo.json is updated too, so it is reloaded
+   */
+  async function setExperience(character: string, target: ExperienceTarget) {
+    if (!session.value) return;
+    isLoading.value = true;
+    experienceStatus.value = "";
+    try {
+      const result = await useInvokeCommand<CharacterExperience>("set_experience",
+        { sessionId: session.value.id, edit: { character, target } });
+      if (result) experience.value[character] = result;
+      hasUnsavedChanges.value = true;
+      await readSaveInfo();
+    } catch (e) {
+      experienceStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Reset experience state
+   */
+  function reset() {
+    experience.value = {};
+    experienceStatus.value = "";
+  }
+
+  return {
+    experience,
+    isLoading,
+    experienceStatus,
+    loadExperience,
+    setExperience,
+    reset
+  };
+}
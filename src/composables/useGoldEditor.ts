@@ -1,91 +1,135 @@
 import { ref, computed } from "vue";
-import { useInvokeCommand } from "./useApi";
+import { useInvokeCommand, errorMessage } from "./useApi";
+import { useSaveExtraction } from "./useSaveExtraction";
 
 export interface GoldItemDisplay {
   name: string;
   amount: number;
+  resource: string;
+  node: number;
 }
 
-export interface SaveState {
+export interface InventoryOwner {
+  kind: "character" | "campChest" | "container" | "unknown";
+  uuid: string | null;
+  name: string;
+  origin: string | null;
+}
+
+export interface OwnerGold {
+  owner: InventoryOwner;
   total_gold: number;
   items: GoldItemDisplay[];
+  item_count: number;
+  in_party: boolean;
+}
+
+export interface SaveState {
+  total_gold: number;
+  owners: OwnerGold[];
+}
+
+export interface SavedSession {
+  backup_path: string;
+  output_path: string;
+}
+
+export type GoldEdit =
+  | { mode: "owner"; owner: string; amount: number }
+  | { mode: "stack"; resource: string; node: number; amount: number }
+  | { mode: "evenSplit"; total: number | null };
+
+/**
+ * Key identifying an owner across resources (matches InventoryOwner::key)
+ */
+export function ownerKey(owner: InventoryOwner): string {
+  return owner.uuid ?? owner.name;
 }
 
 // Shared state (singleton pattern)
 const goldState = ref<SaveState | null>(null);
-const isEditing = ref(false);
-const editedGold = ref(0);
+const hasUnsavedChanges = ref(false);
 const saveStatus = ref("");
 const isLoading = ref(false);
 
 export function useGoldEditor() {
+  const { session } = useSaveExtraction();
 
   const isGoldLoaded = computed(() => goldState.value !== null);
-  const hasChangedGold = computed(() => 
-    isGoldLoaded.value && editedGold.value !== goldState.value!.total_gold
+  const partySize = computed(() =>
+    goldState.value?.owners.filter(group => group.in_party).length ?? 0
   );
 
   /**
    * Load gold information from the extracted save
    */
   async function loadGoldInfo() {
+    if (!session.value) return;
     try {
-      goldState.value = await useInvokeCommand<SaveState>("get_gold_count") || null;
-      if (goldState.value) {
-        editedGold.value = goldState.value.total_gold;
-      }
+      goldState.value = await useInvokeCommand<SaveState>("get_gold_count", 
+        { sessionId: session.value.id }) || null;
     } catch (e) {
       console.error("Failed to load gold info", e);
     }
   }
 
   /**
-   * Enable editing mode for gold amount
+   * Apply a gold edit in the session; the returned totals come from the edited tree
    */
-  function enableEditing() {
-    isEditing.value = true;
-    editedGold.value = goldState.value?.total_gold || 0;
+  async function applyGoldEdit(edit: GoldEdit) {
+    if (!session.value) return;
+    
+    isLoading.value = true;
+    try {
+      goldState.value = await useInvokeCommand<SaveState>("set_gold", 
+        { sessionId: session.value.id, edit }) || goldState.value;
+      hasUnsavedChanges.value = true;
+      saveStatus.value = "";
+    } catch (e) {
+      saveStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
   }
 
-  /**
-   * Cancel editing and discard changes
-   */
-  function cancelEditing() {
-    isEditing.value = false;
-    saveStatus.value = "";
+  function setOwnerGold(owner: InventoryOwner, amount: number) {
+    return applyGoldEdit({ mode: "owner", owner: ownerKey(owner), amount });
+  }
+
+  function setStackGold(item: GoldItemDisplay, amount: number) {
+    return applyGoldEdit({ mode: "stack", resource: item.resource, node: item.node, amount });
+  }
+
+  function splitEvenly(total: number | null = null) {
+    return applyGoldEdit({ mode: "evenSplit", total });
   }
 
   /**
-   * Save modified gold amount back to save file
+   * Write the edited session back to a new save file
    */
   async function saveGoldChanges(onSaveSuccess?: (newSavePath: string) => Promise<void>) {
-    if (!goldState.value) return;
+    if (!session.value) return;
     
     isLoading.value = true;
     saveStatus.value = "Saving changes...";
     
     try {
-      const result = await useInvokeCommand<string>("modify_and_save_gold", 
-        { newGold: editedGold.value }) || "";
-      
-      const newSaveMatch = result.match(/New save: (.+\.lsv)/);
+      const saved = await useInvokeCommand<SavedSession>("save_session", 
+        { sessionId: session.value.id });
       
-      if (newSaveMatch?.[1]) {
-        const modifiedSavePath = newSaveMatch[1];
+      if (saved) {
+        const result = `Save modified successfully!\nBackup: ${saved.backup_path}\nNew save: ${saved.output_path}`;
+        hasUnsavedChanges.value = false;
         saveStatus.value = "✅ Changes saved! Reloading modified save...";
         
         if (onSaveSuccess) {
-          await onSaveSuccess(modifiedSavePath);
+          await onSaveSuccess(saved.output_path);
         }
         
         saveStatus.value = "✅ " + result + "\n\n✓ Modified save loaded successfully!";
-      } else {
-        saveStatus.value = "✅ " + result;
       }
-      
-      isEditing.value = false;
     } catch (e) {
-      saveStatus.value = e as string;
+      saveStatus.value = errorMessage(e);
     } finally {
       isLoading.value = false;
     }
@@ -96,22 +140,21 @@ export function useGoldEditor() {
    */
   function reset() {
     goldState.value = null;
-    isEditing.value = false;
-    editedGold.value = 0;
+    hasUnsavedChanges.value = false;
     saveStatus.value = "";
   }
 
   return {
     goldState,
-    isEditing,
-    editedGold,
+    hasUnsavedChanges,
     saveStatus,
     isLoading,
     isGoldLoaded,
-    hasChangedGold,
+    partySize,
     loadGoldInfo,
-    enableEditing,
-    cancelEditing,
+    setOwnerGold,
+    setStackGold,
+    splitEvenly,
     saveGoldChanges,
     reset
   };
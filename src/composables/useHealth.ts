@@ -0,0 +1,98 @@
+import { ref } from "vue";
+import { useInvokeCommand, errorMessage } from "./useApi";
+import { useSaveExtraction } from "./useSaveExtraction";
+import { useGoldEditor, type InventoryOwner } from "./useGoldEditor";
+import { useParty } from "./useParty";
+
+export interface CharacterHealth {
+  character: InventoryOwner;
+  hp: number | null;
+  max_hp: number | null;
+  dead: boolean;
+  downed: boolean;
+  death_save_successes: number | null;
+  death_save_failures: number | null;
+}
+
+// Shared state (singleton pattern), keyed by character owner key
+const health = ref<Record<string, CharacterHealth>>({});
+const isLoading = ref(false);
+const healthStatus = ref("");
+
+export function useHealth() {
+  const { session } = useSaveExtraction();
+  // Health edits are saved with the gold changes
+  const { hasUnsavedChanges } = useGoldEditor();
+  const { loadParty } = useParty();
+
+  /**
+   * Load a character's HP and death state
+   */
+  async function loadHealth(character: string) {
+    if (!session.value) return;
+    isLoading.value = true;
+    healthStatus.value = "";
+    try {
+      const result = await useInvokeCommand<CharacterHealth>("get_health",
+        { sessionId: session.value.id, character });
+      if (result) health.value[character] = result;
+    } catch (e) {
+      healthStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Run a health edit that returns the character's updated health; the
+   * roster is reloaded since it shows HP
+   */
+  async function applyHealthEdit(character: string, command: string, args: Record<string, unknown>) {
+    if (!session.value) return;
+    isLoading.value = true;
+    healthStatus.value = "";
+    try {
+      const result = await useInvokeCommand<CharacterHealth>(command,
+        { sessionId: session.value.id, ...args });
+      if (result) health.value[character] = result;
+      hasUnsavedChanges.value = true;
+      await loadParty();
+    } catch (e) {
+      healthStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Set current and/or max HP
+   */
+  async function setHitPoints(character: string, hp: number | null, maxHp: number | null) {
+    await applyHealthEdit(character, "set_hit_points", { edit: { character, hp, max_hp: maxHp } });
+  }
+
+  /**
+   * Revive a downed or dead character, to `hp` or full HP
+   */
+  async function reviveCharacter(character: string, hp: number | null = null) {
+    await applyHealthEdit(character, "revive_character", { character, hp });
+  }
+
+  /**
+   * Reset health state
+   */
+  function reset() {
+    health.value = {};
+    healthStatus.value = "";
+  }
+
+  return {
+    health,
+    isLoading,
+    healthStatus,
+    loadHealth,
+    setHitPoints,
+    reviveCharacter,
+    reset
+  };
+}
@@ -0,0 +1,158 @@
+import { ref, computed } from "vue";
+import { useInvokeCommand, errorMessage } from "./useApi";
+import { useSaveExtraction } from "./useSaveExtraction";
+import { useGoldEditor, type InventoryOwner } from "./useGoldEditor";
+
+export interface ItemInfo {
+  resource: string;
+  node: number;
+  uuid: string | null;
+  template: string | null;
+  stats: string | null;
+  name: string;
+  amount: number;
+  slot: number | null;
+  equipped: boolean;
+  stackable: boolean;
+  contents: ItemInfo[];
+}
+
+export type ItemSelector =
+  | { by: "uuid"; uuid: string }
+  | { by: "template"; template: string };
+
+export interface ItemTemplate {
+  template_id: string;
+  stats: string | null;
+  name: string;
+  level_name: string;
+  stackable: boolean;
+}
+
+/** A root template the save holds no item of */
+export interface TemplateDescription {
+  stats: string | null;
+  name: string | null;
+  level_name: string;
+  stackable: boolean;
+  flags: string[];
+}
+
+export interface OwnerInventory {
+  owner: InventoryOwner;
+  items: ItemInfo[];
+}
+
+// Shared state (singleton pattern)
+const inventories = ref<OwnerInventory[] | null>(null);
+const templates = ref<ItemTemplate[]>([]);
+const isLoading = ref(false);
+const inventoryStatus = ref("");
+
+export function useInventory() {
+  const { session } = useSaveExtraction();
+  // Item edits are saved with the gold changes
+  const { hasUnsavedChanges, loadGoldInfo } = useGoldEditor();
+
+  const isInventoryLoaded = computed(() => inventories.value !== null);
+
+  /**
+   * Load every owner's items from the extracted save
+   */
+  async function loadInventory() {
+    if (!session.value) return;
+    isLoading.value = true;
+    try {
+      inventories.value = await useInvokeCommand<OwnerInventory[]>("list_inventory",
+        { sessionId: session.value.id }) || null;
+      templates.value = await useInvokeCommand<ItemTemplate[]>("list_item_templates",
+        { sessionId: session.value.id }) || [];
+    } catch (e) {
+      console.error("Failed to load inventory", e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Run an item edit that returns the updated inventories; gold is reloaded
+   * since gold stacks can be edited here too
+   */
+  async function applyItemEdit(command: string, args: Record<string, unknown>) {
+    if (!session.value) return;
+    isLoading.value = true;
+    inventoryStatus.value = "";
+    try {
+      inventories.value = await useInvokeCommand<OwnerInventory[]>(command,
+        { sessionId: session.value.id, ...args }) || inventories.value;
+      hasUnsavedChanges.value = true;
+      await loadGoldInfo();
+    } catch (e) {
+      inventoryStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Set the stack size of the items matching `item`
+   */
+  async function setItemAmount(item: ItemSelector, amount: number) {
+    await applyItemEdit("set_item_amount", { edit: { item, amount } });
+  }
+
+  /**
+   * Copy an item (and its contents) into `targetOwner`'s inventory, or next
+   * to the original when no owner is given
+   */
+  async function duplicateItem(item: ItemInfo, targetOwner: string | null = null) {
+    await applyItemEdit("duplicate_item", { resource: item.resource, node: item.node, targetOwner });
+  }
+
+  /**
+   * Move an item (and its contents) into `targetOwner`'s inventory, e.g. the camp chest
+   */
+  async function moveItem(item: ItemInfo, targetOwner: string) {
+    await applyItemEdit("move_item", { resource: item.resource, node: item.node, targetOwner });
+  }
+
+  /**
+   * Add a new item by root template UUID or stats id to `targetOwner`'s
+   * inventory; a template the save holds no item of needs a description
+   */
+  async function addItem(targetOwner: string, template: string, amount: number,
+    description: TemplateDescription | null = null) {
+    await applyItemEdit("add_item", { targetOwner, template, amount, description });
+  }
+
+  /**
+   * Remove an item and its contents, with every reference to them
+   */
+  async function deleteItem(item: ItemInfo) {
+    await applyItemEdit("delete_item", { resource: item.resource, node: item.node });
+  }
+
+  /**
+   * Reset inventory state
+   */
+  function reset() {
+    inventories.value = null;
+    templates.value = [];
+    inventoryStatus.value = "";
+  }
+
+  return {
+    inventories,
+    templates,
+    isLoading,
+    isInventoryLoaded,
+    inventoryStatus,
+    loadInventory,
+    setItemAmount,
+    duplicateItem,
+    moveItem,
+    addItem,
+    deleteItem,
+    reset
+  };
+}
@@ -1,23 +1,56 @@
 import { ref } from "vue";
-import { useInvokeCommand } from "./useApi";
+import { open } from "@tauri-apps/plugin-dialog";
+import { useInvokeCommand, errorMessage } from "./useApi";
+
+export interface LslibStatus {
+  path: string;
+  launcher: "native" | "dotnet" | "wine";
+  version: string | null;
+}
 
 // Shared state (singleton pattern)
-const lslibStatus = ref("");
+const lslibStatus = ref<LslibStatus | null>(null);
+const lslibError = ref("");
 
 export function useLsLib() {
   /**
    * Check if LSLib tools are installed and accessible
+   * @param divinePath - Divine.exe / Divine.dll chosen by the user (optional)
+   */
+  async function checkLslib(divinePath?: string) {
+    try {
+      lslibStatus.value = await useInvokeCommand<LslibStatus>("check_lslib_status",
+        { divinePath: divinePath ?? null });
+      lslibError.value = "";
+    } catch (e) {
+      lslibStatus.value = null;
+      lslibError.value = errorMessage(e);
+    }
+  }
+
+  /**
+   * Let the user pick Divine.exe / Divine.dll when it is not found automatically
    */
-  async function checkLslib() {
+  async function browseDivine() {
     try {
-      lslibStatus.value = await useInvokeCommand<string>("check_lslib_status") || "";
+      const selected = await open({
+        multiple: false,
+        directory: false,
+        filters: [{ name: "Divine", extensions: ["exe", "dll"] }]
+      });
+      
+      if (selected) {
+        await checkLslib(selected as string);
+      }
     } catch (e) {
-      lslibStatus.value = e as string;
+      console.error("Failed to open file dialog", e);
     }
   }
 
   return {
     lslibStatus,
-    checkLslib
+    lslibError,
+    checkLslib,
+    browseDivine
   };
 }
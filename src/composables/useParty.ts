@@ -0,0 +1,61 @@
+import { ref, computed } from "vue";
+import { useInvokeCommand } from "./useApi";
+import { useSaveExtraction } from "./useSaveExtraction";
+import type { InventoryOwner } from "./useGoldEditor";
+
+export interface CharacterNode {
+  resource: string;
+  node: number;
+}
+
+export interface CharacterInfo {
+  character: InventoryOwner;
+  is_player: boolean;
+  level: number | null;
+  class: string | null;
+  hp: number | null;
+  max_hp: number | null;
+  location: "active" | "camp" | "unknown";
+  nodes: CharacterNode[];
+}
+
+// Shared state (singleton pattern)
+const party = ref<CharacterInfo[] | null>(null);
+const isLoading = ref(false);
+
+export function useParty() {
+  const { session } = useSaveExtraction();
+
+  const isPartyLoaded = computed(() => party.value !== null);
+
+  /**
+   * Load the player characters and companions from the extracted save
+   */
+  async function loadParty() {
+    if (!session.value) return;
+    isLoading.value = true;
+    try {
+      party.value = await useInvokeCommand<CharacterInfo[]>("list_party",
+        { sessionId: session.value.id }) || null;
+    } catch (e) {
+      console.error("Failed to load party", e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Reset party state
+   */
+  function reset() {
+    party.value = null;
+  }
+
+  return {
+    party,
+    isLoading,
+    isPartyLoaded,
+    loadParty,
+    reset
+  };
+}
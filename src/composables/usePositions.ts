@@ -0,0 +1,80 @@
+import { ref } from "vue";
+import { useInvokeCommand, errorMessage } from "./useApi";
+import { useSaveExtraction } from "./useSaveExtraction";
+import { useGoldEditor, type InventoryOwner } from "./useGoldEditor";
+
+export interface CharacterPosition {
+  character: InventoryOwner;
+  level: string | null;
+  position: number[] | null;
+  rotation: number[] | null;
+}
+
+export type TeleportTarget =
+  | { to: "coordinates"; position: number[]; rotation: number[] | null; level?: string | null }
+  | { to: "character"; character: string };
+
+// Shared state (singleton pattern)
+const positions = ref<CharacterPosition[] | null>(null);
+const isLoading = ref(false);
+const positionStatus = ref("");
+
+export function usePositions() {
+  const { session, readSaveInfo } = useSaveExtraction();
+  // Moves are saved with the gold changes
+  const { hasUnsavedChanges } = useGoldEditor();
+
+  /**
+   * Load the position of every party member
+   */
+  async function loadPositions() {
+    if (!session.value) return;
+    isLoading.value = true;
+    positionStatus.value = "";
+    try {
+      positions.value = await useInvokeCommand<CharacterPosition[]>("list_positions",
+        { sessionId: session.value.id }) || null;
+    } catch (e) {
+      positionStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Move characters to coordinates or to another character; SaveInfo.json
+   * position mirrors are updated too, so it is reloaded
+   */
+  async function teleport(characters: string[], target: TeleportTarget) {
+    if (!session.value) return;
+    isLoading.value = true;
+    positionStatus.value = "";
+    try {
+      positions.value = await useInvokeCommand<CharacterPosition[]>("teleport_characters",
+        { sessionId: session.value.id, edit: { characters, target } }) || positions.value;
+      hasUnsavedChanges.value = true;
+      await readSaveInfo();
+    } catch (e) {
+      positionStatus.value = errorMessage(e);
+    } finally {
+      isLoading.value = false;
+    }
+  }
+
+  /**
+   * Reset position state
+   */
+  function reset() {
+    positions.value = null;
+    positionStatus.value = "";
+  }
+
+  return {
+    positions,
+    isLoading,
+    positionStatus,
+    loadPositions,
+    teleport,
+    reset
+  };
+}
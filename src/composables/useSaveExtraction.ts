@@ -1,30 +1,57 @@
 import { ref } from "vue";
 import { useInvokeCommand } from "./useApi";
 
+export interface SessionInfo {
+  id: string;
+  source_path: string;
+  workspace: string;
+  current_level: string | null;
+  levels: string[];
+  dirty: boolean;
+}
+
 // Shared state (singleton pattern)
 const saveInfo = ref<any>(null);
+const session = ref<SessionInfo | null>(null);
 
 export function useSaveExtraction() {
   /**
-   * Extract and convert save file to editable format
+   * Extract a save into a new session, closing the previous one
    */
   async function extractSave(selectedSavePath: string): Promise<string> {
-    const result = await useInvokeCommand<string>("extract_save", 
+    await closeSession();
+    session.value = await useInvokeCommand<SessionInfo>("extract_save", 
       { savePath: selectedSavePath });
-    return result || "";
+    return session.value ? `Save extracted to ${session.value.workspace}` : "";
   }
 
   /**
    * Read save metadata (campaign info, difficulty, etc)
    */
   async function readSaveInfo() {
+    if (!session.value) return;
     try {
-      saveInfo.value = await useInvokeCommand("read_save_info");
+      saveInfo.value = await useInvokeCommand("read_save_info", 
+        { sessionId: session.value.id });
     } catch (e) {
       console.error("Failed to read save info", e);
     }
   }
 
+  /**
+   * Close the current session and delete its workspace
+   */
+  async function closeSession() {
+    if (!session.value) return;
+    const sessionId = session.value.id;
+    session.value = null;
+    try {
+      await useInvokeCommand("close_session", { sessionId });
+    } catch (e) {
+      console.error("Failed to close session", e);
+    }
+  }
+
   /**
    * Reset extraction state
    */
@@ -34,8 +61,10 @@ export function useSaveExtraction() {
 
   return {
     saveInfo,
+    session,
     extractSave,
     readSaveInfo,
+    closeSession,
     reset
   };
 }
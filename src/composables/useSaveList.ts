@@ -1,6 +1,6 @@
 import { ref, computed } from "vue";
 import { open } from "@tauri-apps/plugin-dialog";
-import { useInvokeCommand } from "./useApi";
+import { useInvokeCommand, errorMessage } from "./useApi";
 
 export interface SaveEntry {
   name: string;
@@ -54,7 +54,7 @@ export function useSaveList() {
         extractionStatus.value = "No saves found in folder";
       }
     } catch (e) {
-      extractionStatus.value = e as string;
+      extractionStatus.value = errorMessage(e);
     }
   }
 